@@ -5,16 +5,27 @@ use reqwest::Client;
 use sea_orm::{ConnectOptions, Database, DatabaseConnection};
 use tracing::log;
 use crate::config::config::Config;
+use crate::services::account::token_blacklist_service::TokenBlacklistCache;
+use crate::services::integrations::mailer_service::{InMemoryMailer, Mailer, SmtpMailer};
 use crate::services::integrations::meilisearch_service::MeilisearchService;
+use crate::services::integrations::oauth_service::OidcCache;
 use crate::services::integrations::r2_service::R2Client;
+use crate::services::integrations::redis_service::CacheClient;
 use crate::services::integrations::stripe_service::StripeClient;
+use crate::services::integrations::twilio_service::{InMemorySmsSender, SmsSender, TwilioSmsSender};
+use crate::utils::message_util::MessageUtil;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
     pub stripe_client: Arc<StripeClient>,
     pub meilisearch_client: Arc<meilisearch_sdk::client::Client>,
-    pub r2_client: Arc<R2Client>
+    pub r2_client: Arc<R2Client>,
+    pub token_blacklist_cache: Arc<TokenBlacklistCache>,
+    pub cache: Arc<CacheClient>,
+    pub mailer: Arc<dyn Mailer>,
+    pub sms_sender: Arc<dyn SmsSender>,
+    pub oidc_cache: Arc<OidcCache>,
 }
 
 impl AppState {
@@ -49,11 +60,30 @@ impl AppState {
             ).await?
         );
         
+        let mailer: Arc<dyn Mailer> = if config.smtp_host.is_empty() {
+            MessageUtil::info("SMTP_HOST is not set, outgoing mail will be kept in memory");
+            Arc::new(InMemoryMailer::new())
+        } else {
+            Arc::new(SmtpMailer::from_config()?)
+        };
+
+        let sms_sender: Arc<dyn SmsSender> = if config.twilio_account_sid.is_empty() {
+            MessageUtil::info("TWILIO_ACCOUNT_SID is not set, outgoing SMS will be kept in memory");
+            Arc::new(InMemorySmsSender::new())
+        } else {
+            Arc::new(TwilioSmsSender::from_config()?)
+        };
+
         Ok(Self {
             db,
             stripe_client,
             meilisearch_client,
             r2_client,
+            token_blacklist_cache: Arc::new(TokenBlacklistCache::new()),
+            cache: Arc::new(CacheClient::from_config()?),
+            mailer,
+            sms_sender,
+            oidc_cache: Arc::new(OidcCache::new()),
         })
     }
 }
\ No newline at end of file
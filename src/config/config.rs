@@ -1,4 +1,5 @@
 use std::env;
+use std::net::IpAddr;
 use std::sync::OnceLock;
 use dotenvy::dotenv;
 use crate::utils::message_util::MessageUtil;
@@ -10,7 +11,42 @@ pub struct Config {
     pub max_db_connections: u32,
     pub host: String,
     pub port: u16,
+    pub trusted_proxies: Vec<IpAddr>,
     pub stripe_key: String,
+    pub stripe_webhook_secret: String,
+    pub stripe_application_fee_bps: i64,
+    pub stripe_connect_refresh_url: String,
+    pub stripe_connect_return_url: String,
+    pub stock_hold_minutes: i64,
+    pub stock_hold_sweep_interval_secs: u64,
+    pub listing_lifetime_days: i64,
+    pub listing_expiry_interval_secs: u64,
+    pub session_lifetime_days: i64,
+    pub untrusted_location_policy: String,
+    pub app_base_url: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub mail_from: String,
+    pub twilio_account_sid: String,
+    pub twilio_auth_token: String,
+    pub twilio_from_number: String,
+    pub oauth_redirect_base_url: String,
+    pub google_client_id: String,
+    pub google_client_secret: String,
+    pub google_issuer: String,
+    pub discord_client_id: String,
+    pub discord_client_secret: String,
+    pub discord_issuer: String,
+    pub github_client_id: String,
+    pub github_client_secret: String,
+    pub github_issuer: String,
+    pub github_api_url: String,
+    pub redis_url: String,
+    pub job_worker_count: usize,
+    pub job_poll_interval_secs: u64,
+    pub job_max_attempts: i32,
     pub meilisearch_url: String,
     pub meilisearch_key: String,
     pub r2_account_id: String,
@@ -54,11 +90,147 @@ impl Config {
                     MessageUtil::error(&format!("PORT must be a valid number: {}", e));
                     ()
                 })?,
+            trusted_proxies: env::var("TRUSTED_PROXIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(|value| value.parse::<IpAddr>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("TRUSTED_PROXIES must be a comma-separated list of IP addresses: {}", e));
+                    ()
+                })?,
             stripe_key: env::var("STRIPE_KEY")
                 .map_err(|e| {
                     MessageUtil::error(&format!("STRIPE_KEY must be set: {}", e));
                     ()
                 })?,
+            stripe_webhook_secret: env::var("STRIPE_WEBHOOK_SECRET")
+                .map_err(|e| {
+                    MessageUtil::error(&format!("STRIPE_WEBHOOK_SECRET must be set: {}", e));
+                    ()
+                })?,
+            stripe_application_fee_bps: env::var("STRIPE_APPLICATION_FEE_BPS")
+                .unwrap_or_else(|_| "500".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("STRIPE_APPLICATION_FEE_BPS must be a valid number: {}", e));
+                    ()
+                })?,
+            stripe_connect_refresh_url: env::var("STRIPE_CONNECT_REFRESH_URL")
+                .unwrap_or_else(|_| "http://localhost:3000/seller/onboarding".to_string()),
+            stripe_connect_return_url: env::var("STRIPE_CONNECT_RETURN_URL")
+                .unwrap_or_else(|_| "http://localhost:3000/seller/onboarding/complete".to_string()),
+            stock_hold_minutes: env::var("STOCK_HOLD_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("STOCK_HOLD_MINUTES must be a valid number: {}", e));
+                    ()
+                })?,
+            stock_hold_sweep_interval_secs: env::var("STOCK_HOLD_SWEEP_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .ok_or_else(|| {
+                    MessageUtil::error("STOCK_HOLD_SWEEP_INTERVAL_SECS must be a number greater than 0");
+                    ()
+                })?,
+            listing_lifetime_days: env::var("LISTING_LIFETIME_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("LISTING_LIFETIME_DAYS must be a valid number: {}", e));
+                    ()
+                })?,
+            listing_expiry_interval_secs: env::var("LISTING_EXPIRY_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse::<u64>()
+                .ok()
+                .filter(|secs| *secs > 0)
+                .ok_or_else(|| {
+                    MessageUtil::error("LISTING_EXPIRY_INTERVAL_SECS must be a number greater than 0");
+                    ()
+                })?,
+            session_lifetime_days: env::var("SESSION_LIFETIME_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("SESSION_LIFETIME_DAYS must be a valid number: {}", e));
+                    ()
+                })?,
+            untrusted_location_policy: env::var("UNTRUSTED_LOCATION_POLICY")
+                .unwrap_or_else(|_| "allow".to_string()),
+            app_base_url: env::var("APP_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            smtp_host: env::var("SMTP_HOST")
+                .unwrap_or_default(),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("SMTP_PORT must be a valid number: {}", e));
+                    ()
+                })?,
+            smtp_username: env::var("SMTP_USERNAME")
+                .unwrap_or_default(),
+            smtp_password: env::var("SMTP_PASSWORD")
+                .unwrap_or_default(),
+            mail_from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "TCGEmporium <no-reply@localhost>".to_string()),
+            twilio_account_sid: env::var("TWILIO_ACCOUNT_SID")
+                .unwrap_or_default(),
+            twilio_auth_token: env::var("TWILIO_AUTH_TOKEN")
+                .unwrap_or_default(),
+            twilio_from_number: env::var("TWILIO_FROM_NUMBER")
+                .unwrap_or_default(),
+            oauth_redirect_base_url: env::var("OAUTH_REDIRECT_BASE_URL")
+                .unwrap_or_else(|_| "http://localhost:8080/api/v1/public/auth/oauth".to_string()),
+            google_client_id: env::var("GOOGLE_CLIENT_ID")
+                .unwrap_or_default(),
+            google_client_secret: env::var("GOOGLE_CLIENT_SECRET")
+                .unwrap_or_default(),
+            google_issuer: env::var("GOOGLE_ISSUER")
+                .unwrap_or_else(|_| "https://accounts.google.com".to_string()),
+            discord_client_id: env::var("DISCORD_CLIENT_ID")
+                .unwrap_or_default(),
+            discord_client_secret: env::var("DISCORD_CLIENT_SECRET")
+                .unwrap_or_default(),
+            discord_issuer: env::var("DISCORD_ISSUER")
+                .unwrap_or_else(|_| "https://discord.com".to_string()),
+            github_client_id: env::var("GITHUB_CLIENT_ID")
+                .unwrap_or_default(),
+            github_client_secret: env::var("GITHUB_CLIENT_SECRET")
+                .unwrap_or_default(),
+            github_issuer: env::var("GITHUB_ISSUER")
+                .unwrap_or_else(|_| "https://github.com".to_string()),
+            github_api_url: env::var("GITHUB_API_URL")
+                .unwrap_or_else(|_| "https://api.github.com".to_string()),
+            redis_url: env::var("REDIS_URL")
+                .unwrap_or_default(),
+            job_worker_count: env::var("JOB_WORKER_COUNT")
+                .unwrap_or_else(|_| "4".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("JOB_WORKER_COUNT must be a valid number: {}", e));
+                    ()
+                })?,
+            job_poll_interval_secs: env::var("JOB_POLL_INTERVAL_SECS")
+                .unwrap_or_else(|_| "2".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("JOB_POLL_INTERVAL_SECS must be a valid number: {}", e));
+                    ()
+                })?,
+            job_max_attempts: env::var("JOB_MAX_ATTEMPTS")
+                .unwrap_or_else(|_| "8".to_string())
+                .parse()
+                .map_err(|e| {
+                    MessageUtil::error(&format!("JOB_MAX_ATTEMPTS must be a valid number: {}", e));
+                    ()
+                })?,
             meilisearch_url: env::var("MEILISEARCH_URL")
                 .map_err(|e| {
                     MessageUtil::error(&format!("MEILISEARCH_URL must be set: {}", e));
@@ -0,0 +1,148 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::prelude::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum UserRoleType {
+    #[sea_orm(iden = "user_role_type")]
+    Enum,
+    Buyer,
+    Seller,
+    Moderator,
+    Admin,
+}
+
+#[derive(DeriveIden)]
+pub enum ProductCategory {
+    #[sea_orm(iden = "product_category")]
+    Enum,
+    Card,
+    Sealed,
+    Accessory,
+    Other,
+}
+
+#[derive(DeriveIden)]
+pub enum ListingStatus {
+    #[sea_orm(iden = "listing_status")]
+    Enum,
+    Active,
+    Sold,
+    Cancelled,
+    Expired,
+}
+
+#[derive(DeriveIden)]
+pub enum CardCondition {
+    #[sea_orm(iden = "card_condition")]
+    Enum,
+    Mint,
+    NearMint,
+    LightlyPlayed,
+    ModeratelyPlayed,
+    HeavilyPlayed,
+    Damaged,
+    New,
+    Used,
+    Sealed,
+}
+
+#[derive(DeriveIden)]
+pub enum OrderStatus {
+    #[sea_orm(iden = "order_status")]
+    Enum,
+    Pending,
+    Paid,
+    Shipped,
+    Delivered,
+    Cancelled,
+    Refunded,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_type(
+            Type::create()
+                .as_enum(UserRoleType::Enum)
+                .values([
+                    UserRoleType::Buyer,
+                    UserRoleType::Seller,
+                    UserRoleType::Moderator,
+                    UserRoleType::Admin,
+                ])
+                .to_owned(),
+        ).await?;
+
+        manager.create_type(
+            Type::create()
+                .as_enum(ProductCategory::Enum)
+                .values([
+                    ProductCategory::Card,
+                    ProductCategory::Sealed,
+                    ProductCategory::Accessory,
+                    ProductCategory::Other,
+                ])
+                .to_owned(),
+        ).await?;
+
+        manager.create_type(
+            Type::create()
+                .as_enum(ListingStatus::Enum)
+                .values([
+                    ListingStatus::Active,
+                    ListingStatus::Sold,
+                    ListingStatus::Cancelled,
+                    ListingStatus::Expired,
+                ])
+                .to_owned(),
+        ).await?;
+
+        manager.create_type(
+            Type::create()
+                .as_enum(CardCondition::Enum)
+                .values([
+                    CardCondition::Mint,
+                    CardCondition::NearMint,
+                    CardCondition::LightlyPlayed,
+                    CardCondition::ModeratelyPlayed,
+                    CardCondition::HeavilyPlayed,
+                    CardCondition::Damaged,
+                    CardCondition::New,
+                    CardCondition::Used,
+                    CardCondition::Sealed,
+                ])
+                .to_owned(),
+        ).await?;
+
+        manager.create_type(
+            Type::create()
+                .as_enum(OrderStatus::Enum)
+                .values([
+                    OrderStatus::Pending,
+                    OrderStatus::Paid,
+                    OrderStatus::Shipped,
+                    OrderStatus::Delivered,
+                    OrderStatus::Cancelled,
+                    OrderStatus::Refunded,
+                ])
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_type(
+            Type::drop()
+                .names([
+                    OrderStatus::Enum.into_iden(),
+                    CardCondition::Enum.into_iden(),
+                    ListingStatus::Enum.into_iden(),
+                    ProductCategory::Enum.into_iden(),
+                    UserRoleType::Enum.into_iden(),
+                ])
+                .to_owned(),
+        ).await
+    }
+}
@@ -0,0 +1,536 @@
+use sea_orm_migration::prelude::*;
+use super::m20250101_000001_create_enums::UserRoleType;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+pub enum Users {
+    Table,
+    Id,
+    Username,
+    PasswordHash,
+    Email,
+    EmailVerified,
+    EmailVerifiedAt,
+    AvatarUrl,
+    FirstName,
+    LastName,
+    PhoneNumber,
+    PhoneVerified,
+    PhoneVerifiedAt,
+    TotpEnabled,
+    TotpSecret,
+    SmsMfaEnabled,
+    AccountStatus,
+    LastLoginAt,
+    PasswordChangedAt,
+    LoginAttempts,
+    LockedUntil,
+    Timezone,
+    Language,
+    DateOfBirth,
+    #[sea_orm(iden = "address_line_1")]
+    AddressLine1,
+    #[sea_orm(iden = "address_line_2")]
+    AddressLine2,
+    City,
+    StateProvince,
+    PostalCode,
+    Country,
+    CountryCode,
+    AddressLatitude,
+    AddressLongitude,
+    AddressVerified,
+    AddressVerifiedAt,
+    AddressVerificationMethod,
+    CreatedAt,
+    UpdatedAt,
+    VerifiedSeller,
+    StripeAccountId,
+}
+
+#[derive(DeriveIden)]
+enum UserRoles {
+    Table,
+    Id,
+    UserId,
+    Role,
+    GrantedAt,
+    GrantedBy,
+    IsActive,
+}
+
+#[derive(DeriveIden)]
+enum MfaBackupCodes {
+    Table,
+    Id,
+    UserId,
+    CodeHash,
+    Label,
+    IsUsed,
+    UsedAt,
+    CreatedAt,
+    ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum KnownLoginLocations {
+    Table,
+    Id,
+    UserId,
+    IpAddress,
+    Country,
+    Region,
+    City,
+    Latitude,
+    Longitude,
+    Timezone,
+    Isp,
+    UserAgent,
+    DeviceFingerprint,
+    IsTrusted,
+    TrustLevel,
+    FirstSeenAt,
+    LastSeenAt,
+    LoginCount,
+    IsActive,
+    Notes,
+}
+
+#[derive(DeriveIden)]
+enum LoginHistory {
+    Table,
+    Id,
+    UserId,
+    IpAddress,
+    UserAgent,
+    DeviceFingerprint,
+    LoginMethod,
+    Success,
+    FailureReason,
+    LocationId,
+    SessionId,
+    IsSuspicious,
+    AttemptedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserSessions {
+    Table,
+    Id,
+    UserId,
+    SessionToken,
+    RefreshToken,
+    IpAddress,
+    UserAgent,
+    LocationId,
+    IsActive,
+    CreatedAt,
+    LastActivityAt,
+    ExpiresAt,
+}
+
+#[derive(DeriveIden)]
+enum AddressHistory {
+    Table,
+    Id,
+    UserId,
+    ChangeType,
+    OldValues,
+    NewValues,
+    ChangedBy,
+    ChangeReason,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TokenBlacklist {
+    Table,
+    Jti,
+    UserId,
+    TokenType,
+    BlacklistedAt,
+    ExpiresAt,
+    Reason,
+}
+
+#[derive(DeriveIden)]
+enum PhoneVerificationCodes {
+    Table,
+    Id,
+    UserId,
+    PhoneNumber,
+    Purpose,
+    CodeHash,
+    Attempts,
+    ExpiresAt,
+    ConsumedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum UserIdentities {
+    Table,
+    Id,
+    UserId,
+    Provider,
+    ProviderSubject,
+    Email,
+    DisplayName,
+    CreatedAt,
+    LastLoginAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(Users::Table)
+                .col(ColumnDef::new(Users::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Users::Username).string().unique_key())
+                .col(ColumnDef::new(Users::PasswordHash).string().not_null())
+                .col(ColumnDef::new(Users::Email).string().not_null().unique_key())
+                .col(ColumnDef::new(Users::EmailVerified).boolean().not_null().default(false))
+                .col(ColumnDef::new(Users::EmailVerifiedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Users::AvatarUrl).string())
+                .col(ColumnDef::new(Users::FirstName).string())
+                .col(ColumnDef::new(Users::LastName).string())
+                .col(ColumnDef::new(Users::PhoneNumber).string())
+                .col(ColumnDef::new(Users::PhoneVerified).boolean().not_null().default(false))
+                .col(ColumnDef::new(Users::PhoneVerifiedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Users::TotpEnabled).boolean().not_null().default(false))
+                .col(ColumnDef::new(Users::TotpSecret).string())
+                .col(ColumnDef::new(Users::SmsMfaEnabled).boolean().not_null().default(false))
+                .col(ColumnDef::new(Users::AccountStatus).string().not_null().default("active"))
+                .col(ColumnDef::new(Users::LastLoginAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Users::PasswordChangedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Users::LoginAttempts).big_integer().default(0))
+                .col(ColumnDef::new(Users::LockedUntil).timestamp_with_time_zone())
+                .col(ColumnDef::new(Users::Timezone).string())
+                .col(ColumnDef::new(Users::Language).string())
+                .col(ColumnDef::new(Users::DateOfBirth).date())
+                .col(ColumnDef::new(Users::AddressLine1).string())
+                .col(ColumnDef::new(Users::AddressLine2).string())
+                .col(ColumnDef::new(Users::City).string())
+                .col(ColumnDef::new(Users::StateProvince).string())
+                .col(ColumnDef::new(Users::PostalCode).string())
+                .col(ColumnDef::new(Users::Country).string())
+                .col(ColumnDef::new(Users::CountryCode).string())
+                .col(ColumnDef::new(Users::AddressLatitude).decimal_len(10, 7))
+                .col(ColumnDef::new(Users::AddressLongitude).decimal_len(10, 7))
+                .col(ColumnDef::new(Users::AddressVerified).boolean().not_null().default(false))
+                .col(ColumnDef::new(Users::AddressVerifiedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Users::AddressVerificationMethod).string())
+                .col(ColumnDef::new(Users::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Users::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Users::VerifiedSeller).boolean().not_null().default(false))
+                .col(ColumnDef::new(Users::StripeAccountId).string())
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(UserRoles::Table)
+                .col(ColumnDef::new(UserRoles::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(UserRoles::UserId).uuid().not_null())
+                .col(ColumnDef::new(UserRoles::Role).custom(UserRoleType::Enum).not_null())
+                .col(ColumnDef::new(UserRoles::GrantedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(UserRoles::GrantedBy).uuid())
+                .col(ColumnDef::new(UserRoles::IsActive).boolean().not_null().default(true))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(UserRoles::Table, UserRoles::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(UserRoles::Table, UserRoles::GrantedBy)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::SetNull),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_user_roles_user_id_role")
+                .table(UserRoles::Table)
+                .col(UserRoles::UserId)
+                .col(UserRoles::Role)
+                .unique()
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(MfaBackupCodes::Table)
+                .col(ColumnDef::new(MfaBackupCodes::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(MfaBackupCodes::UserId).uuid().not_null())
+                .col(ColumnDef::new(MfaBackupCodes::CodeHash).string().not_null())
+                .col(ColumnDef::new(MfaBackupCodes::Label).string())
+                .col(ColumnDef::new(MfaBackupCodes::IsUsed).boolean().not_null().default(false))
+                .col(ColumnDef::new(MfaBackupCodes::UsedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(MfaBackupCodes::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(MfaBackupCodes::ExpiresAt).timestamp_with_time_zone())
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(MfaBackupCodes::Table, MfaBackupCodes::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_mfa_backup_codes_user_id")
+                .table(MfaBackupCodes::Table)
+                .col(MfaBackupCodes::UserId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(KnownLoginLocations::Table)
+                .col(ColumnDef::new(KnownLoginLocations::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(KnownLoginLocations::UserId).uuid().not_null())
+                .col(ColumnDef::new(KnownLoginLocations::IpAddress).string().not_null())
+                .col(ColumnDef::new(KnownLoginLocations::Country).string())
+                .col(ColumnDef::new(KnownLoginLocations::Region).string())
+                .col(ColumnDef::new(KnownLoginLocations::City).string())
+                .col(ColumnDef::new(KnownLoginLocations::Latitude).decimal_len(10, 7))
+                .col(ColumnDef::new(KnownLoginLocations::Longitude).decimal_len(10, 7))
+                .col(ColumnDef::new(KnownLoginLocations::Timezone).string())
+                .col(ColumnDef::new(KnownLoginLocations::Isp).string())
+                .col(ColumnDef::new(KnownLoginLocations::UserAgent).string())
+                .col(ColumnDef::new(KnownLoginLocations::DeviceFingerprint).string())
+                .col(ColumnDef::new(KnownLoginLocations::IsTrusted).boolean().not_null().default(false))
+                .col(ColumnDef::new(KnownLoginLocations::TrustLevel).integer().not_null().default(0))
+                .col(ColumnDef::new(KnownLoginLocations::FirstSeenAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(KnownLoginLocations::LastSeenAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(KnownLoginLocations::LoginCount).integer().not_null().default(1))
+                .col(ColumnDef::new(KnownLoginLocations::IsActive).boolean().not_null().default(true))
+                .col(ColumnDef::new(KnownLoginLocations::Notes).text())
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(KnownLoginLocations::Table, KnownLoginLocations::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_known_login_locations_user_id_ip_address")
+                .table(KnownLoginLocations::Table)
+                .col(KnownLoginLocations::UserId)
+                .col(KnownLoginLocations::IpAddress)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(UserSessions::Table)
+                .col(ColumnDef::new(UserSessions::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(UserSessions::UserId).uuid().not_null())
+                .col(ColumnDef::new(UserSessions::SessionToken).string().not_null())
+                .col(ColumnDef::new(UserSessions::RefreshToken).string())
+                .col(ColumnDef::new(UserSessions::IpAddress).string())
+                .col(ColumnDef::new(UserSessions::UserAgent).string())
+                .col(ColumnDef::new(UserSessions::LocationId).uuid())
+                .col(ColumnDef::new(UserSessions::IsActive).boolean().not_null().default(true))
+                .col(ColumnDef::new(UserSessions::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(UserSessions::LastActivityAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(UserSessions::ExpiresAt).timestamp_with_time_zone().not_null())
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(UserSessions::Table, UserSessions::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(UserSessions::Table, UserSessions::LocationId)
+                        .to(KnownLoginLocations::Table, KnownLoginLocations::Id)
+                        .on_delete(ForeignKeyAction::SetNull),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_user_sessions_user_id")
+                .table(UserSessions::Table)
+                .col(UserSessions::UserId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(LoginHistory::Table)
+                .col(ColumnDef::new(LoginHistory::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(LoginHistory::UserId).uuid())
+                .col(ColumnDef::new(LoginHistory::IpAddress).string().not_null())
+                .col(ColumnDef::new(LoginHistory::UserAgent).string())
+                .col(ColumnDef::new(LoginHistory::DeviceFingerprint).string())
+                .col(ColumnDef::new(LoginHistory::LoginMethod).string().not_null().default("password"))
+                .col(ColumnDef::new(LoginHistory::Success).boolean().not_null())
+                .col(ColumnDef::new(LoginHistory::FailureReason).string())
+                .col(ColumnDef::new(LoginHistory::LocationId).uuid())
+                .col(ColumnDef::new(LoginHistory::SessionId).uuid())
+                .col(ColumnDef::new(LoginHistory::IsSuspicious).boolean().not_null().default(false))
+                .col(ColumnDef::new(LoginHistory::AttemptedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(LoginHistory::Table, LoginHistory::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(LoginHistory::Table, LoginHistory::LocationId)
+                        .to(KnownLoginLocations::Table, KnownLoginLocations::Id)
+                        .on_delete(ForeignKeyAction::SetNull),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_login_history_user_id_attempted_at")
+                .table(LoginHistory::Table)
+                .col(LoginHistory::UserId)
+                .col(LoginHistory::AttemptedAt)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(AddressHistory::Table)
+                .col(ColumnDef::new(AddressHistory::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(AddressHistory::UserId).uuid().not_null())
+                .col(ColumnDef::new(AddressHistory::ChangeType).string().not_null())
+                .col(ColumnDef::new(AddressHistory::OldValues).json_binary())
+                .col(ColumnDef::new(AddressHistory::NewValues).json_binary())
+                .col(ColumnDef::new(AddressHistory::ChangedBy).uuid())
+                .col(ColumnDef::new(AddressHistory::ChangeReason).string())
+                .col(ColumnDef::new(AddressHistory::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(AddressHistory::Table, AddressHistory::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(AddressHistory::Table, AddressHistory::ChangedBy)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::SetNull),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(TokenBlacklist::Table)
+                .col(ColumnDef::new(TokenBlacklist::Jti).string().not_null().primary_key())
+                .col(ColumnDef::new(TokenBlacklist::UserId).uuid().not_null())
+                .col(ColumnDef::new(TokenBlacklist::TokenType).string().not_null())
+                .col(ColumnDef::new(TokenBlacklist::BlacklistedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(TokenBlacklist::ExpiresAt).timestamp_with_time_zone().not_null())
+                .col(ColumnDef::new(TokenBlacklist::Reason).string())
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(TokenBlacklist::Table, TokenBlacklist::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_token_blacklist_expires_at")
+                .table(TokenBlacklist::Table)
+                .col(TokenBlacklist::ExpiresAt)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(PhoneVerificationCodes::Table)
+                .col(ColumnDef::new(PhoneVerificationCodes::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(PhoneVerificationCodes::UserId).uuid().not_null())
+                .col(ColumnDef::new(PhoneVerificationCodes::PhoneNumber).string().not_null())
+                .col(ColumnDef::new(PhoneVerificationCodes::Purpose).string().not_null())
+                .col(ColumnDef::new(PhoneVerificationCodes::CodeHash).string().not_null())
+                .col(ColumnDef::new(PhoneVerificationCodes::Attempts).integer().not_null().default(0))
+                .col(ColumnDef::new(PhoneVerificationCodes::ExpiresAt).timestamp_with_time_zone().not_null())
+                .col(ColumnDef::new(PhoneVerificationCodes::ConsumedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(PhoneVerificationCodes::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(PhoneVerificationCodes::Table, PhoneVerificationCodes::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_phone_verification_codes_user_id_purpose")
+                .table(PhoneVerificationCodes::Table)
+                .col(PhoneVerificationCodes::UserId)
+                .col(PhoneVerificationCodes::Purpose)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(UserIdentities::Table)
+                .col(ColumnDef::new(UserIdentities::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(UserIdentities::UserId).uuid().not_null())
+                .col(ColumnDef::new(UserIdentities::Provider).string().not_null())
+                .col(ColumnDef::new(UserIdentities::ProviderSubject).string().not_null())
+                .col(ColumnDef::new(UserIdentities::Email).string())
+                .col(ColumnDef::new(UserIdentities::DisplayName).string())
+                .col(ColumnDef::new(UserIdentities::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(UserIdentities::LastLoginAt).timestamp_with_time_zone())
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(UserIdentities::Table, UserIdentities::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_user_identities_provider_subject")
+                .table(UserIdentities::Table)
+                .col(UserIdentities::Provider)
+                .col(UserIdentities::ProviderSubject)
+                .unique()
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(UserIdentities::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(PhoneVerificationCodes::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(TokenBlacklist::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(AddressHistory::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(LoginHistory::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(UserSessions::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(KnownLoginLocations::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(MfaBackupCodes::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(UserRoles::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Users::Table).to_owned()).await
+    }
+}
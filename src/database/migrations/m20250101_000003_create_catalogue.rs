@@ -0,0 +1,206 @@
+use sea_orm_migration::prelude::*;
+use super::m20250101_000001_create_enums::ProductCategory;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Games {
+    Table,
+    Id,
+    Name,
+    Description,
+    Publisher,
+    ReleaseDate,
+    ImageUrl,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Sets {
+    Table,
+    Id,
+    GameName,
+    Name,
+    Description,
+    ReleaseDate,
+    ImageUrl,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum Products {
+    Table,
+    Id,
+    Name,
+    Description,
+    ImageUrl,
+    Game,
+    Set,
+    Category,
+    Subcategory,
+    Metadata,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ProductVariants {
+    Table,
+    Id,
+    ProductId,
+    Name,
+    SetNumber,
+    IsPrimary,
+    SortOrder,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum VariantImages {
+    Table,
+    Id,
+    VariantId,
+    ImageType,
+    ImageUrl,
+    Size,
+    CreatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(Games::Table)
+                .col(ColumnDef::new(Games::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Games::Name).string().not_null().unique_key())
+                .col(ColumnDef::new(Games::Description).text())
+                .col(ColumnDef::new(Games::Publisher).string())
+                .col(ColumnDef::new(Games::ReleaseDate).date())
+                .col(ColumnDef::new(Games::ImageUrl).string())
+                .col(ColumnDef::new(Games::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Games::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(Sets::Table)
+                .col(ColumnDef::new(Sets::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Sets::GameName).string().not_null())
+                .col(ColumnDef::new(Sets::Name).string().not_null())
+                .col(ColumnDef::new(Sets::Description).text())
+                .col(ColumnDef::new(Sets::ReleaseDate).date())
+                .col(ColumnDef::new(Sets::ImageUrl).string())
+                .col(ColumnDef::new(Sets::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Sets::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(Sets::Table, Sets::GameName)
+                        .to(Games::Table, Games::Name)
+                        .on_update(ForeignKeyAction::Cascade)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_sets_game_name_name")
+                .table(Sets::Table)
+                .col(Sets::GameName)
+                .col(Sets::Name)
+                .unique()
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(Products::Table)
+                .col(ColumnDef::new(Products::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Products::Name).string().not_null())
+                .col(ColumnDef::new(Products::Description).text())
+                .col(ColumnDef::new(Products::ImageUrl).string())
+                .col(ColumnDef::new(Products::Game).string().not_null())
+                .col(ColumnDef::new(Products::Set).string())
+                .col(ColumnDef::new(Products::Category).custom(ProductCategory::Enum).not_null())
+                .col(ColumnDef::new(Products::Subcategory).string())
+                .col(ColumnDef::new(Products::Metadata).json())
+                .col(ColumnDef::new(Products::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Products::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_products_game")
+                .table(Products::Table)
+                .col(Products::Game)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(ProductVariants::Table)
+                .col(ColumnDef::new(ProductVariants::Id).integer().not_null().auto_increment().primary_key())
+                .col(ColumnDef::new(ProductVariants::ProductId).uuid().not_null())
+                .col(ColumnDef::new(ProductVariants::Name).string().not_null())
+                .col(ColumnDef::new(ProductVariants::SetNumber).string())
+                .col(ColumnDef::new(ProductVariants::IsPrimary).boolean().not_null().default(false))
+                .col(ColumnDef::new(ProductVariants::SortOrder).integer().not_null().default(0))
+                .col(ColumnDef::new(ProductVariants::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(ProductVariants::Table, ProductVariants::ProductId)
+                        .to(Products::Table, Products::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_product_variants_product_id")
+                .table(ProductVariants::Table)
+                .col(ProductVariants::ProductId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(VariantImages::Table)
+                .col(ColumnDef::new(VariantImages::Id).integer().not_null().auto_increment().primary_key())
+                .col(ColumnDef::new(VariantImages::VariantId).integer().not_null())
+                .col(ColumnDef::new(VariantImages::ImageType).string().not_null())
+                .col(ColumnDef::new(VariantImages::ImageUrl).string().not_null())
+                .col(ColumnDef::new(VariantImages::Size).string())
+                .col(ColumnDef::new(VariantImages::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(VariantImages::Table, VariantImages::VariantId)
+                        .to(ProductVariants::Table, ProductVariants::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_variant_images_variant_id")
+                .table(VariantImages::Table)
+                .col(VariantImages::VariantId)
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(VariantImages::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(ProductVariants::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Products::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Sets::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Games::Table).to_owned()).await
+    }
+}
@@ -0,0 +1,345 @@
+use sea_orm_migration::prelude::*;
+use super::m20250101_000001_create_enums::{CardCondition, ListingStatus, OrderStatus};
+use super::m20250101_000002_create_accounts::Users;
+use super::m20250101_000003_create_catalogue::Products;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Listings {
+    Table,
+    Id,
+    ProductId,
+    SellerId,
+    Price,
+    Condition,
+    Quantity,
+    ReservedQuantity,
+    Status,
+    StripeProductId,
+    PreviousStripeProductId,
+    ImageUrl,
+    Description,
+    ExpiresAt,
+    CreatedAt,
+    UpdatedAt,
+    DeletedAt,
+}
+
+#[derive(DeriveIden)]
+enum CartItems {
+    Table,
+    Id,
+    UserId,
+    ListingId,
+    Quantity,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Orders {
+    Table,
+    Id,
+    BuyerId,
+    Status,
+    TotalAmount,
+    Currency,
+    StripePaymentIntentId,
+    CreatedAt,
+    UpdatedAt,
+    PaidAt,
+    ShippedAt,
+    DeliveredAt,
+    CancelledAt,
+}
+
+#[derive(DeriveIden)]
+enum OrderItems {
+    Table,
+    Id,
+    OrderId,
+    ListingId,
+    SellerId,
+    ProductId,
+    ProductName,
+    Condition,
+    UnitPrice,
+    Quantity,
+    StripeProductId,
+    ApplicationFeeAmount,
+    StripeTransferId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum StockHolds {
+    Table,
+    Id,
+    ListingId,
+    OrderId,
+    UserId,
+    Quantity,
+    ExpiresAt,
+    ReleasedAt,
+    CommittedAt,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum StripeEvents {
+    Table,
+    Id,
+    EventType,
+    Livemode,
+    CreatedAt,
+    ProcessedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(Listings::Table)
+                .col(ColumnDef::new(Listings::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Listings::ProductId).uuid().not_null())
+                .col(ColumnDef::new(Listings::SellerId).uuid().not_null())
+                .col(ColumnDef::new(Listings::Price).big_integer().not_null())
+                .col(ColumnDef::new(Listings::Condition).custom(CardCondition::Enum).not_null())
+                .col(ColumnDef::new(Listings::Quantity).big_integer().not_null())
+                .col(ColumnDef::new(Listings::ReservedQuantity).big_integer().not_null().default(0))
+                .col(ColumnDef::new(Listings::Status).custom(ListingStatus::Enum).not_null())
+                .col(ColumnDef::new(Listings::StripeProductId).string())
+                .col(ColumnDef::new(Listings::PreviousStripeProductId).string())
+                .col(ColumnDef::new(Listings::ImageUrl).string())
+                .col(ColumnDef::new(Listings::Description).text())
+                .col(ColumnDef::new(Listings::ExpiresAt).timestamp_with_time_zone().not_null())
+                .col(ColumnDef::new(Listings::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Listings::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Listings::DeletedAt).timestamp_with_time_zone())
+                .check(Expr::col(Listings::ReservedQuantity).between(0, Expr::col(Listings::Quantity)))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(Listings::Table, Listings::ProductId)
+                        .to(Products::Table, Products::Id),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(Listings::Table, Listings::SellerId)
+                        .to(Users::Table, Users::Id),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_listings_product_id")
+                .table(Listings::Table)
+                .col(Listings::ProductId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_listings_seller_id")
+                .table(Listings::Table)
+                .col(Listings::SellerId)
+                .to_owned(),
+        ).await?;
+
+        // Used by the expiry job to find active listings past their `expires_at`.
+        manager.create_index(
+            Index::create()
+                .name("idx_listings_status_expires_at")
+                .table(Listings::Table)
+                .col(Listings::Status)
+                .col(Listings::ExpiresAt)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(CartItems::Table)
+                .col(ColumnDef::new(CartItems::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(CartItems::UserId).uuid().not_null())
+                .col(ColumnDef::new(CartItems::ListingId).uuid().not_null())
+                .col(ColumnDef::new(CartItems::Quantity).big_integer().not_null())
+                .col(ColumnDef::new(CartItems::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(CartItems::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(CartItems::Table, CartItems::UserId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(CartItems::Table, CartItems::ListingId)
+                        .to(Listings::Table, Listings::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_cart_items_user_id_listing_id")
+                .table(CartItems::Table)
+                .col(CartItems::UserId)
+                .col(CartItems::ListingId)
+                .unique()
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(Orders::Table)
+                .col(ColumnDef::new(Orders::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Orders::BuyerId).uuid().not_null())
+                .col(ColumnDef::new(Orders::Status).custom(OrderStatus::Enum).not_null())
+                .col(ColumnDef::new(Orders::TotalAmount).big_integer().not_null())
+                .col(ColumnDef::new(Orders::Currency).string_len(3).not_null())
+                .col(ColumnDef::new(Orders::StripePaymentIntentId).string().unique_key())
+                .col(ColumnDef::new(Orders::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Orders::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Orders::PaidAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Orders::ShippedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Orders::DeliveredAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Orders::CancelledAt).timestamp_with_time_zone())
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(Orders::Table, Orders::BuyerId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Restrict),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_orders_buyer_id")
+                .table(Orders::Table)
+                .col(Orders::BuyerId)
+                .to_owned(),
+        ).await?;
+
+        // `product_id` has no foreign key: order history outlives deleted products.
+        manager.create_table(
+            Table::create()
+                .table(OrderItems::Table)
+                .col(ColumnDef::new(OrderItems::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(OrderItems::OrderId).uuid().not_null())
+                .col(ColumnDef::new(OrderItems::ListingId).uuid().not_null())
+                .col(ColumnDef::new(OrderItems::SellerId).uuid().not_null())
+                .col(ColumnDef::new(OrderItems::ProductId).uuid().not_null())
+                .col(ColumnDef::new(OrderItems::ProductName).string().not_null())
+                .col(ColumnDef::new(OrderItems::Condition).custom(CardCondition::Enum).not_null())
+                .col(ColumnDef::new(OrderItems::UnitPrice).big_integer().not_null())
+                .col(ColumnDef::new(OrderItems::Quantity).big_integer().not_null())
+                .col(ColumnDef::new(OrderItems::StripeProductId).string())
+                .col(ColumnDef::new(OrderItems::ApplicationFeeAmount).big_integer().not_null().default(0))
+                .col(ColumnDef::new(OrderItems::StripeTransferId).string())
+                .col(ColumnDef::new(OrderItems::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(OrderItems::Table, OrderItems::OrderId)
+                        .to(Orders::Table, Orders::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(OrderItems::Table, OrderItems::ListingId)
+                        .to(Listings::Table, Listings::Id)
+                        .on_delete(ForeignKeyAction::Restrict),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(OrderItems::Table, OrderItems::SellerId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::Restrict),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_order_items_order_id")
+                .table(OrderItems::Table)
+                .col(OrderItems::OrderId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_order_items_seller_id")
+                .table(OrderItems::Table)
+                .col(OrderItems::SellerId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(StockHolds::Table)
+                .col(ColumnDef::new(StockHolds::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(StockHolds::ListingId).uuid().not_null())
+                .col(ColumnDef::new(StockHolds::OrderId).uuid().not_null())
+                .col(ColumnDef::new(StockHolds::UserId).uuid().not_null())
+                .col(ColumnDef::new(StockHolds::Quantity).big_integer().not_null())
+                .col(ColumnDef::new(StockHolds::ExpiresAt).timestamp_with_time_zone().not_null())
+                .col(ColumnDef::new(StockHolds::ReleasedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(StockHolds::CommittedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(StockHolds::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(StockHolds::Table, StockHolds::ListingId)
+                        .to(Listings::Table, Listings::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(StockHolds::Table, StockHolds::OrderId)
+                        .to(Orders::Table, Orders::Id)
+                        .on_delete(ForeignKeyAction::Cascade),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_stock_holds_order_id")
+                .table(StockHolds::Table)
+                .col(StockHolds::OrderId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_stock_holds_expires_at")
+                .table(StockHolds::Table)
+                .col(StockHolds::ExpiresAt)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(StripeEvents::Table)
+                .col(ColumnDef::new(StripeEvents::Id).string().not_null().primary_key())
+                .col(ColumnDef::new(StripeEvents::EventType).string().not_null())
+                .col(ColumnDef::new(StripeEvents::Livemode).boolean().not_null())
+                .col(ColumnDef::new(StripeEvents::CreatedAt).timestamp_with_time_zone().not_null())
+                .col(ColumnDef::new(StripeEvents::ProcessedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(StripeEvents::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(StockHolds::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(OrderItems::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Orders::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(CartItems::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(Listings::Table).to_owned()).await
+    }
+}
@@ -0,0 +1,124 @@
+use sea_orm_migration::prelude::*;
+use super::m20250101_000002_create_accounts::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum AuditEvents {
+    Table,
+    Id,
+    ActorId,
+    Action,
+    TargetType,
+    TargetId,
+    OldValues,
+    NewValues,
+    Metadata,
+    IpAddress,
+    RequestId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Jobs {
+    Table,
+    Id,
+    Kind,
+    Payload,
+    Status,
+    Attempts,
+    MaxAttempts,
+    RunAt,
+    LockedAt,
+    LastError,
+    CompletedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_table(
+            Table::create()
+                .table(AuditEvents::Table)
+                .col(ColumnDef::new(AuditEvents::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(AuditEvents::ActorId).uuid())
+                .col(ColumnDef::new(AuditEvents::Action).string().not_null())
+                .col(ColumnDef::new(AuditEvents::TargetType).string().not_null())
+                .col(ColumnDef::new(AuditEvents::TargetId).string())
+                .col(ColumnDef::new(AuditEvents::OldValues).json_binary())
+                .col(ColumnDef::new(AuditEvents::NewValues).json_binary())
+                .col(ColumnDef::new(AuditEvents::Metadata).json_binary())
+                .col(ColumnDef::new(AuditEvents::IpAddress).string())
+                .col(ColumnDef::new(AuditEvents::RequestId).uuid())
+                .col(ColumnDef::new(AuditEvents::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .foreign_key(
+                    ForeignKey::create()
+                        .from(AuditEvents::Table, AuditEvents::ActorId)
+                        .to(Users::Table, Users::Id)
+                        .on_delete(ForeignKeyAction::SetNull),
+                )
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_audit_events_created_at")
+                .table(AuditEvents::Table)
+                .col(AuditEvents::CreatedAt)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_audit_events_actor_id")
+                .table(AuditEvents::Table)
+                .col(AuditEvents::ActorId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_audit_events_target")
+                .table(AuditEvents::Table)
+                .col(AuditEvents::TargetType)
+                .col(AuditEvents::TargetId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_table(
+            Table::create()
+                .table(Jobs::Table)
+                .col(ColumnDef::new(Jobs::Id).uuid().not_null().primary_key())
+                .col(ColumnDef::new(Jobs::Kind).string().not_null())
+                .col(ColumnDef::new(Jobs::Payload).json_binary().not_null())
+                .col(ColumnDef::new(Jobs::Status).string().not_null().default("pending"))
+                .col(ColumnDef::new(Jobs::Attempts).integer().not_null().default(0))
+                .col(ColumnDef::new(Jobs::MaxAttempts).integer().not_null())
+                .col(ColumnDef::new(Jobs::RunAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Jobs::LockedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Jobs::LastError).text())
+                .col(ColumnDef::new(Jobs::CompletedAt).timestamp_with_time_zone())
+                .col(ColumnDef::new(Jobs::CreatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .col(ColumnDef::new(Jobs::UpdatedAt).timestamp_with_time_zone().not_null().default(Expr::current_timestamp()))
+                .to_owned(),
+        ).await?;
+
+        // Workers claim the oldest due pending job, so this is the hot path.
+        manager.create_index(
+            Index::create()
+                .name("idx_jobs_status_run_at")
+                .table(Jobs::Table)
+                .col(Jobs::Status)
+                .col(Jobs::RunAt)
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_table(Table::drop().table(Jobs::Table).to_owned()).await?;
+        manager.drop_table(Table::drop().table(AuditEvents::Table).to_owned()).await
+    }
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Listings {
+    Table,
+    Id,
+    Price,
+    Status,
+    CreatedAt,
+}
+
+/// Public browsing filters on status and pages through listings by date or price, with
+/// the id as a tie-breaker.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_index(
+            Index::create()
+                .name("idx_listings_status_created_at_id")
+                .table(Listings::Table)
+                .col(Listings::Status)
+                .col(Listings::CreatedAt)
+                .col(Listings::Id)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_listings_status_price_id")
+                .table(Listings::Table)
+                .col(Listings::Status)
+                .col(Listings::Price)
+                .col(Listings::Id)
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(
+            Index::drop()
+                .name("idx_listings_status_price_id")
+                .table(Listings::Table)
+                .to_owned(),
+        ).await?;
+
+        manager.drop_index(
+            Index::drop()
+                .name("idx_listings_status_created_at_id")
+                .table(Listings::Table)
+                .to_owned(),
+        ).await
+    }
+}
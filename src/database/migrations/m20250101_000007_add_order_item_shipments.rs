@@ -0,0 +1,33 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum OrderItems {
+    Table,
+    ShippedAt,
+}
+
+/// Orders can span several sellers, so shipment is tracked per item rather than only
+/// on the order.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(OrderItems::Table)
+                .add_column(ColumnDef::new(OrderItems::ShippedAt).timestamp_with_time_zone())
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(OrderItems::Table)
+                .drop_column(OrderItems::ShippedAt)
+                .to_owned(),
+        ).await
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum OrderItems {
+    Table,
+    StripeTransferReversalId,
+    ApplicationFeeRefunded,
+}
+
+/// A refunded order claws back each seller's transfer and gives up the platform fee, and
+/// both are recorded per item so a retried refund doesn't reverse anything twice.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(OrderItems::Table)
+                .add_column(ColumnDef::new(OrderItems::StripeTransferReversalId).string())
+                .add_column(
+                    ColumnDef::new(OrderItems::ApplicationFeeRefunded)
+                        .big_integer()
+                        .not_null()
+                        .default(0)
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(OrderItems::Table)
+                .drop_column(OrderItems::StripeTransferReversalId)
+                .drop_column(OrderItems::ApplicationFeeRefunded)
+                .to_owned(),
+        ).await
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(DeriveIden)]
+enum Orders {
+    Table,
+    AmountRefunded,
+}
+
+/// How much of an order's charge Stripe has refunded so far, so partial refunds are on
+/// record even though they don't change the order's status.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Orders::Table)
+                .add_column(
+                    ColumnDef::new(Orders::AmountRefunded)
+                        .big_integer()
+                        .not_null()
+                        .default(0)
+                )
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.alter_table(
+            Table::alter()
+                .table(Orders::Table)
+                .drop_column(Orders::AmountRefunded)
+                .to_owned(),
+        ).await
+    }
+}
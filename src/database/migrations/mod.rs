@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+mod m20250101_000001_create_enums;
+mod m20250101_000002_create_accounts;
+mod m20250101_000003_create_catalogue;
+mod m20250101_000004_create_marketplace;
+mod m20250101_000005_create_audit_and_jobs;
+mod m20250101_000006_add_listing_browse_indexes;
+mod m20250101_000007_add_order_item_shipments;
+mod m20250101_000008_add_order_item_refunds;
+mod m20250101_000009_add_order_refunded_amount;
+
+/// Applied in order; the names are recorded in `seaql_migrations`, so never rename one
+/// that has shipped. Schema changes go in a new migration.
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20250101_000001_create_enums::Migration),
+            Box::new(m20250101_000002_create_accounts::Migration),
+            Box::new(m20250101_000003_create_catalogue::Migration),
+            Box::new(m20250101_000004_create_marketplace::Migration),
+            Box::new(m20250101_000005_create_audit_and_jobs::Migration),
+            Box::new(m20250101_000006_add_listing_browse_indexes::Migration),
+            Box::new(m20250101_000007_add_order_item_shipments::Migration),
+            Box::new(m20250101_000008_add_order_item_refunds::Migration),
+            Box::new(m20250101_000009_add_order_refunded_amount::Migration),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_run_in_name_order() {
+        let names: Vec<String> = Migrator::migrations().iter().map(|migration| migration.name().to_string()).collect();
+
+        let mut sorted = names.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        assert_eq!(names, sorted);
+    }
+}
@@ -1 +1,2 @@
-pub mod migrations;
\ No newline at end of file
+pub mod migrations;
+pub mod seed;
\ No newline at end of file
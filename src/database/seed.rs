@@ -0,0 +1,327 @@
+use sea_orm::*;
+use uuid::Uuid;
+use crate::entities::{games, product_variants, products, sets, user_roles, users};
+use crate::entities::products::ProductCategory;
+use crate::entities::user_roles::UserRoleType;
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::utils::message_util::MessageUtil;
+
+/// Shared by every demo account. Only ever meant for local and staging databases.
+pub const DEMO_PASSWORD: &str = "tcgemporium-demo";
+
+const GAMES: &[(&str, &str)] = &[
+    ("Pokemon", "The Pokémon Company"),
+    ("Magic: The Gathering", "Wizards of the Coast"),
+    ("Yu-Gi-Oh!", "Konami"),
+];
+
+const SETS: &[(&str, &str)] = &[
+    ("Pokemon", "Base Set"),
+    ("Pokemon", "Scarlet & Violet"),
+    ("Magic: The Gathering", "Dominaria United"),
+    ("Yu-Gi-Oh!", "Legend of Blue Eyes White Dragon"),
+];
+
+struct DemoProduct {
+    name: &'static str,
+    game: &'static str,
+    set: &'static str,
+    category: ProductCategory,
+    set_number: &'static str,
+    rarity: Option<&'static str>,
+    variants: &'static [&'static str],
+}
+
+const PRODUCTS: &[DemoProduct] = &[
+    DemoProduct {
+        name: "Charizard",
+        game: "Pokemon",
+        set: "Base Set",
+        category: ProductCategory::Card,
+        set_number: "4/102",
+        rarity: Some("Rare Holo"),
+        variants: &["Unlimited", "Shadowless", "1st Edition"],
+    },
+    DemoProduct {
+        name: "Pikachu",
+        game: "Pokemon",
+        set: "Base Set",
+        category: ProductCategory::Card,
+        set_number: "58/102",
+        rarity: Some("Common"),
+        variants: &["Unlimited", "1st Edition"],
+    },
+    DemoProduct {
+        name: "Scarlet & Violet Booster Box",
+        game: "Pokemon",
+        set: "Scarlet & Violet",
+        category: ProductCategory::Sealed,
+        set_number: "SV01-BB",
+        rarity: None,
+        variants: &["English"],
+    },
+    DemoProduct {
+        name: "Sheoldred, the Apocalypse",
+        game: "Magic: The Gathering",
+        set: "Dominaria United",
+        category: ProductCategory::Card,
+        set_number: "107",
+        rarity: Some("Mythic Rare"),
+        variants: &["Regular", "Foil"],
+    },
+    DemoProduct {
+        name: "Blue-Eyes White Dragon",
+        game: "Yu-Gi-Oh!",
+        set: "Legend of Blue Eyes White Dragon",
+        category: ProductCategory::Card,
+        set_number: "LOB-001",
+        rarity: Some("Ultra Rare"),
+        variants: &["Unlimited", "1st Edition"],
+    },
+];
+
+const USERS: &[(&str, &str, UserRoleType)] = &[
+    ("admin", "admin@tcgemporium.local", UserRoleType::Admin),
+    ("moderator", "moderator@tcgemporium.local", UserRoleType::Moderator),
+    ("seller", "seller@tcgemporium.local", UserRoleType::Seller),
+    ("buyer", "buyer@tcgemporium.local", UserRoleType::Buyer),
+];
+
+/// Demo data for a fresh environment. Anything that already exists, matched by name or
+/// email, is left alone, so seeding twice is harmless.
+pub struct Seeder;
+
+impl Seeder {
+    pub async fn run(db: &DatabaseConnection) -> Result<(), String> {
+        let txn = db.begin()
+            .await
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let games = Self::seed_games(&txn).await?;
+        let sets = Self::seed_sets(&txn).await?;
+        let product_ids = Self::seed_products(&txn).await?;
+        let users = Self::seed_users(&txn).await?;
+
+        // Workers pick this up once the server starts, so the demo catalogue is searchable.
+        if !product_ids.is_empty() {
+            JobService::enqueue(&txn, Job::IndexProducts { product_ids: product_ids.clone() }).await
+                .map_err(|e| e.to_string())?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit seed data: {}", e))?;
+
+        MessageUtil::success(&format!(
+            "Seeded {} games, {} sets, {} products and {} users",
+            games, sets, product_ids.len(), users
+        ));
+
+        if users > 0 {
+            MessageUtil::info(&format!("Demo accounts use the password '{}'", DEMO_PASSWORD));
+        }
+
+        Ok(())
+    }
+
+    async fn seed_games<C: ConnectionTrait>(conn: &C) -> Result<usize, String> {
+        let mut created = 0;
+
+        for (name, publisher) in GAMES {
+            let exists = games::Entity::find()
+                .filter(games::Column::Name.eq(*name))
+                .one(conn)
+                .await
+                .map_err(|e| format!("Failed to fetch game: {}", e))?
+                .is_some();
+
+            if exists {
+                continue;
+            }
+
+            games::ActiveModel {
+                name: Set(name.to_string()),
+                publisher: Set(Some(publisher.to_string())),
+                ..games::ActiveModel::new()
+            }
+                .insert(conn)
+                .await
+                .map_err(|e| format!("Failed to create game {}: {}", name, e))?;
+
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    async fn seed_sets<C: ConnectionTrait>(conn: &C) -> Result<usize, String> {
+        let mut created = 0;
+
+        for (game_name, name) in SETS {
+            let exists = sets::Entity::find()
+                .filter(sets::Column::GameName.eq(*game_name))
+                .filter(sets::Column::Name.eq(*name))
+                .one(conn)
+                .await
+                .map_err(|e| format!("Failed to fetch set: {}", e))?
+                .is_some();
+
+            if exists {
+                continue;
+            }
+
+            sets::ActiveModel {
+                game_name: Set(game_name.to_string()),
+                name: Set(name.to_string()),
+                ..sets::ActiveModel::new()
+            }
+                .insert(conn)
+                .await
+                .map_err(|e| format!("Failed to create set {}: {}", name, e))?;
+
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    async fn seed_products<C: ConnectionTrait>(conn: &C) -> Result<Vec<Uuid>, String> {
+        let mut created = Vec::new();
+
+        for demo in PRODUCTS {
+            let exists = products::Entity::find()
+                .filter(products::Column::Game.eq(demo.game))
+                .filter(products::Column::Name.eq(demo.name))
+                .one(conn)
+                .await
+                .map_err(|e| format!("Failed to fetch product: {}", e))?
+                .is_some();
+
+            if exists {
+                continue;
+            }
+
+            let now = chrono::Utc::now();
+            let mut metadata = serde_json::json!({ "set_number": demo.set_number });
+
+            if let Some(rarity) = demo.rarity {
+                metadata["rarity"] = serde_json::Value::String(rarity.to_string());
+            }
+
+            let product = products::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                name: Set(demo.name.to_string()),
+                description: Set(None),
+                image_url: Set(None),
+                game: Set(demo.game.to_string()),
+                set: Set(Some(demo.set.to_string())),
+                category: Set(demo.category.clone()),
+                subcategory: Set(None),
+                metadata: Set(Some(metadata)),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+                .insert(conn)
+                .await
+                .map_err(|e| format!("Failed to create product {}: {}", demo.name, e))?;
+
+            for (position, variant) in demo.variants.iter().enumerate() {
+                product_variants::ActiveModel {
+                    product_id: Set(product.id),
+                    name: Set(variant.to_string()),
+                    set_number: Set(Some(demo.set_number.to_string())),
+                    is_primary: Set(position == 0),
+                    sort_order: Set(position as i32),
+                    created_at: Set(now),
+                    ..Default::default()
+                }
+                    .insert(conn)
+                    .await
+                    .map_err(|e| format!("Failed to create product variant: {}", e))?;
+            }
+
+            created.push(product.id);
+        }
+
+        Ok(created)
+    }
+
+    async fn seed_users<C: ConnectionTrait>(conn: &C) -> Result<usize, String> {
+        let password_hash = bcrypt::hash(DEMO_PASSWORD, bcrypt::DEFAULT_COST)
+            .map_err(|e| format!("Failed to hash password: {}", e))?;
+
+        let mut created = 0;
+
+        for (username, email, role) in USERS {
+            let exists = users::Entity::find()
+                .filter(users::Column::Email.eq(*email))
+                .one(conn)
+                .await
+                .map_err(|e| format!("Failed to fetch user: {}", e))?
+                .is_some();
+
+            if exists {
+                continue;
+            }
+
+            let user = users::ActiveModel {
+                username: Set(Some(username.to_string())),
+                email: Set(email.to_string()),
+                password_hash: Set(password_hash.clone()),
+                email_verified: Set(true),
+                email_verified_at: Set(Some(chrono::Utc::now())),
+                verified_seller: Set(*role == UserRoleType::Seller),
+                ..Default::default()
+            }
+                .insert(conn)
+                .await
+                .map_err(|e| format!("Failed to create user {}: {}", username, e))?;
+
+            user_roles::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                user_id: Set(user.id),
+                role: Set(role.clone()),
+                granted_at: Set(chrono::Utc::now().into()),
+                granted_by: Set(None),
+                is_active: Set(true),
+            }
+                .insert(conn)
+                .await
+                .map_err(|e| format!("Failed to grant {} role: {}", role.as_str(), e))?;
+
+            created += 1;
+        }
+
+        Ok(created)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_demo_set_belongs_to_a_demo_game() {
+        for (game, set) in SETS {
+            assert!(GAMES.iter().any(|(name, _)| name == game), "{} has no game {}", set, game);
+        }
+    }
+
+    #[test]
+    fn every_demo_product_belongs_to_a_demo_set() {
+        for product in PRODUCTS {
+            assert!(
+                SETS.iter().any(|(game, set)| *game == product.game && *set == product.set),
+                "{} has no set {} / {}", product.name, product.game, product.set
+            );
+            assert!(!product.variants.is_empty(), "{} has no variants", product.name);
+        }
+    }
+
+    #[test]
+    fn demo_accounts_are_unique() {
+        for (i, (username, email, _)) in USERS.iter().enumerate() {
+            assert!(USERS[i + 1..].iter().all(|(other, other_email, _)| other != username && other_email != email));
+        }
+    }
+}
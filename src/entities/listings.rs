@@ -55,10 +55,11 @@ pub struct Model {
     pub quantity: i64,
     pub reserved_quantity: i64,
     pub status: ListingStatus,
-    pub stripe_product_id: String,
+    pub stripe_product_id: Option<String>,
     pub previous_stripe_product_id: Option<String>,
     pub image_url: Option<String>,
     pub description: Option<String>,
+    pub expires_at: DateTimeUtc,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
     pub deleted_at: Option<DateTimeUtc>,
@@ -94,6 +95,17 @@ impl Related<super::users::Entity> for Entity {
 
 impl ActiveModelBehavior for ActiveModel {}
 
+impl ListingStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingStatus::Active => "active",
+            ListingStatus::Sold => "sold",
+            ListingStatus::Cancelled => "cancelled",
+            ListingStatus::Expired => "expired",
+        }
+    }
+}
+
 impl Model {
     pub fn is_active(&self) -> bool {
         self.status == ListingStatus::Active
@@ -110,9 +122,27 @@ impl Model {
     pub fn is_expired(&self) -> bool {
         self.status == ListingStatus::Expired
     }
+
+    pub fn available_quantity(&self) -> i64 {
+        self.quantity - self.reserved_quantity
+    }
 }
 
 impl Condition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Condition::Mint => "mint",
+            Condition::NearMint => "near_mint",
+            Condition::LightlyPlayed => "lightly_played",
+            Condition::ModeratelyPlayed => "moderately_played",
+            Condition::HeavilyPlayed => "heavily_played",
+            Condition::Damaged => "damaged",
+            Condition::New => "new",
+            Condition::Used => "used",
+            Condition::Sealed => "sealed",
+        }
+    }
+
     pub fn valid_for_category(&self, category: &ProductCategory) -> bool {
         match (self, category) {
             (Condition::Mint | Condition::NearMint | Condition::LightlyPlayed |
@@ -159,6 +189,16 @@ pub fn string_to_condition(condition: &str) -> Option<Condition> {
     }
 }
 
+pub fn string_to_status(status: &str) -> Option<ListingStatus> {
+    match status.to_lowercase().as_str() {
+        "active" => Some(ListingStatus::Active),
+        "sold" => Some(ListingStatus::Sold),
+        "cancelled" => Some(ListingStatus::Cancelled),
+        "expired" => Some(ListingStatus::Expired),
+        _ => None,
+    }
+}
+
 pub fn get_valid_conditions_for_category(category: &ProductCategory) -> Vec<Condition> {
     match category {
         ProductCategory::Card => Condition::card_conditions(),
@@ -16,6 +16,7 @@ pub struct Model {
     pub failure_reason: Option<String>,
     pub location_id: Option<Uuid>,
     pub session_id: Option<Uuid>,
+    pub is_suspicious: bool,
     pub attempted_at: ChronoDateTimeUtc,
 }
 
@@ -56,6 +57,7 @@ impl ActiveModelBehavior for ActiveModel {
         Self {
             id: Set(Uuid::new_v4()),
             login_method: Set("password".to_owned()),
+            is_suspicious: Set(false),
             attempted_at: Set(chrono::Utc::now()),
             ..ActiveModelTrait::default()
         }
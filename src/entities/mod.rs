@@ -12,6 +12,15 @@ pub mod games;
 pub mod sets;
 pub(crate) mod product_variants;
 mod variant_images;
+pub mod cart_items;
+pub mod orders;
+pub mod order_items;
+pub mod stripe_events;
+pub mod stock_holds;
+pub mod phone_verification_codes;
+pub mod user_identities;
+pub mod audit_events;
+pub mod jobs;
 
 pub use users::Entity as Users;
 pub use mfa_backup_codes::Entity as MfaBackupCodes;
@@ -0,0 +1,82 @@
+use sea_orm::entity::prelude::*;
+use sea_orm::Set;
+use serde::{Deserialize, Serialize};
+use crate::entities::listings::Condition;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_items")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub listing_id: Uuid,
+    pub seller_id: Uuid,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub condition: Condition,
+    pub unit_price: i64,
+    pub quantity: i64,
+    pub stripe_product_id: Option<String>,
+    pub application_fee_amount: i64,
+    pub stripe_transfer_id: Option<String>,
+    pub shipped_at: Option<DateTimeUtc>,
+    pub stripe_transfer_reversal_id: Option<String>,
+    pub application_fee_refunded: i64,
+    pub created_at: DateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::orders::Entity",
+        from = "Column::OrderId",
+        to = "super::orders::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Order,
+    #[sea_orm(
+        belongs_to = "super::listings::Entity",
+        from = "Column::ListingId",
+        to = "super::listings::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Restrict"
+    )]
+    Listing,
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::SellerId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Restrict"
+    )]
+    Seller,
+}
+
+impl Related<super::orders::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+impl Related<super::listings::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Listing.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: Set(Uuid::new_v4()),
+            created_at: Set(chrono::Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
+
+impl Model {
+    pub fn line_total(&self) -> i64 {
+        self.unit_price * self.quantity
+    }
+}
@@ -10,6 +10,7 @@ pub struct Model {
     pub name: String,
     pub set_number: Option<String>,
     pub is_primary: bool,
+    pub sort_order: i32,
     pub created_at: DateTimeUtc,
 }
 
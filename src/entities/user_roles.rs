@@ -47,4 +47,35 @@ impl Related<super::users::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
\ No newline at end of file
+impl UserRoleType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRoleType::Buyer => "buyer",
+            UserRoleType::Seller => "seller",
+            UserRoleType::Moderator => "moderator",
+            UserRoleType::Admin => "admin",
+        }
+    }
+
+    /// Permissions granted by the role. `all` is a wildcard that satisfies any check.
+    pub fn permissions(&self) -> &'static [&'static str] {
+        match self {
+            UserRoleType::Buyer => &[],
+            UserRoleType::Seller => &["listing:write"],
+            UserRoleType::Moderator => &["product:write", "catalogue:write"],
+            UserRoleType::Admin => &["all"],
+        }
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub fn string_to_role(role: &str) -> Option<UserRoleType> {
+    match role.to_lowercase().as_str() {
+        "buyer" => Some(UserRoleType::Buyer),
+        "seller" => Some(UserRoleType::Seller),
+        "moderator" => Some(UserRoleType::Moderator),
+        "admin" => Some(UserRoleType::Admin),
+        _ => None,
+    }
+}
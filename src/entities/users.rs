@@ -2,6 +2,10 @@ use sea_orm::entity::prelude::*;
 use sea_orm::Set;
 use serde::{Deserialize, Serialize};
 
+pub const ACCOUNT_STATUS_ACTIVE: &str = "active";
+pub const ACCOUNT_STATUS_SUSPENDED: &str = "suspended";
+pub const ACCOUNT_STATUS_BANNED: &str = "banned";
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "users")]
 pub struct Model {
@@ -20,6 +24,7 @@ pub struct Model {
     pub phone_verified_at: Option<ChronoDateTimeUtc>,
     pub totp_enabled: bool,
     pub totp_secret: Option<String>,
+    pub sms_mfa_enabled: bool,
     pub account_status: String,
     pub last_login_at: Option<ChronoDateTimeUtc>,
     pub password_changed_at: Option<ChronoDateTimeUtc>,
@@ -43,6 +48,7 @@ pub struct Model {
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
     pub verified_seller: bool,
+    pub stripe_account_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -59,6 +65,10 @@ pub enum Relation {
     AddressHistory,
     #[sea_orm(has_many = "super::user_roles::Entity")]
     UserRoles,
+    #[sea_orm(has_many = "super::phone_verification_codes::Entity")]
+    PhoneVerificationCodes,
+    #[sea_orm(has_many = "super::user_identities::Entity")]
+    UserIdentities,
 }
 
 impl Related<super::mfa_backup_codes::Entity> for Entity {
@@ -97,6 +107,18 @@ impl Related<super::user_roles::Entity> for Entity {
     }
 }
 
+impl Related<super::phone_verification_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PhoneVerificationCodes.def()
+    }
+}
+
+impl Related<super::user_identities::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserIdentities.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {
     fn new() -> Self {
         Self {
@@ -107,7 +129,8 @@ impl ActiveModelBehavior for ActiveModel {
             verified_seller: Set(false),
             phone_verified: Set(false),
             totp_enabled: Set(false),
-            account_status: Set("active".to_owned()),
+            sms_mfa_enabled: Set(false),
+            account_status: Set(ACCOUNT_STATUS_ACTIVE.to_owned()),
             login_attempts: Set(Some(0)),
             timezone: Set(Some("UTC".to_owned())),
             language: Set(Some("en".to_owned())),
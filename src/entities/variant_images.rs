@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    pub variant_id: Uuid,
+    pub variant_id: i32,
     pub image_type: String,
     pub image_url: String,
     pub size: Option<String>,
@@ -0,0 +1,281 @@
+use std::fmt;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use sea_orm::DbErr;
+use serde::Serialize;
+use crate::handlers::ApiResponse;
+use crate::utils::message_util::MessageUtil;
+
+/// An external service a request depended on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpstreamService {
+    Stripe,
+    Meilisearch,
+    R2,
+    Twilio,
+    Mail,
+    OAuth,
+    Redis,
+}
+
+impl UpstreamService {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpstreamService::Stripe => "stripe",
+            UpstreamService::Meilisearch => "meilisearch",
+            UpstreamService::R2 => "r2",
+            UpstreamService::Twilio => "twilio",
+            UpstreamService::Mail => "mail",
+            UpstreamService::OAuth => "oauth",
+            UpstreamService::Redis => "redis",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Validation {
+        message: String,
+        fields: Vec<FieldError>,
+    },
+    Conflict(String),
+    RateLimited(String),
+    Upstream(UpstreamService, String),
+    Internal(String),
+}
+
+/// The `data` of a failed `ApiResponse`, so clients can branch on `code` rather than
+/// on the wording of `message`.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl AppError {
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation {
+            message: message.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// A validation failure pinned to a single request field.
+    pub fn field(field: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+
+        AppError::Validation {
+            message: message.clone(),
+            fields: vec![FieldError {
+                field: field.to_string(),
+                message,
+            }],
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::Validation { .. } => "validation_failed",
+            AppError::Conflict(_) => "conflict",
+            AppError::RateLimited(_) => "rate_limited",
+            AppError::Upstream(..) => "upstream_unavailable",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::Validation { message, .. }
+            | AppError::Conflict(message)
+            | AppError::RateLimited(message)
+            | AppError::Upstream(_, message)
+            | AppError::Internal(message) => message,
+        }
+    }
+
+    /// The message that is safe to show a client.
+    pub fn public_message(&self) -> String {
+        match self {
+            AppError::Internal(_) => "Something went wrong, please try again later".to_string(),
+            AppError::Upstream(service, _) => format!("{} is unavailable, please try again later", match service {
+                UpstreamService::Stripe => "Payment processing",
+                UpstreamService::Meilisearch => "Search",
+                UpstreamService::R2 => "Image storage",
+                UpstreamService::Twilio => "SMS delivery",
+                UpstreamService::Mail => "Email delivery",
+                UpstreamService::OAuth => "The sign-in provider",
+                UpstreamService::Redis => "The cache",
+            }),
+            _ => self.message().to_string(),
+        }
+    }
+
+    /// The public message, after logging whatever it hides. For failures reported inside
+    /// an otherwise successful response, like a single row of an import.
+    pub fn report(&self) -> String {
+        self.log();
+        self.public_message()
+    }
+
+    fn log(&self) {
+        match self {
+            AppError::Internal(message) => MessageUtil::error(message),
+            AppError::Upstream(service, message) => {
+                MessageUtil::error(&format!("{} error: {}", service.as_str(), message))
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Upstream(..) => StatusCode::BAD_GATEWAY,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Internal and upstream details are logged rather than sent, since they can carry
+    /// SQL or provider responses.
+    fn error_response(&self) -> HttpResponse {
+        self.log();
+
+        let fields = match self {
+            AppError::Validation { fields, .. } => fields.clone(),
+            _ => Vec::new(),
+        };
+
+        let service = match self {
+            AppError::Upstream(service, _) => Some(service.as_str()),
+            _ => None,
+        };
+
+        HttpResponse::build(self.status_code()).json(ApiResponse {
+            success: false,
+            message: self.public_message(),
+            data: Some(ErrorBody {
+                code: self.code(),
+                service,
+                fields,
+            }),
+        })
+    }
+}
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        match e {
+            DbErr::RecordNotFound(message) => AppError::NotFound(message),
+            e => AppError::Internal(format!("Database error: {}", e)),
+        }
+    }
+}
+
+impl From<validator::ValidationErrors> for AppError {
+    fn from(e: validator::ValidationErrors) -> Self {
+        let mut fields: Vec<FieldError> = e.field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| errors.iter().map(move |error| FieldError {
+                field: field.to_string(),
+                message: error.message
+                    .as_ref()
+                    .map(|message| message.to_string())
+                    .unwrap_or_else(|| format!("{} is invalid", field)),
+            }))
+            .collect();
+
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+
+        AppError::Validation {
+            message: fields.first()
+                .map(|field| field.message.clone())
+                .unwrap_or_else(|| "Request is invalid".to_string()),
+            fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_each_variant_to_its_status_and_code() {
+        let cases = [
+            (AppError::NotFound("missing".to_string()), StatusCode::NOT_FOUND, "not_found"),
+            (AppError::Unauthorized("who".to_string()), StatusCode::UNAUTHORIZED, "unauthorized"),
+            (AppError::Forbidden("no".to_string()), StatusCode::FORBIDDEN, "forbidden"),
+            (AppError::validation("bad"), StatusCode::BAD_REQUEST, "validation_failed"),
+            (AppError::Conflict("taken".to_string()), StatusCode::CONFLICT, "conflict"),
+            (AppError::RateLimited("slow down".to_string()), StatusCode::TOO_MANY_REQUESTS, "rate_limited"),
+            (AppError::Upstream(UpstreamService::Stripe, "timeout".to_string()), StatusCode::BAD_GATEWAY, "upstream_unavailable"),
+            (AppError::Internal("boom".to_string()), StatusCode::INTERNAL_SERVER_ERROR, "internal_error"),
+        ];
+
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code(), status, "{:?}", error);
+            assert_eq!(error.code(), code, "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn hides_internal_and_upstream_details() {
+        let internal = AppError::Internal("duplicate key value violates unique constraint".to_string());
+        let upstream = AppError::Upstream(UpstreamService::Twilio, "401 invalid credentials".to_string());
+
+        assert_eq!(internal.public_message(), "Something went wrong, please try again later");
+        assert_eq!(upstream.public_message(), "SMS delivery is unavailable, please try again later");
+        assert_eq!(AppError::Conflict("Order is already paid".to_string()).public_message(), "Order is already paid");
+    }
+
+    #[test]
+    fn pins_field_errors_to_their_field() {
+        match AppError::field("format", "Format must be csv or json") {
+            AppError::Validation { message, fields } => {
+                assert_eq!(message, "Format must be csv or json");
+                assert_eq!(fields.len(), 1);
+                assert_eq!(fields[0].field, "format");
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn treats_missing_records_as_not_found() {
+        assert_eq!(AppError::from(DbErr::RecordNotFound("user".to_string())).code(), "not_found");
+        assert_eq!(AppError::from(DbErr::Custom("broken".to_string())).code(), "internal_error");
+    }
+}
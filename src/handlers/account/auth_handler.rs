@@ -6,10 +6,15 @@ use futures_util::TryFutureExt;
 use serde::{Deserialize, Serialize};
 use tracing::log;
 use crate::app_state::AppState;
+use crate::errors::AppError;
+use crate::middleware::rate_limit_middleware::RateLimit;
+use crate::services::account::account_email_service::AccountEmailService;
 use crate::services::account::auth_service::{AuthService, AuthenticatedUser};
 use crate::services::account::jwt_service::{Claims, JwtService};
+use crate::services::account::session_service::SessionService;
 use crate::services::account::user_service::UserService;
 use crate::services::integrations::cookie_service::CookieService;
+use crate::utils::request_util::ClientInfo;
 use crate::utils::validator_util::ValidatorUtil;
 
 //Login Route
@@ -35,25 +40,34 @@ pub struct UserResponse {
     pub permissions: Vec<String>,
 }
 
-#[post("/login")]
+#[post("/login", wrap = "RateLimit::new(10, 60)")]
 async fn login(
     state: web::Data<AppState>,
+    client: ClientInfo,
     request: web::Json<LoginRequest>,
 ) -> Result<impl Responder> {
     let auth_service = AuthService::new(state.as_ref().clone());
     
-    match auth_service.authenticate_user(&request.email, &request.password).await {
+    match auth_service.authenticate_user(&request.email, &request.password, &client).await {
         Ok (authenticated_user) => {
-            Ok(HttpResponse::Ok()
-                .cookie(CookieService::auth_cookie(&authenticated_user.token))
-                .json(serde_json::json!({
-                    "message": "Login successful",
-                    "success": true,
-                })))
+            let mut response = HttpResponse::Ok();
+            response.cookie(CookieService::auth_cookie(&authenticated_user.token));
+
+            if let Some(refresh_token) = &authenticated_user.refresh_token {
+                response.cookie(CookieService::refresh_cookie(refresh_token));
+            }
+
+            Ok(response.json(serde_json::json!({
+                "message": "Login successful",
+                "success": true,
+                "mfa_required": authenticated_user.refresh_token.is_none(),
+                "mfa_methods": {
+                    "totp": authenticated_user.user.totp_enabled,
+                    "sms": authenticated_user.user.sms_mfa_enabled,
+                },
+            })))
         }
-        Err (e) => Err(
-            actix_web::error::ErrorUnauthorized(e)
-        ),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -76,8 +90,8 @@ async fn get_current_user(
             token_purpose: claims.purpose,
             issued_at: claims.iat as i64,
         })),
-        Ok(None) => Err(actix_web::error::ErrorNotFound("User not found")),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Ok(None) => Err(AppError::NotFound("User not found".to_string()).into()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -91,44 +105,205 @@ pub struct RegisterRequest {
     pub email: String,
 }
 
-#[post("/register")]
+#[post("/register", wrap = "RateLimit::new(5, 60 * 60)")]
 pub async fn register(
     state: web::Data<AppState>,
+    client: ClientInfo,
     request: web::Json<RegisterRequest>,
 ) -> Result<impl Responder> {
     let request = request.into_inner();
     let auth_service = AuthService::new(state.as_ref().clone());
     
-    match auth_service.register_user(request).await {
+    match auth_service.register_user(request, &client).await {
         Ok(_) => Ok(HttpResponse::Ok().json(
             serde_json::json!({
                 "message": "User registered successfully",
                 "success": true
             }
         ))),
-        Err(e) => Err(actix_web::error::ErrorBadRequest(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
 //Logout Route
 #[post("/logout")]
-async fn logout() -> Result<impl Responder> {
+async fn logout(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let auth_service = AuthService::new(state.as_ref().clone());
+
+    if let Err(e) = auth_service.logout(&claims).await {
+        return Err(e.into());
+    }
 
     let logout_cookie = CookieService::logout_cookie();
 
     Ok(HttpResponse::Ok()
         .cookie(logout_cookie)
+        .cookie(CookieService::clear_refresh_cookie())
         .json(serde_json::json!({
             "message": "Logged out successfully",
             "success": true
         })))
 }
 
+//Refresh Route
+#[post("/refresh")]
+async fn refresh(
+    state: web::Data<AppState>,
+    client: ClientInfo,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    let refresh_token = match req.cookie("refresh_token") {
+        Some(cookie) => cookie.value().to_string(),
+        None => return Err(AppError::Unauthorized("Missing refresh token".to_string()).into()),
+    };
+
+    let session_service = SessionService::new(state.as_ref().clone());
+
+    match session_service.refresh(&refresh_token, &client).await {
+        Ok(session) => Ok(HttpResponse::Ok()
+            .cookie(CookieService::auth_cookie(&session.access_token))
+            .cookie(CookieService::refresh_cookie(&session.refresh_token))
+            .json(serde_json::json!({
+                "message": "Session refreshed",
+                "success": true
+            }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+//Email Verification Routes
+#[derive(Deserialize)]
+pub struct ActionTokenRequest {
+    pub token: String,
+}
+
+#[post("/verify-email")]
+async fn verify_email(
+    state: web::Data<AppState>,
+    request: web::Json<ActionTokenRequest>,
+) -> Result<impl Responder> {
+    let email_service = AccountEmailService::new(state.as_ref().clone());
+
+    match email_service.verify_email(&request.token).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Email verified successfully",
+            "success": true
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/verify-email/resend", wrap = "RateLimit::new(3, 60 * 60)")]
+async fn resend_verification_email(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let email_service = AccountEmailService::new(state.as_ref().clone());
+
+    match email_service.resend_verification_email(&claims.sub).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Verification email sent",
+            "success": true
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/confirm-location")]
+async fn confirm_location(
+    state: web::Data<AppState>,
+    request: web::Json<ActionTokenRequest>,
+) -> Result<impl Responder> {
+    let email_service = AccountEmailService::new(state.as_ref().clone());
+
+    match email_service.confirm_location(&request.token).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Location confirmed, you can now sign in",
+            "success": true
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+//Password Reset Routes
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+    #[serde(rename = "confirmPassword")]
+    pub confirm_password: String,
+}
+
+#[post("/forgot-password", wrap = "RateLimit::new(5, 60 * 60)")]
+async fn forgot_password(
+    state: web::Data<AppState>,
+    request: web::Json<ForgotPasswordRequest>,
+) -> Result<impl Responder> {
+    let email_service = AccountEmailService::new(state.as_ref().clone());
+
+    match email_service.request_password_reset(&request.email).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "If an account exists for this email, a reset link has been sent",
+            "success": true
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/reset-password", wrap = "RateLimit::new(10, 60 * 60)")]
+async fn reset_password(
+    state: web::Data<AppState>,
+    client: ClientInfo,
+    request: web::Json<ResetPasswordRequest>,
+) -> Result<impl Responder> {
+    let email_service = AccountEmailService::new(state.as_ref().clone());
+
+    match email_service.reset_password(request.into_inner(), &client).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Password reset successfully, please log in again",
+            "success": true
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
 
-//Oauth2 Route
+//Change Password Route
 #[derive(Deserialize)]
-struct Oauth2Request {
-    pub provider: String,
-    pub code: String,
+pub struct ChangePasswordRequest {
+    #[serde(rename = "currentPassword")]
+    pub current_password: String,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+    #[serde(rename = "confirmPassword")]
+    pub confirm_password: String,
 }
 
+#[post("/password")]
+async fn change_password(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    request: web::Json<ChangePasswordRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    let auth_service = AuthService::new(state.as_ref().clone());
+
+    match auth_service.change_password(&claims, request, &client).await {
+        Ok(_) => Ok(HttpResponse::Ok()
+            .cookie(CookieService::logout_cookie())
+            .cookie(CookieService::clear_refresh_cookie())
+            .json(serde_json::json!({
+                "message": "Password changed successfully, please log in again",
+                "success": true
+            }))),
+        Err(e) => Err(e.into()),
+    }
+}
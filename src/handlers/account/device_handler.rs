@@ -0,0 +1,59 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::account::login_history_service::LoginHistoryService;
+
+#[get("")]
+pub async fn get_devices(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let history_service = LoginHistoryService::new(state.as_ref().clone());
+
+    match history_service.get_devices(claims.sub).await {
+        Ok(devices) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Devices retrieved successfully".to_string(),
+            data: Some(devices),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/trust")]
+pub async fn trust_device(
+    state: web::Data<AppState>,
+    claims: Claims,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let history_service = LoginHistoryService::new(state.as_ref().clone());
+
+    match history_service.trust_device(claims.sub, id.into_inner()).await {
+        Ok(device) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Device trusted successfully".to_string(),
+            data: Some(device),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/{id}")]
+pub async fn forget_device(
+    state: web::Data<AppState>,
+    claims: Claims,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let history_service = LoginHistoryService::new(state.as_ref().clone());
+
+    match history_service.forget_device(claims.sub, id.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "Device forgotten successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -2,10 +2,15 @@ use serde::Deserialize;
 use actix_web::{web, HttpResponse, Responder, post};
 use sea_orm::Set;
 use crate::app_state::AppState;
+use crate::middleware::rate_limit_middleware::RateLimit;
 use crate::entities::users::Model;
+use crate::services::account::auth_service::AuthService;
 use crate::services::account::jwt_service::Claims;
 use crate::services::account::mfa_service::{MfaService};
+use crate::services::account::phone_verification_service::PhoneVerificationService;
 use crate::services::account::user_service::UserService;
+use crate::services::integrations::cookie_service::CookieService;
+use crate::utils::request_util::ClientInfo;
 use crate::services::marketplace::listing_service::ListingService;
 
 //MFA code validation route
@@ -19,6 +24,8 @@ pub struct MFARequest {
 pub struct MFALoginRequest {
     pub mfa_code: String,
     pub is_backup_code: bool,
+    #[serde(default)]
+    pub is_sms_code: bool,
     pub temp_token: String,
 }
 
@@ -35,16 +42,12 @@ async fn setup_mfa(
     
     match mfa_service.generate_user_secret(&user_id).await {
         Ok(_) => {}
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError(e))?;
-        }
+        Err(e) => return Err(e.into()),
     }
     
     match mfa_service.generate_qr_code(&user_id).await {
         Ok(qr_code_url) => Ok(HttpResponse::Ok().json(qr_code_url)),
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError(e))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -53,31 +56,84 @@ async fn setup_mfa(
 async fn enable_mfa(
     state: web::Data<AppState>,
     claims: Claims,
+    client: ClientInfo,
     request: web::Json<MFARequest>,
 ) -> Result<impl Responder, actix_web::Error> {
     let user_id = claims.sub;
     let mfa_code = &request.mfa_code;
     let mfa_service = MfaService::new(state.as_ref().clone());
     
-    match mfa_service.enable_mfa_for_user(&user_id, &mfa_code).await {
+    match mfa_service.enable_mfa_for_user(&user_id, &mfa_code, &client).await {
         Ok(_) => Ok(HttpResponse::Ok().finish()),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
-#[post("/verify")]
+#[post("/verify", wrap = "RateLimit::new(10, 60)")]
 async fn verify_mfa(
     state: web::Data<AppState>,
     claims: Claims,
+    client: ClientInfo,
     request: web::Json<MFALoginRequest>,
 ) -> Result<impl Responder, actix_web::Error> {
-    let user_id = claims.sub;
     let request = request.into_inner();
+    let auth_service = AuthService::new(state.as_ref().clone());
+
+    match auth_service.complete_mfa_login(&claims, request, &client).await {
+        Ok(session) => Ok(HttpResponse::Ok()
+            .cookie(CookieService::auth_cookie(&session.access_token))
+            .cookie(CookieService::refresh_cookie(&session.refresh_token))
+            .json(serde_json::json!({
+                "message": "Login successful",
+                "success": true,
+            }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+//SMS second factor routes
+#[post("/sms/send", wrap = "RateLimit::new(5, 60 * 60)")]
+async fn send_sms_code(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder, actix_web::Error> {
+    let phone_service = PhoneVerificationService::new(state.as_ref().clone());
+
+    match phone_service.send_mfa_code(claims.sub).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "message": "Verification code sent",
+            "success": true,
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/sms/enable")]
+async fn enable_sms_mfa(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+) -> Result<impl Responder, actix_web::Error> {
     let mfa_service = MfaService::new(state.as_ref().clone());
-    
-    match mfa_service.verify_code(&user_id, request).await {
-        Ok(_) => Ok(HttpResponse::Ok()),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+
+    match mfa_service.enable_sms_mfa_for_user(&claims.sub, &client).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/sms/disable")]
+async fn disable_sms_mfa(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    request: web::Json<MFARequest>,
+) -> Result<impl Responder, actix_web::Error> {
+    let mfa_service = MfaService::new(state.as_ref().clone());
+
+    match mfa_service.disable_sms_mfa_for_user(&claims.sub, request.into_inner(), &client).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -85,15 +141,16 @@ async fn verify_mfa(
 async fn disable_mfa(
     state: web::Data<AppState>,
     claims: Claims,
+    client: ClientInfo,
     request: web::Json<MFARequest>,
 ) -> Result<impl Responder, actix_web::Error> {
     let request = request.into_inner();
     let user_id = claims.sub;
     let mfa_service = MfaService::new(state.as_ref().clone());
     
-    match mfa_service.disable_mfa_for_user(&user_id, request).await {
+    match mfa_service.disable_mfa_for_user(&user_id, request, &client).await {
         Ok(_) => Ok(HttpResponse::Ok()),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
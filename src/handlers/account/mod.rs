@@ -1,3 +1,7 @@
 pub mod auth_handler;
 pub mod health_handler;
-pub mod mfa_handler;
\ No newline at end of file
+pub mod mfa_handler;
+pub mod session_handler;
+pub mod device_handler;
+pub mod phone_handler;
+pub mod oauth_handler;
\ No newline at end of file
@@ -0,0 +1,167 @@
+use actix_web::{delete, get, web, HttpRequest, HttpResponse, Responder, Result};
+use actix_web::http::header;
+use reqwest::Url;
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::handlers::ApiResponse;
+use crate::services::account::identity_service::IdentityService;
+use crate::services::account::jwt_service::Claims;
+use crate::services::integrations::cookie_service::CookieService;
+use crate::services::integrations::oauth_service::{OAuthProvider, OAuthService};
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Deserialize)]
+pub struct OAuthCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+//Sends the browser to the provider's sign-in page
+#[get("/oauth/{provider}/authorize")]
+pub async fn authorize(
+    state: web::Data<AppState>,
+    provider: web::Path<String>,
+) -> Result<impl Responder> {
+    let provider = OAuthProvider::from_name(&provider)?;
+    let oauth_service = OAuthService::new(state.as_ref().clone());
+
+    match oauth_service.begin(provider, None).await {
+        Ok(redirect) => Ok(HttpResponse::Found()
+            .insert_header((header::LOCATION, redirect.url))
+            .cookie(CookieService::oauth_flow_cookie(&redirect.flow_cookie))
+            .finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+//Same as authorize, but the callback links the provider to the signed-in account
+#[get("/oauth/{provider}/link")]
+pub async fn link(
+    state: web::Data<AppState>,
+    claims: Claims,
+    provider: web::Path<String>,
+) -> Result<impl Responder> {
+    let provider = OAuthProvider::from_name(&provider)?;
+    let oauth_service = OAuthService::new(state.as_ref().clone());
+
+    match oauth_service.begin(provider, Some(claims.sub)).await {
+        Ok(redirect) => Ok(HttpResponse::Found()
+            .insert_header((header::LOCATION, redirect.url))
+            .cookie(CookieService::oauth_flow_cookie(&redirect.flow_cookie))
+            .finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+//The provider redirects here; the browser is sent on to the frontend either way
+#[get("/oauth/{provider}/callback")]
+pub async fn callback(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    client: ClientInfo,
+    provider: web::Path<String>,
+    query: web::Query<OAuthCallbackQuery>,
+) -> Result<impl Responder> {
+    let provider = OAuthProvider::from_name(&provider)?;
+    let query = query.into_inner();
+
+    let (code, oauth_state) = match (query.code, query.state, query.error) {
+        (Some(code), Some(oauth_state), None) => (code, oauth_state),
+        (_, _, Some(error)) => return Ok(error_redirect(&format!("Sign-in was cancelled: {}", error))),
+        _ => return Ok(error_redirect("Invalid OAuth callback")),
+    };
+
+    let flow_cookie = match req.cookie("oauth_flow") {
+        Some(cookie) => cookie.value().to_string(),
+        None => return Ok(error_redirect("OAuth sign-in expired, please try again")),
+    };
+
+    let oauth_service = OAuthService::new(state.as_ref().clone());
+    let identity_service = IdentityService::new(state.as_ref().clone());
+
+    let (identity, flow) = match oauth_service.complete(provider, &flow_cookie, &code, &oauth_state).await {
+        Ok(result) => result,
+        Err(e) => return Ok(error_redirect(&e.public_message())),
+    };
+
+    if let Some(user_id) = flow.link_user_id {
+        return match identity_service.link_identity(user_id, identity).await {
+            Ok(_) => Ok(HttpResponse::Found()
+                .insert_header((header::LOCATION, app_url("settings/connections", &[("linked", provider.name())])))
+                .cookie(CookieService::clear_oauth_flow_cookie())
+                .finish()),
+            Err(e) => Ok(error_redirect(&e.public_message())),
+        };
+    }
+
+    match identity_service.login_with_identity(identity, &client).await {
+        Ok(authenticated_user) => {
+            let mfa_required = authenticated_user.refresh_token.is_none();
+
+            let mut response = HttpResponse::Found();
+            response.insert_header((header::LOCATION, app_url("oauth/complete", &[("mfa_required", &mfa_required.to_string())])))
+                .cookie(CookieService::clear_oauth_flow_cookie())
+                .cookie(CookieService::auth_cookie(&authenticated_user.token));
+
+            if let Some(refresh_token) = &authenticated_user.refresh_token {
+                response.cookie(CookieService::refresh_cookie(refresh_token));
+            }
+
+            Ok(response.finish())
+        }
+        Err(e) => Ok(error_redirect(&e.public_message())),
+    }
+}
+
+#[get("/identities")]
+pub async fn get_identities(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let identity_service = IdentityService::new(state.as_ref().clone());
+
+    match identity_service.get_identities(claims.sub).await {
+        Ok(identities) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Linked accounts retrieved successfully".to_string(),
+            data: Some(identities),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/identities/{id}")]
+pub async fn unlink_identity(
+    state: web::Data<AppState>,
+    claims: Claims,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let identity_service = IdentityService::new(state.as_ref().clone());
+
+    match identity_service.unlink_identity(claims.sub, id.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "Account unlinked successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn app_url(path: &str, params: &[(&str, &str)]) -> String {
+    let base = format!("{}/{}", Config::get().app_base_url.trim_end_matches('/'), path);
+
+    Url::parse_with_params(&base, params)
+        .map(|url| url.to_string())
+        .unwrap_or(base)
+}
+
+fn error_redirect(error: &str) -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, app_url("login", &[("oauth_error", error)])))
+        .cookie(CookieService::clear_oauth_flow_cookie())
+        .finish()
+}
@@ -0,0 +1,74 @@
+use actix_web::{post, web, HttpResponse, Responder, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::middleware::rate_limit_middleware::RateLimit;
+use crate::services::account::jwt_service::Claims;
+use crate::services::account::phone_verification_service::PhoneVerificationService;
+
+#[derive(Deserialize)]
+pub struct StartPhoneVerificationRequest {
+    #[serde(rename = "phoneNumber")]
+    pub phone_number: String,
+    /// A code sent to the current number via `/mfa/sms/send`, needed to change the number
+    /// while SMS verification is on.
+    #[serde(rename = "mfaCode", default)]
+    pub mfa_code: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmPhoneVerificationRequest {
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct PhoneResponse {
+    pub phone_number: Option<String>,
+    pub phone_verified: bool,
+    pub phone_verified_at: Option<DateTime<Utc>>,
+}
+
+#[post("/verify", wrap = "RateLimit::new(5, 60 * 60)")]
+pub async fn start_phone_verification(
+    state: web::Data<AppState>,
+    claims: Claims,
+    request: web::Json<StartPhoneVerificationRequest>,
+) -> Result<impl Responder> {
+    let phone_service = PhoneVerificationService::new(state.as_ref().clone());
+
+    match phone_service.start_phone_verification(
+        claims.sub,
+        &request.phone_number,
+        request.mfa_code.as_deref(),
+    ).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "Verification code sent".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/verify/confirm", wrap = "RateLimit::new(10, 60)")]
+pub async fn confirm_phone_verification(
+    state: web::Data<AppState>,
+    claims: Claims,
+    request: web::Json<ConfirmPhoneVerificationRequest>,
+) -> Result<impl Responder> {
+    let phone_service = PhoneVerificationService::new(state.as_ref().clone());
+
+    match phone_service.confirm_phone_verification(claims.sub, &request.code).await {
+        Ok(user) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Phone number verified successfully".to_string(),
+            data: Some(PhoneResponse {
+                phone_number: user.phone_number,
+                phone_verified: user.phone_verified,
+                phone_verified_at: user.phone_verified_at,
+            }),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,63 @@
+use actix_web::{delete, get, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::account::session_service::SessionService;
+use crate::services::integrations::cookie_service::CookieService;
+
+#[get("")]
+pub async fn get_sessions(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let session_service = SessionService::new(state.as_ref().clone());
+
+    match session_service.get_active_sessions(claims.sub, claims.sid).await {
+        Ok(sessions) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Sessions retrieved successfully".to_string(),
+            data: Some(sessions),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/{id}")]
+pub async fn revoke_session(
+    state: web::Data<AppState>,
+    claims: Claims,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let session_id = id.into_inner();
+    let session_service = SessionService::new(state.as_ref().clone());
+
+    match session_service.revoke_session(claims.sub, session_id, "revoked").await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "Session revoked successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("")]
+pub async fn revoke_all_sessions(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let session_service = SessionService::new(state.as_ref().clone());
+
+    match session_service.revoke_all_sessions(claims.sub, "revoked").await {
+        Ok(count) => Ok(HttpResponse::Ok()
+            .cookie(CookieService::logout_cookie())
+            .cookie(CookieService::clear_refresh_cookie())
+            .json(ApiResponse {
+                success: true,
+                message: "All sessions revoked successfully".to_string(),
+                data: Some(serde_json::json!({ "revoked": count })),
+            })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,86 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Debug, Deserialize)]
+pub struct AuditEventQuery {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub request_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[get("")]
+pub async fn search_audit_events(
+    state: web::Data<AppState>,
+    query: web::Query<AuditEventQuery>,
+) -> Result<impl Responder> {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let audit_service = AuditService::new(state.as_ref().clone());
+
+    match audit_service.search(&query, offset, limit).await {
+        Ok(result) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Audit events retrieved successfully".to_string(),
+            data: Some(serde_json::json!({
+                "events": result.events,
+                "pagination": {
+                    "total": result.total,
+                    "offset": offset,
+                    "limit": limit,
+                    "more": offset + limit < result.total
+                }
+            })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Exports are themselves audited, since the log holds IP addresses.
+#[get("/export")]
+pub async fn export_audit_events(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    query: web::Query<AuditEventQuery>,
+) -> Result<impl Responder> {
+    let audit_service = AuditService::new(state.as_ref().clone());
+
+    let csv = audit_service.export(&query).await?;
+
+    audit_service.record(AuditEvent {
+        actor_id: Some(claims.sub),
+        client: &client,
+        action: "audit.exported",
+        target_type: "audit_log",
+        target_id: None,
+        old_values: None,
+        new_values: None,
+        metadata: Some(serde_json::json!({
+            "actor_id": query.actor_id,
+            "action": query.action,
+            "target_type": query.target_type,
+            "target_id": query.target_id,
+            "request_id": query.request_id,
+            "from": query.from,
+            "to": query.to,
+        })),
+    }).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(("Content-Disposition", "attachment; filename=\"audit-events.csv\""))
+        .body(csv))
+}
@@ -0,0 +1,56 @@
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use crate::app_state::AppState;
+use crate::errors::AppError;
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::marketplace::catalogue_import_service::{CatalogueImportService, ImportFormat};
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Deserialize)]
+pub struct CatalogueImportQuery {
+    pub format: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Takes the set list as the raw request body. The format comes from `?format=` or, failing
+/// that, the content type.
+#[post("/import")]
+pub async fn import_catalogue(
+    state: web::Data<AppState>,
+    claims: Claims,
+    req: HttpRequest,
+    query: web::Query<CatalogueImportQuery>,
+    body: web::Bytes,
+) -> Result<impl Responder> {
+    let content_type = req.headers()
+        .get(actix_web::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+
+    let format = match query.format.as_deref() {
+        Some(format) => ImportFormat::from_name(format),
+        None if content_type.starts_with("text/csv") => Some(ImportFormat::Csv),
+        None if content_type.starts_with("application/json") => Some(ImportFormat::Json),
+        None => None,
+    }.ok_or_else(|| AppError::field("format", "Format must be csv or json"))?;
+
+    let rows = CatalogueImportService::parse(&body, format)?;
+
+    let import_service = CatalogueImportService::new(state.as_ref().clone());
+
+    let client = ClientInfo::from_http_request(&req);
+
+    match import_service.import(Some(claims.sub), &client, rows, query.dry_run).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: report.failed == 0,
+            message: format!(
+                "{} rows processed: {} created, {} updated, {} unchanged, {} failed",
+                report.total_rows, report.created, report.updated, report.unchanged, report.failed
+            ),
+            data: Some(report),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
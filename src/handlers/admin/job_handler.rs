@@ -0,0 +1,79 @@
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::jobs::job_service::JobService;
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Deserialize)]
+pub struct JobQuery {
+    pub status: Option<String>,
+    pub kind: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[get("")]
+pub async fn list_jobs(
+    state: web::Data<AppState>,
+    query: web::Query<JobQuery>,
+) -> Result<impl Responder> {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let job_service = JobService::new(state.as_ref().clone());
+
+    match job_service.list_jobs(query.status.as_deref(), query.kind.as_deref(), offset, limit).await {
+        Ok(result) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Jobs retrieved successfully".to_string(),
+            data: Some(serde_json::json!({
+                "jobs": result.jobs,
+                "pagination": {
+                    "total": result.total,
+                    "offset": offset,
+                    "limit": limit,
+                    "more": offset + limit < result.total
+                }
+            })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/{id}")]
+pub async fn get_job(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let job_service = JobService::new(state.as_ref().clone());
+
+    match job_service.get_job(id.into_inner()).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Job retrieved successfully".to_string(),
+            data: Some(job),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/retry")]
+pub async fn retry_job(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let job_service = JobService::new(state.as_ref().clone());
+
+    match job_service.retry_job(claims.sub, &client, id.into_inner()).await {
+        Ok(job) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Job queued for retry".to_string(),
+            data: Some(job),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod user_admin_handler;
+pub mod catalogue_import_handler;
+pub mod job_handler;
+pub mod audit_handler;
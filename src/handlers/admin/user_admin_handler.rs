@@ -0,0 +1,189 @@
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::admin::user_admin_service::UserAdminService;
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Deserialize)]
+pub struct UserSearchQuery {
+    pub q: Option<String>,
+    pub status: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct AccountStatusRequest {
+    pub status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct GrantRoleRequest {
+    pub role: String,
+}
+
+#[get("")]
+pub async fn search_users(
+    state: web::Data<AppState>,
+    query: web::Query<UserSearchQuery>,
+) -> Result<impl Responder> {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.search_users(query.q.as_deref(), query.status.as_deref(), offset, limit).await {
+        Ok(result) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Users retrieved successfully".to_string(),
+            data: Some(serde_json::json!({
+                "users": result.users,
+                "pagination": {
+                    "total": result.total,
+                    "offset": offset,
+                    "limit": limit,
+                    "more": offset + limit < result.total
+                }
+            })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/{id}")]
+pub async fn get_user(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.get_user_profile(id.into_inner()).await {
+        Ok(profile) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "User retrieved successfully".to_string(),
+            data: Some(profile),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/status")]
+pub async fn set_account_status(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+    request: web::Json<AccountStatusRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.set_account_status(claims.sub, &client, id.into_inner(), &request.status, request.reason).await {
+        Ok(user) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Account status updated successfully".to_string(),
+            data: Some(user),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/unlock")]
+pub async fn unlock_user(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.unlock_user(claims.sub, &client, id.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "User unlocked successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/logout")]
+pub async fn force_logout(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.force_logout(claims.sub, &client, id.into_inner()).await {
+        Ok(count) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "User sessions revoked successfully".to_string(),
+            data: Some(serde_json::json!({ "revoked": count })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/roles")]
+pub async fn grant_role(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+    request: web::Json<GrantRoleRequest>,
+) -> Result<impl Responder> {
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.grant_role(claims.sub, &client, id.into_inner(), &request.role).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "Role granted successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/{id}/roles/{role}")]
+pub async fn revoke_role(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    path: web::Path<(Uuid, String)>,
+) -> Result<impl Responder> {
+    let (user_id, role) = path.into_inner();
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.revoke_role(claims.sub, &client, user_id, &role).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "Role revoked successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/mfa/reset")]
+pub async fn reset_mfa(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let user_admin_service = UserAdminService::new(state.as_ref().clone());
+
+    match user_admin_service.reset_mfa(claims.sub, &client, id.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(ApiResponse::<()> {
+            success: true,
+            message: "MFA reset successfully".to_string(),
+            data: None,
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,6 +1,7 @@
 use actix_web::{get, web, HttpResponse, Responder, Result};
 use serde::{Deserialize, Serialize};
 use crate::app_state::AppState;
+use crate::errors::AppError;
 use crate::handlers::ApiResponse;
 use crate::services::integrations::meilisearch_service::{MeilisearchService, SearchableListing, SearchableProduct};
 
@@ -45,11 +46,7 @@ pub async fn quick_search(
     let query_params = query.into_inner();
 
     if query_params.q.trim().is_empty() {
-        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()> {
-            success: false,
-            message: "Search query cannot be empty".to_string(),
-            data: None,
-        }));
+        return Err(AppError::field("q", "Search query cannot be empty").into());
     }
 
     if query_params.q.len() < 2 {
@@ -100,13 +97,7 @@ pub async fn quick_search(
                 processing_time_ms: processing_time,
             }))
         }
-        (Err(e), _) | (_, Err(e)) => {
-            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                success: false,
-                message: format!("Search failed: {}", e),
-                data: None,
-            }))
-        }
+        (Err(e), _) | (_, Err(e)) => Err(e.into()),
     }
 }
 
@@ -173,13 +164,7 @@ pub async fn search_products(
                 }),
             }))
         }
-        Err(e) => {
-            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                success: false,
-                message: format!("Product search failed: {}", e),
-                data: None,
-            }))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -189,12 +174,9 @@ pub async fn get_trending_products(
 ) -> Result<impl Responder> {
     let search_service = MeilisearchService::new(state.as_ref().clone());
 
-    match search_service.search_products_trending(
-        0,
-        10
-    ).await {
+    match search_service.get_trending_products().await {
         Ok(products) => {
-            let hits: Vec<_> = products.hits.into_iter().map(|hit| hit.result).collect();
+            let hits = products.hits;
             let hits_count = hits.len();
             let estimated_total_hits = products.estimated_total_hits.unwrap_or(hits.len());
 
@@ -212,13 +194,7 @@ pub async fn get_trending_products(
                 }),
             }))
         }
-        Err(e) => {
-            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()> {
-                success: false,
-                message: format!("Failed to retrieve trending products: {}", e),
-                data: None,
-            }))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -1,2 +1,3 @@
 pub mod r2_handler;
-pub mod meilisearch_handler;
\ No newline at end of file
+pub mod meilisearch_handler;
+pub mod stripe_handler;
\ No newline at end of file
@@ -0,0 +1,33 @@
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder, Result};
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::errors::AppError;
+use crate::services::integrations::stripe_webhook_service::StripeWebhookService;
+
+#[post("/stripe")]
+pub async fn stripe_webhook(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Bytes,
+) -> Result<impl Responder> {
+    let signature = req.headers()
+        .get("Stripe-Signature")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::validation("Missing Stripe-Signature header"))?;
+
+    let event = StripeWebhookService::construct_event(
+        &payload,
+        signature,
+        &Config::get().stripe_webhook_secret,
+    )?;
+
+    let webhook_service = StripeWebhookService::new(state.as_ref().clone());
+
+    match webhook_service.process_event(event).await {
+        Ok(processed) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "received": true,
+            "duplicate": !processed,
+        }))),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,250 @@
+use actix_web::{delete, patch, post, put, web, HttpResponse, Responder, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+use crate::app_state::AppState;
+use crate::errors::AppError;
+use crate::handlers::ApiResponse;
+use crate::handlers::marketplace::product_handler::UpdateProductRequest;
+use crate::services::account::jwt_service::Claims;
+use crate::services::marketplace::catalogue_service::CatalogueService;
+use crate::services::marketplace::product_service::ProductService;
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct GameRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    #[validate(length(max = 2000, message = "Description cannot exceed 2000 characters"))]
+    pub description: Option<String>,
+    #[validate(length(max = 100))]
+    pub publisher: Option<String>,
+    pub release_date: Option<NaiveDate>,
+    #[validate(url(message = "Must be a valid URL"))]
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct SetRequest {
+    #[validate(length(min = 1, max = 100, message = "Game is required"))]
+    pub game_name: String,
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    #[validate(length(max = 2000, message = "Description cannot exceed 2000 characters"))]
+    pub description: Option<String>,
+    pub release_date: Option<NaiveDate>,
+    #[validate(url(message = "Must be a valid URL"))]
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct UpdateVariantRequest {
+    #[validate(length(min = 1, max = 100, message = "Variant name is required"))]
+    pub name: Option<String>,
+    #[validate(length(max = 50))]
+    pub set_number: Option<String>,
+    pub is_primary: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReorderVariantsRequest {
+    pub variant_ids: Vec<i32>,
+    pub primary_variant_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MergeProductsRequest {
+    pub duplicate_id: Uuid,
+}
+
+#[post("/games")]
+pub async fn create_game(
+    state: web::Data<AppState>,
+    request: web::Json<GameRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    request.validate().map_err(AppError::from)?;
+
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.create_game(request).await {
+        Ok(game) => Ok(HttpResponse::Created().json(ApiResponse {
+            success: true,
+            message: "Game created successfully".to_string(),
+            data: Some(game),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[put("/games/{id}")]
+pub async fn update_game(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+    request: web::Json<GameRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    request.validate().map_err(AppError::from)?;
+
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.update_game(id.into_inner(), request).await {
+        Ok(game) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Game updated successfully".to_string(),
+            data: Some(game),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/games/{id}")]
+pub async fn delete_game(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.delete_game(id.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/sets")]
+pub async fn create_set(
+    state: web::Data<AppState>,
+    request: web::Json<SetRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    request.validate().map_err(AppError::from)?;
+
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.create_set(request).await {
+        Ok(set) => Ok(HttpResponse::Created().json(ApiResponse {
+            success: true,
+            message: "Set created successfully".to_string(),
+            data: Some(set),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[put("/sets/{id}")]
+pub async fn update_set(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+    request: web::Json<SetRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    request.validate().map_err(AppError::from)?;
+
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.update_set(id.into_inner(), request).await {
+        Ok(set) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Set updated successfully".to_string(),
+            data: Some(set),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/sets/{id}")]
+pub async fn delete_set(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.delete_set(id.into_inner()).await {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[patch("/products/{id}")]
+pub async fn update_product(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+    request: web::Json<UpdateProductRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    request.validate().map_err(AppError::from)?;
+
+    let product_service = ProductService::new(state.as_ref().clone());
+
+    match product_service.update_product(claims.sub, &client, id.into_inner(), request).await {
+        Ok(product) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Product updated successfully".to_string(),
+            data: Some(product),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/products/{id}/merge")]
+pub async fn merge_products(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+    request: web::Json<MergeProductsRequest>,
+) -> Result<impl Responder> {
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.merge_products(claims.sub, &client, id.into_inner(), request.duplicate_id).await {
+        Ok(product) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Products merged successfully".to_string(),
+            data: Some(product),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[patch("/products/{id}/variants/{variant_id}")]
+pub async fn update_variant(
+    state: web::Data<AppState>,
+    path: web::Path<(Uuid, i32)>,
+    request: web::Json<UpdateVariantRequest>,
+) -> Result<impl Responder> {
+    let (product_id, variant_id) = path.into_inner();
+    let request = request.into_inner();
+    request.validate().map_err(AppError::from)?;
+
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.update_variant(product_id, variant_id, request).await {
+        Ok(variant) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Variant updated successfully".to_string(),
+            data: Some(variant),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[put("/products/{id}/variants/order")]
+pub async fn reorder_variants(
+    state: web::Data<AppState>,
+    id: web::Path<Uuid>,
+    request: web::Json<ReorderVariantsRequest>,
+) -> Result<impl Responder> {
+    let catalogue_service = CatalogueService::new(state.as_ref().clone());
+
+    match catalogue_service.reorder_variants(id.into_inner(), request.into_inner()).await {
+        Ok(variants) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Variants reordered successfully".to_string(),
+            data: Some(variants),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
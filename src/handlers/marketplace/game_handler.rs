@@ -17,7 +17,7 @@ pub async fn get_game(
                 Ok(HttpResponse::NotFound().body("Game not found"))
             }
         }
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -29,7 +29,7 @@ pub async fn get_games(
 
     match game_service.get_games().await {
         Ok(games) => Ok(HttpResponse::Ok().json(games)),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -42,7 +42,7 @@ pub async fn get_sets_by_game(
 
     match game_service.get_sets_by_game(&name).await {
         Ok(sets) => Ok(HttpResponse::Ok().json(sets)),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -5,10 +5,14 @@ use actix_web::{Responder, Result, web, post, get, delete};
 use futures_util::TryStreamExt;
 use uuid::Uuid;
 use crate::app_state::AppState;
+use crate::errors::AppError;
 use crate::handlers::ApiResponse;
 use crate::services::account::jwt_service::Claims;
+use crate::middleware::rate_limit_middleware::RateLimit;
+use crate::services::marketplace::listing_import_service::ListingImportService;
 use crate::services::marketplace::listing_service::ListingService;
 use crate::services::marketplace::product_service::ProductService;
+use crate::utils::request_util::ClientInfo;
 
 #[derive(Deserialize)]
 pub struct CreateListingRequest {
@@ -30,22 +34,64 @@ pub struct UpdateListingRequest {
     pub description: Option<String>,
 }
 
+/// `condition` and `status` take comma-separated lists. Prices are in minor units.
+#[derive(Debug, Deserialize)]
+pub struct ListingBrowseQuery {
+    pub condition: Option<String>,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    pub seller_id: Option<Uuid>,
+    pub product_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub game: Option<String>,
+    pub set: Option<String>,
+    pub sort: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<u64>,
+}
+
+#[get("")]
+pub async fn browse_listings(
+    state: web::Data<AppState>,
+    query: web::Query<ListingBrowseQuery>,
+) -> Result<impl Responder> {
+    let limit = query.limit.unwrap_or(24).clamp(1, 100);
+    let listing_service = ListingService::new(state.as_ref().clone());
+
+    match listing_service.browse_listings(&query, limit).await {
+        Ok(page) => Ok(actix_web::HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Listings retrieved successfully".to_string(),
+            data: Some(serde_json::json!({
+                "listings": page.listings,
+                "pagination": {
+                    "limit": limit,
+                    "next_cursor": page.next_cursor,
+                    "more": page.next_cursor.is_some()
+                }
+            })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
 #[post("")]
 pub async fn create_listing(
     state: web::Data<AppState>,
     claims: Claims,
+    client: ClientInfo,
     request: web::Json<CreateListingRequest>,
 ) -> Result<impl Responder> {
     let request = request.into_inner();
     let listing_service = ListingService::new(state.as_ref().clone());
 
-    match listing_service.create_listing(claims.sub, request).await {
+    match listing_service.create_listing(claims.sub, &client, request).await {
         Ok(listing) => Ok(actix_web::HttpResponse::Ok().json(ApiResponse {
             success: true,
             message: "Listing created successfully".to_string(),
             data: Some(listing),
         })),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
 
     }
 }
@@ -54,14 +100,15 @@ pub async fn create_listing(
 pub async fn update_listing(
     state: web::Data<AppState>,
     claims: Claims,
+    client: ClientInfo,
     request: web::Json<UpdateListingRequest>,
 ) -> Result<impl Responder> {
     let request = request.into_inner();
     let listing_service = ListingService::new(state.as_ref().clone());
 
-    match listing_service.update_listing(claims.sub, request).await {
+    match listing_service.update_listing(claims.sub, &client, request).await {
         Ok(listing) => Ok(actix_web::HttpResponse::Ok().json(listing)),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -75,7 +122,7 @@ pub async fn get_listing(
 
     match listing_service.get_listing(id).await {
         Ok(listing) => Ok(actix_web::HttpResponse::Ok().json(listing)),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError("Fail to get listing by id")),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -83,14 +130,81 @@ pub async fn get_listing(
 pub async fn delete_listing(
     state: web::Data<AppState>,
     claims: Claims,
+    client: ClientInfo,
     id: web::Path<Uuid>,
 ) -> Result<impl Responder> {
     let id = id.into_inner();
     let listing_service = ListingService::new(state.as_ref().clone());
 
-    match listing_service.delete_listing(claims.sub, id).await {
+    match listing_service.delete_listing(claims.sub, &client, id).await {
         Ok(true) => Ok(actix_web::HttpResponse::NoContent().finish()),
-        Ok(false) => Err(actix_web::error::ErrorNotFound("Listing has already been deleted")),
-        Err(_) => Err(actix_web::error::ErrorNotFound("Listing not found")),
+        Ok(false) => Err(AppError::NotFound("Listing has already been deleted".to_string()).into()),
+        Err(e) => Err(e.into()),
     }
-}
\ No newline at end of file
+}
+
+#[post("/{id}/renew")]
+pub async fn renew_listing(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let id = id.into_inner();
+    let listing_service = ListingService::new(state.as_ref().clone());
+
+    match listing_service.renew_listing(claims.sub, &client, id).await {
+        Ok(listing) => Ok(actix_web::HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Listing renewed successfully".to_string(),
+            data: Some(listing),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListingImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Takes the seller's inventory as a CSV request body and answers with a per-row report.
+#[post("/import", wrap = "RateLimit::new(10, 60 * 60)")]
+pub async fn import_listings(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    query: web::Query<ListingImportQuery>,
+    body: web::Bytes,
+) -> Result<impl Responder> {
+    let import_service = ListingImportService::new(state.as_ref().clone());
+
+    match import_service.import(claims.sub, &client, &body, query.dry_run).await {
+        Ok(report) => Ok(actix_web::HttpResponse::Ok().json(ApiResponse {
+            success: report.failed == 0,
+            message: format!(
+                "{} rows processed: {} created, {} updated, {} deleted, {} unchanged, {} failed",
+                report.total_rows, report.created, report.updated, report.deleted, report.unchanged, report.failed
+            ),
+            data: Some(report),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/export")]
+pub async fn export_listings(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let import_service = ListingImportService::new(state.as_ref().clone());
+
+    match import_service.export(claims.sub).await {
+        Ok(csv) => Ok(actix_web::HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(("Content-Disposition", "attachment; filename=\"listings.csv\""))
+            .body(csv)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,3 +1,5 @@
 pub mod listing_handler;
 pub mod product_handler;
-pub mod game_handler;
\ No newline at end of file
+pub mod game_handler;
+pub mod seller_handler;
+pub mod catalogue_handler;
\ No newline at end of file
@@ -8,10 +8,13 @@ use uuid::Uuid;
 use validator::Validate;
 use crate::app_state::AppState;
 use crate::entities::{product_variants, products};
+use crate::errors::AppError;
 use crate::handlers::ApiResponse;
+use crate::middleware::permission_middleware::RequirePermission;
 use crate::services::account::jwt_service::Claims;
 use crate::services::marketplace::listing_service::ListingService;
 use crate::services::marketplace::product_service::ProductService;
+use crate::utils::request_util::ClientInfo;
 
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct CreateProductRequest {
@@ -34,6 +37,25 @@ pub struct CreateProductRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct UpdateProductRequest {
+    #[validate(length(min = 1, max = 255, message = "Name must be between 1 and 255 characters"))]
+    pub name: Option<String>,
+    #[validate(length(max = 2000, message = "Description cannot exceed 2000 characters"))]
+    pub description: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "Category is required"))]
+    pub category: Option<String>,
+    #[validate(length(max = 100, message = "Subcategory cannot exceed 100 characters"))]
+    pub subcategory: Option<String>,
+    #[validate(length(min = 1, max = 100, message = "Game is required"))]
+    pub game: Option<String>,
+    #[validate(length(max = 100))]
+    pub set: Option<String>,
+    #[validate(url(message = "Must be a valid URL"))]
+    pub image_url: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Validate)]
 pub struct CreateVariantRequest {
     #[validate(length(min = 1, max = 100, message = "Variant name is required"))]
@@ -83,15 +105,17 @@ pub struct VariantResponse {
     pub created_at: DateTime<Utc>,
 }
 
-#[post("")]
+#[post("", wrap = "RequirePermission::new(\"product:write\")")]
 pub async fn create_product(
     state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
     request: web::Json<CreateProductRequest>,
 ) -> Result<impl Responder> {
     let request = request.into_inner();
     let product_service = ProductService::new(state.as_ref().clone());
 
-    match product_service.create_product(request).await {
+    match product_service.create_product(claims.sub, &client, request).await {
         Ok(product) => Ok(HttpResponse::Ok().json(
             serde_json::json!({
                 "success": true,
@@ -99,11 +123,11 @@ pub async fn create_product(
                 "data": product,
             })
         )),
-        Err(e) => Err(actix_web::error::ErrorInternalServerError(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
-#[post("/{product_id}/variants")]
+#[post("/{product_id}/variants", wrap = "RequirePermission::new(\"product:write\")")]
 pub async fn create_product_variants(
     state: web::Data<AppState>,
     path: web::Path<(Uuid)>,
@@ -122,7 +146,7 @@ pub async fn create_product_variants(
                 "variants": variants,
             })
         )),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(format!("Error: {}", e))),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -142,13 +166,11 @@ pub async fn get_product_variants(
                 data: Some(variants),
             }))
         },
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError("Failed to get product variants"))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
-#[post("/{product_id}/images")]
+#[post("/{product_id}/images", wrap = "RequirePermission::new(\"product:write\")")]
 pub async fn upload_product_images(
     state: web::Data<AppState>,
     path: web::Path<Uuid>,
@@ -159,12 +181,7 @@ pub async fn upload_product_images(
 
     let upload_request = match parse_image_upload_form(payload).await {
         Ok(request) => request,
-        Err(e) => return Ok(HttpResponse::BadRequest().json(
-            serde_json::json!({
-                "success": false,
-                "message": format!("Failed to parse upload form: {}", e),
-            })
-        )),
+        Err(e) => return Err(AppError::validation(format!("Failed to parse upload form: {}", e)).into()),
     };
 
     let product_service = ProductService::new(state.as_ref().clone());
@@ -176,12 +193,7 @@ pub async fn upload_product_images(
                 "message": "Images uploaded successfully",
             })
         )),
-        Err(e) => Ok(HttpResponse::InternalServerError().json(
-            serde_json::json!({
-                "success": false,
-                "message": format!("Upload failed: {}", e),
-            })
-        )),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -201,26 +213,24 @@ pub async fn get_product_listings(
                 data: Some(listings),
             }))
         },
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError("Failed to get product listings"))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
-#[delete("/{id}")]
+#[delete("/{id}", wrap = "RequirePermission::new(\"product:write\")")]
 pub async fn delete_product(
     state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
     id: web::Path<uuid::Uuid>,
 ) -> Result<impl Responder> {
     let id = id.into_inner();
     let product_service = ProductService::new(state.as_ref().clone());
     
-    match product_service.delete_product(id).await {
+    match product_service.delete_product(claims.sub, &client, id).await {
         Ok(true) => Ok(actix_web::HttpResponse::NoContent().finish()),
-        Ok(false) => Err(actix_web::error::ErrorNotFound("Product not found")),
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError("Product failed to delete"))
-        }
+        Ok(false) => Err(AppError::NotFound("Product not found".to_string()).into()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -239,19 +249,14 @@ pub async fn get_products(
     let limit = query.limit.unwrap_or(10);
     let product_service = ProductService::new(state.as_ref().clone());
 
-    let total_number = product_service.get_number_of_products()
-        .await
-        .map_err(|e| {
-            println!("Failed to get total number of products: {}", e);
-            actix_web::error::ErrorInternalServerError("Failed to get total number of products")
-        })?;
+    let total_number = product_service.get_number_of_products().await?;
 
     if limit == 0 {
-        return Err(actix_web::error::ErrorBadRequest("Limit must be greater than 0"));
+        return Err(AppError::field("limit", "Limit must be greater than 0").into());
     }
 
     if offset > total_number {
-        return Err(actix_web::error::ErrorBadRequest("Offset exceeds total number of products"));
+        return Err(AppError::field("offset", "Offset exceeds total number of products").into());
     }
 
     match product_service.get_products(offset, limit).await {
@@ -265,10 +270,7 @@ pub async fn get_products(
                     "more": offset + limit < total_number
                 })
             ))},
-        Err(e) => {
-            println!("Failed to get products: {}", e);
-            Err(actix_web::error::ErrorInternalServerError("Products failed to query"))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -288,13 +290,8 @@ pub async fn get_product_by_id(
                 data: Some(product),
             }))
         },
-        Ok(None) => {
-            Err(actix_web::error::ErrorNotFound("Product not found"))
-        },
-        Err(e) => {
-            println!("Failed to get product by ID: {}", e);
-            Err(actix_web::error::ErrorInternalServerError("Failed to get product"))
-        }
+        Ok(None) => Err(AppError::NotFound("Product not found".to_string()).into()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -306,9 +303,7 @@ pub async fn get_number_of_products(
 
     match product_service.get_number_of_products().await {
         Ok(count) => Ok(actix_web::HttpResponse::Ok().json(count)),
-        Err(e) => {
-            Err(actix_web::error::ErrorInternalServerError("Failed to count products"))
-        }
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use crate::app_state::AppState;
+use crate::handlers::ApiResponse;
+use crate::handlers::marketplace::listing_handler::ListingBrowseQuery;
+use crate::services::account::jwt_service::Claims;
+use crate::services::marketplace::seller_service::SellerService;
+
+#[derive(Debug, Serialize)]
+pub struct SellerOnboardingStatus {
+    pub stripe_account_id: Option<String>,
+    pub verified_seller: bool,
+    pub charges_enabled: bool,
+    pub payouts_enabled: bool,
+    pub details_submitted: bool,
+}
+
+/// `status` takes a comma-separated list, e.g. `active,expired`.
+#[derive(Debug, Deserialize)]
+pub struct SellerListingsQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SellerSalesQuery {
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[post("/onboarding")]
+pub async fn start_onboarding(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let seller_service = SellerService::new(state.as_ref().clone());
+
+    match seller_service.start_onboarding(&claims.sub).await {
+        Ok(link) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Onboarding link created successfully".to_string(),
+            data: Some(serde_json::json!({
+                "url": link.url,
+                "expires_at": link.expires_at,
+            })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/onboarding")]
+pub async fn get_onboarding_status(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let seller_service = SellerService::new(state.as_ref().clone());
+
+    match seller_service.get_onboarding_status(&claims.sub).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Onboarding status retrieved successfully".to_string(),
+            data: Some(status),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/listings")]
+pub async fn get_my_listings(
+    state: web::Data<AppState>,
+    claims: Claims,
+    query: web::Query<SellerListingsQuery>,
+) -> Result<impl Responder> {
+    let seller_service = SellerService::new(state.as_ref().clone());
+
+    match seller_service.get_my_listings(claims.sub, query.status.as_deref()).await {
+        Ok(listings) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Listings retrieved successfully".to_string(),
+            data: Some(listings),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/sales")]
+pub async fn get_my_sales(
+    state: web::Data<AppState>,
+    claims: Claims,
+    query: web::Query<SellerSalesQuery>,
+) -> Result<impl Responder> {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let seller_service = SellerService::new(state.as_ref().clone());
+
+    match seller_service.get_my_sales(claims.sub, offset, limit).await {
+        Ok(result) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Sales retrieved successfully".to_string(),
+            data: Some(serde_json::json!({
+                "sales": result.sales,
+                "totals": result.totals,
+                "pagination": {
+                    "total": result.total,
+                    "offset": offset,
+                    "limit": limit,
+                    "more": offset + limit < result.total
+                }
+            })),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Takes the same filters, sorting and cursor as the listing browse endpoint, always
+/// scoped to the seller's active listings.
+#[get("/{username}")]
+pub async fn get_storefront(
+    state: web::Data<AppState>,
+    username: web::Path<String>,
+    query: web::Query<ListingBrowseQuery>,
+) -> Result<impl Responder> {
+    let limit = query.limit.unwrap_or(24).clamp(1, 100);
+    let seller_service = SellerService::new(state.as_ref().clone());
+
+    match seller_service.get_storefront(&username, query.into_inner(), limit).await {
+        Ok(storefront) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Storefront retrieved successfully".to_string(),
+            data: Some(storefront),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
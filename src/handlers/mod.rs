@@ -1,6 +1,8 @@
 
 use actix_web::web;
 use serde::{Deserialize, Serialize};
+use crate::middleware::admin_middleware::AdminMiddleware;
+use crate::middleware::permission_middleware::RequirePermission;
 
 pub mod account;
 pub mod marketplace;
@@ -15,8 +17,17 @@ pub struct ApiResponse<T> {
     pub data: Option<T>,
 }
 
+/// Set lists for large sets run to a few megabytes.
+const MAX_CATALOGUE_IMPORT_BYTES: usize = 20 * 1024 * 1024;
+
+const MAX_LISTING_IMPORT_BYTES: usize = 5 * 1024 * 1024;
+
 pub fn configure_admin_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::scope("/api/v1/admin").configure(admin_routes));
+    cfg.service(
+        web::scope("/api/v1/admin")
+            .wrap(AdminMiddleware)
+            .configure(admin_routes)
+    );
 }
 
 pub fn configure_private_routes(cfg: &mut web::ServiceConfig) {
@@ -27,26 +38,83 @@ pub fn configure_public_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(web::scope("/api/v1/public").configure(public_routes));
 }
 
-fn admin_routes(cfg: &mut web::ServiceConfig) {}
+fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/users")
+            .service(admin::user_admin_handler::search_users)
+            .service(admin::user_admin_handler::get_user)
+            .service(admin::user_admin_handler::set_account_status)
+            .service(admin::user_admin_handler::unlock_user)
+            .service(admin::user_admin_handler::force_logout)
+            .service(admin::user_admin_handler::grant_role)
+            .service(admin::user_admin_handler::revoke_role)
+            .service(admin::user_admin_handler::reset_mfa)
+    )
+    .service(
+        web::scope("/catalogue")
+            .app_data(web::PayloadConfig::new(MAX_CATALOGUE_IMPORT_BYTES))
+            .service(admin::catalogue_import_handler::import_catalogue)
+    )
+    .service(
+        web::scope("/jobs")
+            .service(admin::job_handler::list_jobs)
+            .service(admin::job_handler::get_job)
+            .service(admin::job_handler::retry_job)
+    )
+    .service(
+        web::scope("/audit")
+            .service(admin::audit_handler::search_audit_events)
+            .service(admin::audit_handler::export_audit_events)
+    );
+}
 
 fn private_routes(cfg: &mut web::ServiceConfig) {
         cfg.service(
             web::scope("/auth")
                 .service(account::auth_handler::get_current_user)
                 .service(account::auth_handler::logout)
+                .service(account::auth_handler::change_password)
+                .service(account::auth_handler::resend_verification_email)
+                .service(account::oauth_handler::link)
+                .service(account::oauth_handler::get_identities)
+                .service(account::oauth_handler::unlink_identity)
+        )
+        .service(
+            web::scope("/sessions")
+                .service(account::session_handler::get_sessions)
+                .service(account::session_handler::revoke_all_sessions)
+                .service(account::session_handler::revoke_session)
+        )
+        .service(
+            web::scope("/devices")
+                .service(account::device_handler::get_devices)
+                .service(account::device_handler::trust_device)
+                .service(account::device_handler::forget_device)
+        )
+        .service(
+            web::scope("/phone")
+                .service(account::phone_handler::start_phone_verification)
+                .service(account::phone_handler::confirm_phone_verification)
         )
         .service(
             web::scope("/mfa")
                 .service(account::mfa_handler::setup_mfa)
                 .service(account::mfa_handler::enable_mfa)
                 .service(account::mfa_handler::disable_mfa)
-                .service(account::mfa_handler::verify_mfa),
+                .service(account::mfa_handler::verify_mfa)
+                .service(account::mfa_handler::send_sms_code)
+                .service(account::mfa_handler::enable_sms_mfa)
+                .service(account::mfa_handler::disable_sms_mfa),
         )
         .service(
             web::scope("/listings")
+                .app_data(web::PayloadConfig::new(MAX_LISTING_IMPORT_BYTES))
+                .service(marketplace::listing_handler::import_listings)
+                .service(marketplace::listing_handler::export_listings)
                 .service(marketplace::listing_handler::create_listing)
                 .service(marketplace::listing_handler::update_listing)
                 .service(marketplace::listing_handler::delete_listing)
+                .service(marketplace::listing_handler::renew_listing)
         )
         .service(
             web::scope("/product")
@@ -54,6 +122,41 @@ fn private_routes(cfg: &mut web::ServiceConfig) {
                 .service(marketplace::product_handler::delete_product)
                 .service(marketplace::product_handler::create_product_variants)
                 .service(marketplace::product_handler::upload_product_images)
+        )
+        .service(
+            web::scope("/catalogue")
+                .wrap(RequirePermission::new("catalogue:write"))
+                .service(marketplace::catalogue_handler::create_game)
+                .service(marketplace::catalogue_handler::update_game)
+                .service(marketplace::catalogue_handler::delete_game)
+                .service(marketplace::catalogue_handler::create_set)
+                .service(marketplace::catalogue_handler::update_set)
+                .service(marketplace::catalogue_handler::delete_set)
+                .service(marketplace::catalogue_handler::update_product)
+                .service(marketplace::catalogue_handler::merge_products)
+                .service(marketplace::catalogue_handler::reorder_variants)
+                .service(marketplace::catalogue_handler::update_variant)
+        )
+        .service(
+            web::scope("/sellers")
+                .service(marketplace::seller_handler::start_onboarding)
+                .service(marketplace::seller_handler::get_onboarding_status)
+                .service(marketplace::seller_handler::get_my_listings)
+                .service(marketplace::seller_handler::get_my_sales)
+        )
+        .service(
+            web::scope("/orders")
+                .service(transactions::cart_handler::get_cart)
+                .service(transactions::cart_handler::add_cart_item)
+                .service(transactions::cart_handler::remove_cart_item)
+                .service(transactions::cart_handler::clear_cart)
+                .service(transactions::order_handler::checkout)
+                .service(transactions::order_handler::get_orders)
+                .service(transactions::order_handler::get_order)
+                .service(transactions::order_handler::get_order_payment)
+                .service(transactions::order_handler::cancel_order)
+                .service(transactions::order_handler::ship_order)
+                .service(transactions::order_handler::deliver_order)
         );
 }
 
@@ -62,7 +165,14 @@ fn public_routes(cfg: &mut web::ServiceConfig) {
         .service(
             web::scope("/auth")
                 .service(account::auth_handler::login)
-                .service(account::auth_handler::register),
+                .service(account::auth_handler::register)
+                .service(account::auth_handler::refresh)
+                .service(account::auth_handler::verify_email)
+                .service(account::auth_handler::confirm_location)
+                .service(account::auth_handler::forgot_password)
+                .service(account::auth_handler::reset_password)
+                .service(account::oauth_handler::authorize)
+                .service(account::oauth_handler::callback),
         )
         .service(
             web::scope("/products")
@@ -76,6 +186,14 @@ fn public_routes(cfg: &mut web::ServiceConfig) {
             web::scope("/listing")
                 .service(marketplace::listing_handler::get_listing),
         )
+        .service(
+            web::scope("/listings")
+                .service(marketplace::listing_handler::browse_listings),
+        )
+        .service(
+            web::scope("/sellers")
+                .service(marketplace::seller_handler::get_storefront),
+        )
         .service(
             web::scope("/games")
                 .service(marketplace::game_handler::get_games)
@@ -85,5 +203,9 @@ fn public_routes(cfg: &mut web::ServiceConfig) {
             web::scope("/search")
                 .service(integrations::meilisearch_handler::search_products)
                 .service(integrations::meilisearch_handler::get_trending_products),
+        )
+        .service(
+            web::scope("/webhooks")
+                .service(integrations::stripe_handler::stripe_webhook),
         );
 }
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use actix_web::{delete, get, post, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{cart_items, listings};
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::transactions::cart_service::CartService;
+
+#[derive(Deserialize)]
+pub struct AddCartItemRequest {
+    pub listing_id: Uuid,
+    pub quantity: i64,
+}
+
+#[derive(Deserialize)]
+pub struct RemoveCartItemQuery {
+    pub quantity: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct CartItemResponse {
+    pub item: cart_items::Model,
+    pub listing: listings::Model,
+    pub line_total: i64,
+    pub available: bool,
+}
+
+#[derive(Serialize)]
+pub struct CartResponse {
+    pub items: Vec<CartItemResponse>,
+    pub total_amount: i64,
+    pub currency: String,
+}
+
+#[get("/cart")]
+pub async fn get_cart(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let cart_service = CartService::new(state.as_ref().clone());
+
+    match cart_service.get_cart(claims.sub).await {
+        Ok(cart) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Cart retrieved successfully".to_string(),
+            data: Some(cart),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/cart/items")]
+pub async fn add_cart_item(
+    state: web::Data<AppState>,
+    claims: Claims,
+    request: web::Json<AddCartItemRequest>,
+) -> Result<impl Responder> {
+    let request = request.into_inner();
+    let cart_service = CartService::new(state.as_ref().clone());
+
+    match cart_service.add_item(claims.sub, request).await {
+        Ok(cart) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Item added to cart".to_string(),
+            data: Some(cart),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/cart/items/{listing_id}")]
+pub async fn remove_cart_item(
+    state: web::Data<AppState>,
+    claims: Claims,
+    listing_id: web::Path<Uuid>,
+    query: web::Query<RemoveCartItemQuery>,
+) -> Result<impl Responder> {
+    let listing_id = listing_id.into_inner();
+    let cart_service = CartService::new(state.as_ref().clone());
+
+    match cart_service.remove_item(claims.sub, listing_id, query.quantity).await {
+        Ok(cart) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Item removed from cart".to_string(),
+            data: Some(cart),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[delete("/cart")]
+pub async fn clear_cart(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let cart_service = CartService::new(state.as_ref().clone());
+
+    match cart_service.clear_cart(claims.sub).await {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,2 @@
+pub mod cart_handler;
+pub mod order_handler;
\ No newline at end of file
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use actix_web::{get, post, web, HttpResponse, Responder, Result};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{order_items, orders};
+use crate::handlers::ApiResponse;
+use crate::services::account::jwt_service::Claims;
+use crate::services::transactions::order_service::OrderService;
+use crate::utils::request_util::ClientInfo;
+
+#[derive(Debug, Serialize)]
+pub struct OrderResponse {
+    pub order: orders::Model,
+    pub items: Vec<order_items::Model>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckoutResponse {
+    pub order: orders::Model,
+    pub items: Vec<order_items::Model>,
+    pub client_secret: String,
+    pub hold_expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentResponse {
+    pub order_id: Uuid,
+    pub client_secret: String,
+}
+
+#[post("/checkout")]
+pub async fn checkout(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+) -> Result<impl Responder> {
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.checkout(claims.sub, &client).await {
+        Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Order created successfully".to_string(),
+            data: Some(order),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("")]
+pub async fn get_orders(
+    state: web::Data<AppState>,
+    claims: Claims,
+) -> Result<impl Responder> {
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.get_orders(claims.sub).await {
+        Ok(orders) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Orders retrieved successfully".to_string(),
+            data: Some(orders),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/{id}")]
+pub async fn get_order(
+    state: web::Data<AppState>,
+    claims: Claims,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let id = id.into_inner();
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.get_order(claims.sub, id).await {
+        Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Order retrieved successfully".to_string(),
+            data: Some(order),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[get("/{id}/payment")]
+pub async fn get_order_payment(
+    state: web::Data<AppState>,
+    claims: Claims,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let id = id.into_inner();
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.get_payment_client_secret(claims.sub, id).await {
+        Ok(client_secret) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Payment retrieved successfully".to_string(),
+            data: Some(PaymentResponse {
+                order_id: id,
+                client_secret,
+            }),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/cancel")]
+pub async fn cancel_order(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let id = id.into_inner();
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.cancel_order(claims.sub, &client, id).await {
+        Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Order cancelled successfully".to_string(),
+            data: Some(order),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/ship")]
+pub async fn ship_order(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let id = id.into_inner();
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.mark_order_shipped(claims.sub, &client, id).await {
+        Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Order marked as shipped".to_string(),
+            data: Some(order),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[post("/{id}/deliver")]
+pub async fn deliver_order(
+    state: web::Data<AppState>,
+    claims: Claims,
+    client: ClientInfo,
+    id: web::Path<Uuid>,
+) -> Result<impl Responder> {
+    let id = id.into_inner();
+    let order_service = OrderService::new(state.as_ref().clone());
+
+    match order_service.mark_order_delivered(claims.sub, &client, id).await {
+        Ok(order) => Ok(HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            message: "Order marked as delivered".to_string(),
+            data: Some(order),
+        })),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,11 +1,16 @@
 use actix_web::dev::Service;
-use actix_web::{web, App, HttpServer, Result};
+use actix_web::{web, App, HttpMessage, HttpServer, Result};
 use actix_web::web::Data;
 use sea_orm::ColumnType::Uuid;
 use crate::app_state::AppState;
+use crate::services::account::token_blacklist_service::TokenBlacklistService;
 use crate::services::integrations::meilisearch_service::MeilisearchService;
+use crate::services::jobs::job_service::JobService;
+use crate::services::marketplace::listing_expiry_service::ListingExpiryService;
+use crate::services::transactions::reservation_service::ReservationService;
 use crate::utils::cli_util::CliUtil;
 use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::RequestId;
 
 mod handlers;
 mod services;
@@ -15,6 +20,7 @@ mod utils;
 mod middleware;
 mod database;
 mod app_state;
+mod errors;
 
 #[actix_web::main]
 async fn main() -> Result<()> {
@@ -23,6 +29,18 @@ async fn main() -> Result<()> {
     MessageUtil::info("Starting TCGEmporium server...");
 
     let config = config::config::Config::get();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.first().map(String::as_str) == Some("migrate") {
+        match CliUtil::migrate(&args[1..]).await {
+            Ok(_) => std::process::exit(0),
+            Err(e) => {
+                MessageUtil::error(&e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     let app_state = match AppState::new().await {
         Ok(state) => {
             MessageUtil::info("Application state initialized successfully");
@@ -43,6 +61,21 @@ async fn main() -> Result<()> {
         }
     }
 
+    if !args.is_empty() {
+        match CliUtil::run_command(app_state.clone(), &args).await {
+            Ok(_) => std::process::exit(0),
+            Err(e) => {
+                MessageUtil::error(&e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    ReservationService::start_hold_sweeper(app_state.clone());
+    ListingExpiryService::start_listing_expiry_job(app_state.clone());
+    TokenBlacklistService::start_purge_job(app_state.clone());
+    JobService::start_workers(app_state.clone());
+
     let server = HttpServer::new(move || {
         App::new()
             .app_data(Data::new(app_state.clone()))
@@ -52,6 +85,7 @@ async fn main() -> Result<()> {
                 let request_id = uuid::Uuid::new_v4();
                 
                 middleware::logger_middleware::LoggerMiddleware::log_request(&req, req.path(), &request_id);
+                req.extensions_mut().insert(RequestId(request_id));
                 let fut = srv.call(req);
                 async move {
                     let res = fut.await?;
@@ -65,6 +99,7 @@ async fn main() -> Result<()> {
                 web::scope("")
                     .wrap(middleware::auth_middleware::AuthMiddleware)
                     .configure(handlers::configure_private_routes)
+                    .configure(handlers::configure_admin_routes)
             )
     })
         .bind(format!("{}:{}", config.host, config.port))?
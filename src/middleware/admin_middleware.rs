@@ -0,0 +1,94 @@
+use crate::app_state::AppState;
+use crate::errors::AppError;
+use crate::services::account::jwt_service::Claims;
+use crate::services::admin::admin_service::AdminService;
+use actix_web::{
+    Error, HttpMessage, ResponseError,
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    web,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{Ready, ready},
+    rc::Rc,
+};
+
+/// Guards the admin API. Admin status is checked against `user_roles` on every request
+/// rather than trusted from the token, so a revoked admin loses access immediately.
+pub struct AdminMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for AdminMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = AdminMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AdminMiddlewareService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AdminMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AdminMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let user_id = req.extensions().get::<Claims>().map(|claims| claims.sub);
+
+            let user_id = match user_id {
+                Some(user_id) => user_id,
+                None => {
+                    let response = AppError::Unauthorized("No authentication token found".to_string()).error_response();
+                    return Ok(req.into_response(response));
+                }
+            };
+
+            let state = match req.app_data::<web::Data<AppState>>() {
+                Some(state) => state.get_ref().clone(),
+                None => {
+                    let response = AppError::Internal("Application state is not configured".to_string()).error_response();
+                    return Ok(req.into_response(response));
+                }
+            };
+
+            match AdminService::new(state).is_admin(&user_id).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    let response = AppError::Forbidden("Admin access required".to_string()).error_response();
+                    return Ok(req.into_response(response));
+                }
+                Err(e) => {
+                    let response = e.error_response();
+                    return Ok(req.into_response(response));
+                }
+            }
+
+            let response = service.call(req).await?;
+            Ok(response.map_into_boxed_body())
+        })
+    }
+}
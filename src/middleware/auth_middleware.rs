@@ -1,8 +1,13 @@
+use crate::app_state::AppState;
+use crate::errors::AppError;
 use crate::services::account::jwt_service::JwtService;
+use crate::services::account::session_service::SessionService;
+use crate::services::account::token_blacklist_service::TokenBlacklistService;
 use actix_web::{
-    Error, HttpMessage, HttpResponse,
+    Error, HttpMessage, ResponseError,
     body::{BoxBody, MessageBody},
     dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    web,
 };
 use futures_util::future::LocalBoxFuture;
 use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode};
@@ -56,9 +61,7 @@ where
             let token = match extract_jwt_from_cookie(&req) {
                 Some(token) => token,
                 None => {
-                    let response = HttpResponse::Unauthorized().json(serde_json::json!({
-                        "error": "Missing authentication token"
-                    }));
+                    let response = AppError::Unauthorized("Missing authentication token".to_string()).error_response();
                     return Ok(req.into_response(response));
                 }
             };
@@ -67,42 +70,57 @@ where
             let claims = match JwtService::validate_token(&token).await {
                 Ok(claims) => claims,
                 Err(err) => {
-                    let response = HttpResponse::Unauthorized().json(serde_json::json!({
-                        "error": format!("Invalid token: {}", err)
-                    }));
+                    let response = AppError::Unauthorized(format!("Invalid token: {}", err)).error_response();
                     return Ok(req.into_response(response));
                 }
             };
 
+            let state = match req.app_data::<web::Data<AppState>>() {
+                Some(state) => state.get_ref().clone(),
+                None => {
+                    let response = AppError::Internal("Application state is not configured".to_string()).error_response();
+                    return Ok(req.into_response(response));
+                }
+            };
+
+            match TokenBlacklistService::new(state.clone()).is_revoked(&claims).await {
+                Ok(false) => {}
+                Ok(true) => {
+                    let response = AppError::Unauthorized("Token has been revoked".to_string()).error_response();
+                    return Ok(req.into_response(response));
+                }
+                Err(err) => {
+                    let response = AppError::Internal(format!("Failed to check token: {}", err)).error_response();
+                    return Ok(req.into_response(response));
+                }
+            }
+
             let path = req.path();
 
             match claims.purpose.as_str() {
                 "temporary" => {
-                    if path != "/mfa/verify" {
-                        let response = HttpResponse::Forbidden()
-                                .json(serde_json::json!({
-                                    "error": "MFA verification required. This token can only access MFA verification endpoints."
-                                }));
+                    if !path.ends_with("/mfa/verify") && !path.ends_with("/mfa/sms/send") {
+                        let response = AppError::Forbidden("MFA verification required. This token can only access MFA verification endpoints.".to_string()).error_response();
                         return Ok(req.into_response(response));
                     }
                 }
                 "access" => {
-                    if path == "/mfa/verify" {
-                        let response = HttpResponse::Forbidden().json(serde_json::json!({
-                            "error": "Use MFA verification token for this endpoint"
-                        }));
+                    if path.ends_with("/mfa/verify") {
+                        let response = AppError::Forbidden("Use MFA verification token for this endpoint".to_string()).error_response();
                         return Ok(req.into_response(response));
                     }
                 }
                 "admin" => {}
                 _ => {
-                    let response = HttpResponse::Unauthorized().json(serde_json::json!({
-                        "error": "Invalid token purpose"
-                    }));
+                    let response = AppError::Unauthorized("Invalid token purpose".to_string()).error_response();
                     return Ok(req.into_response(response));
                 }
             }
 
+            if let Some(session_id) = claims.sid {
+                SessionService::touch_session(state, session_id);
+            }
+
             req.extensions_mut().insert(claims);
 
             let response = service.call(req).await?;
@@ -1,2 +1,5 @@
 pub mod auth_middleware;
-pub mod logger_middleware;
\ No newline at end of file
+pub mod logger_middleware;
+pub mod rate_limit_middleware;
+pub mod permission_middleware;
+pub mod admin_middleware;
\ No newline at end of file
@@ -0,0 +1,151 @@
+use crate::errors::AppError;
+use crate::services::account::jwt_service::Claims;
+use actix_web::{
+    Error, HttpMessage, ResponseError,
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{Ready, ready},
+    rc::Rc,
+};
+
+/// Route guard for a single permission, e.g.
+/// `#[post("", wrap = "RequirePermission::new(\"product:write\")")]`. It reads the claims
+/// left by `AuthMiddleware`, so it only works on routes inside the authenticated scope.
+pub struct RequirePermission {
+    permission: &'static str,
+}
+
+impl RequirePermission {
+    pub fn new(permission: &'static str) -> Self {
+        Self { permission }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = RequirePermissionService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionService {
+            service: Rc::new(service),
+            permission: self.permission,
+        }))
+    }
+}
+
+pub struct RequirePermissionService<S> {
+    service: Rc<S>,
+    permission: &'static str,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let permission = self.permission;
+
+        Box::pin(async move {
+            let allowed = req.extensions()
+                .get::<Claims>()
+                .map(|claims| claims.has_permission(permission));
+
+            let allowed = match allowed {
+                Some(allowed) => allowed,
+                None => {
+                    let response = AppError::Unauthorized("No authentication token found".to_string()).error_response();
+                    return Ok(req.into_response(response));
+                }
+            };
+
+            if !allowed {
+                let response = AppError::Forbidden(format!("Missing required permission: {}", permission)).error_response();
+                return Ok(req.into_response(response));
+            }
+
+            let response = service.call(req).await?;
+            Ok(response.map_into_boxed_body())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::dev::Service as _;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+    use uuid::Uuid;
+
+    fn claims(permissions: &[&str]) -> Claims {
+        Claims {
+            sub: Uuid::nil(),
+            purpose: "access".to_string(),
+            exp: 0,
+            iat: 0,
+            jti: "jti".to_string(),
+            roles: Vec::new(),
+            permissions: permissions.iter().map(|permission| permission.to_string()).collect(),
+            sid: None,
+            resource_id: None,
+        }
+    }
+
+    async fn status_for(claims: Option<Claims>) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(move |req, srv| {
+                    if let Some(claims) = claims.clone() {
+                        req.extensions_mut().insert(claims);
+                    }
+                    srv.call(req)
+                })
+                .service(
+                    web::resource("/")
+                        .wrap(RequirePermission::new("product:write"))
+                        .route(web::post().to(HttpResponse::Ok)),
+                ),
+        ).await;
+
+        test::call_service(&app, test::TestRequest::post().uri("/").to_request())
+            .await
+            .status()
+    }
+
+    #[actix_web::test]
+    async fn lets_through_holders_of_the_permission() {
+        assert_eq!(status_for(Some(claims(&["product:write"]))).await, StatusCode::OK);
+        assert_eq!(status_for(Some(claims(&["all"]))).await, StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn forbids_everyone_else() {
+        assert_eq!(status_for(Some(claims(&["product:read"]))).await, StatusCode::FORBIDDEN);
+        assert_eq!(status_for(Some(claims(&[]))).await, StatusCode::FORBIDDEN);
+    }
+
+    #[actix_web::test]
+    async fn requires_a_token() {
+        assert_eq!(status_for(None).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::app_state::AppState;
+use crate::errors::AppError;
+use actix_web::{
+    Error, ResponseError,
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::header::{self, HeaderValue},
+    web,
+};
+use futures_util::future::LocalBoxFuture;
+use std::{
+    future::{Ready, ready},
+    rc::Rc,
+    time::Duration,
+};
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::client_ip;
+
+/// Per-IP throttle for a single route, e.g. `#[post("/login", wrap = "RateLimit::new(5, 60)")]`.
+pub struct RateLimit {
+    max_requests: usize,
+    window: Duration,
+}
+
+impl RateLimit {
+    pub fn new(max_requests: usize, window_seconds: u64) -> Self {
+        Self {
+            max_requests,
+            window: Duration::from_secs(window_seconds),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = RateLimitService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitService {
+            service: Rc::new(service),
+            max_requests: self.max_requests,
+            window: self.window,
+        }))
+    }
+}
+
+pub struct RateLimitService<S> {
+    service: Rc<S>,
+    max_requests: usize,
+    window: Duration,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let max_requests = self.max_requests;
+        let window = self.window;
+
+        Box::pin(async move {
+            if let Some(state) = req.app_data::<web::Data<AppState>>() {
+                let ip_address = client_ip(req.request())
+                    .map(|ip| ip.to_string())
+                    .unwrap_or_else(|| "unknown".to_string());
+                let key = format!("{}:{}", req.path(), ip_address);
+
+                // Fails open: a cache outage shouldn't take the login endpoints down with it.
+                match state.cache.hit_window(&format!("ratelimit:{}", key), max_requests, window).await {
+                    Ok(Ok(())) => {}
+                    Ok(Err(retry_after)) => {
+                        let mut response = AppError::RateLimited("Too many requests, please try again later".to_string()).error_response();
+                        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after.as_secs().max(1)));
+                        return Ok(req.into_response(response));
+                    }
+                    Err(e) => MessageUtil::error(&e.to_string()),
+                }
+            }
+
+            let response = service.call(req).await?;
+            Ok(response.map_into_boxed_body())
+        })
+    }
+}
@@ -0,0 +1,411 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{Set, TransactionTrait};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::{known_login_locations, users};
+use crate::errors::AppError;
+use crate::handlers::account::auth_handler::ResetPasswordRequest;
+use crate::services::account::jwt_service::{Claims, JwtService};
+use crate::services::account::lockout_service::LockoutService;
+use crate::services::account::login_history_service::LoginHistoryService;
+use crate::services::account::session_service::SessionService;
+use crate::services::account::token_blacklist_service::TokenBlacklistService;
+use crate::services::account::user_service::UserService;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::integrations::mailer_service::EmailMessage;
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
+use crate::utils::validator_util::ValidatorUtil;
+
+const EMAIL_VERIFICATION_PURPOSE: &str = "email_verification";
+const PASSWORD_RESET_PURPOSE: &str = "password_reset";
+const LOCATION_CONFIRMATION_PURPOSE: &str = "location_confirmation";
+const PASSWORD_RESET_LIFETIME_HOURS: i64 = 1;
+
+pub struct AccountEmailService {
+    state: AppState,
+}
+
+impl AccountEmailService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn send_verification_email(
+        &self,
+        user: &users::Model,
+    ) -> Result<(), AppError> {
+        if user.email_verified {
+            return Err(AppError::Conflict("Email is already verified".to_string()));
+        }
+
+        let token = JwtService::generate_action_token(
+            user.id,
+            EMAIL_VERIFICATION_PURPOSE,
+            None,
+            chrono::Duration::hours(24),
+        ).await?;
+
+        self.send(EmailMessage {
+            to: user.email.clone(),
+            subject: "Verify your email address".to_string(),
+            body: format!(
+                "Welcome to TCGEmporium!\n\nConfirm your email address by opening the link below:\n\n{}\n\nThe link expires in 24 hours.",
+                Self::link("verify-email", &token)
+            ),
+        }).await
+    }
+
+    pub async fn resend_verification_email(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<(), AppError> {
+        let user = UserService::new(self.state.clone())
+            .get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        self.send_verification_email(&user).await
+    }
+
+    pub async fn verify_email(
+        &self,
+        token: &str,
+    ) -> Result<(), AppError> {
+        let claims = self.consume_action_token(token, EMAIL_VERIFICATION_PURPOSE).await?;
+
+        UserService::new(self.state.clone())
+            .update_user_field(&claims.sub, |user| {
+                user.email_verified = Set(true);
+                user.email_verified_at = Set(Some(chrono::Utc::now()));
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Always gives the same answer, and hands the lookup and the email to a background
+    /// task, so neither the response nor its timing reveals which emails have accounts.
+    pub async fn request_password_reset(
+        &self,
+        email: &str,
+    ) -> Result<(), AppError> {
+        if ValidatorUtil::validate_email(email).is_err() {
+            return Ok(());
+        }
+
+        let state = self.state.clone();
+        let email = email.to_string();
+
+        tokio::spawn(async move {
+            if let Err(e) = AccountEmailService::new(state).send_password_reset(&email).await {
+                MessageUtil::error(&format!("Failed to send password reset email: {}", e));
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn send_password_reset(
+        &self,
+        email: &str,
+    ) -> Result<(), AppError> {
+        let user = match UserService::new(self.state.clone()).get_user_by_email(email).await? {
+            Some(user) => user,
+            None => return Ok(()),
+        };
+
+        let token = Self::mint_password_reset_token(user.id).await?;
+
+        self.send(Self::password_reset_message(&user.email, &Self::link("reset-password", &token))).await
+    }
+
+    async fn mint_password_reset_token(user_id: Uuid) -> Result<String, AppError> {
+        JwtService::generate_action_token(
+            user_id,
+            PASSWORD_RESET_PURPOSE,
+            None,
+            chrono::Duration::hours(PASSWORD_RESET_LIFETIME_HOURS),
+        ).await
+    }
+
+    fn password_reset_message(
+        to: &str,
+        link: &str,
+    ) -> EmailMessage {
+        EmailMessage {
+            to: to.to_string(),
+            subject: "Reset your password".to_string(),
+            body: format!(
+                "Someone asked to reset the password for your TCGEmporium account.\n\nIf it was you, open the link below to choose a new password:\n\n{}\n\nThe link expires in 1 hour. If you didn't ask for this, you can ignore this email.",
+                link
+            ),
+        }
+    }
+
+    /// Sets a new password and signs the user out everywhere, since a reset usually means
+    /// the old password can't be trusted.
+    pub async fn reset_password(
+        &self,
+        request: ResetPasswordRequest,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        match ValidatorUtil::validate_password(&request.password) {
+            Ok(_) => {},
+            Err(e) => return Err(AppError::field("password", e.to_string())),
+        }
+
+        if request.password != request.confirm_password {
+            return Err(AppError::field("confirmPassword", "Passwords do not match"));
+        }
+
+        let claims = JwtService::validate_token(&request.token)
+            .await
+            .map_err(|_| AppError::Unauthorized("Invalid or expired link".to_string()))?;
+
+        let user_service = UserService::new(self.state.clone());
+
+        let user = user_service.get_user_by_id(&claims.sub)
+            .await?
+            .ok_or_else(|| AppError::Unauthorized("Invalid or expired link".to_string()))?;
+
+        if Self::predates_password_change(&claims, user.password_changed_at) {
+            return Err(AppError::Unauthorized("Invalid or expired link".to_string()));
+        }
+
+        self.consume_action_token(&request.token, PASSWORD_RESET_PURPOSE).await?;
+
+        let password_hash = bcrypt::hash(&request.password, bcrypt::DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let user = UserService::update_user_field_on(&txn, &user.id, |user| {
+            user.password_hash = Set(password_hash);
+            user.password_changed_at = Set(Some(chrono::Utc::now()));
+        }).await?;
+
+        LockoutService::reset_on(&txn, &user).await?;
+
+        SessionService::new(self.state.clone())
+            .revoke_all_sessions_on(&txn, user.id, "password_reset")
+            .await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(user.id),
+            client,
+            action: "auth.password_reset",
+            target_type: "user",
+            target_id: Some(user.id.to_string()),
+            old_values: None,
+            new_values: None,
+            metadata: None,
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit password reset: {}", e)))
+    }
+
+    pub async fn send_location_confirmation(
+        &self,
+        user: &users::Model,
+        location: &known_login_locations::Model,
+    ) -> Result<(), AppError> {
+        let token = JwtService::generate_action_token(
+            user.id,
+            LOCATION_CONFIRMATION_PURPOSE,
+            Some(location.id),
+            chrono::Duration::hours(1),
+        ).await?;
+
+        self.send(EmailMessage {
+            to: user.email.clone(),
+            subject: "Confirm a new sign-in location".to_string(),
+            body: format!(
+                "We noticed a sign-in to your TCGEmporium account from a new location (IP {}, {}).\n\nIf this was you, confirm it by opening the link below, then sign in again:\n\n{}\n\nIf it wasn't you, change your password straight away.",
+                location.ip_address,
+                location.user_agent.as_deref().unwrap_or("unknown device"),
+                Self::link("confirm-location", &token)
+            ),
+        }).await
+    }
+
+    pub async fn confirm_location(
+        &self,
+        token: &str,
+    ) -> Result<(), AppError> {
+        let claims = self.consume_action_token(token, LOCATION_CONFIRMATION_PURPOSE).await?;
+
+        let location_id = claims.resource_id
+            .ok_or_else(|| AppError::Unauthorized("Invalid or expired link".to_string()))?;
+
+        let history_service = LoginHistoryService::new(self.state.clone());
+
+        let location = history_service.get_devices(claims.sub)
+            .await?
+            .into_iter()
+            .find(|location| location.id == location_id)
+            .ok_or_else(|| AppError::Unauthorized("Invalid or expired link".to_string()))?;
+
+        history_service.verify_location(location).await?;
+
+        Ok(())
+    }
+
+    /// Validates an emailed token for `purpose` and blacklists it so the link works once.
+    async fn consume_action_token(
+        &self,
+        token: &str,
+        purpose: &str,
+    ) -> Result<Claims, AppError> {
+        let claims = Self::validate_action_token(token, purpose).await?;
+
+        if !TokenBlacklistService::new(self.state.clone()).revoke(&claims, "consumed").await? {
+            return Err(AppError::Unauthorized("This link has already been used".to_string()));
+        }
+
+        Ok(claims)
+    }
+
+    /// Checks the signature, expiry and purpose of an emailed token, without using it up.
+    async fn validate_action_token(
+        token: &str,
+        purpose: &str,
+    ) -> Result<Claims, AppError> {
+        let claims = JwtService::validate_token(token)
+            .await
+            .map_err(|_| AppError::Unauthorized("Invalid or expired link".to_string()))?;
+
+        if claims.purpose != purpose {
+            return Err(AppError::Unauthorized("Invalid or expired link".to_string()));
+        }
+
+        Ok(claims)
+    }
+
+    /// A link issued before the last password change belongs to an older reset.
+    fn predates_password_change(
+        claims: &Claims,
+        password_changed_at: Option<DateTime<Utc>>,
+    ) -> bool {
+        password_changed_at
+            .map(|changed_at| (claims.iat as i64) < changed_at.timestamp())
+            .unwrap_or(false)
+    }
+
+    async fn send(
+        &self,
+        message: EmailMessage,
+    ) -> Result<(), AppError> {
+        self.state.mailer.send(message).await
+    }
+
+    fn link(path: &str, token: &str) -> String {
+        format!("{}/{}?token={}", Config::get().app_base_url.trim_end_matches('/'), path, token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::integrations::mailer_service::{InMemoryMailer, Mailer};
+
+    const BASE_URL: &str = "https://tcgemporium.test";
+
+    fn use_test_secret() {
+        // Every test sets the same value, so tests racing on it can't see a different one.
+        #[allow(unused_unsafe)]
+        unsafe { std::env::set_var("JWT_SECRET", "account-email-service-test-secret") };
+    }
+
+    fn reset_link(token: &str) -> String {
+        format!("{}/reset-password?token={}", BASE_URL, token)
+    }
+
+    fn token_from(message: &EmailMessage) -> String {
+        let start = message.body.find("?token=").unwrap() + "?token=".len();
+
+        message.body[start..]
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    #[actix_web::test]
+    async fn mints_a_reset_link_that_validates_for_the_user() {
+        use_test_secret();
+        let user_id = Uuid::new_v4();
+        let mailer = InMemoryMailer::new();
+
+        let token = AccountEmailService::mint_password_reset_token(user_id).await.unwrap();
+        mailer.send(AccountEmailService::password_reset_message("buyer@example.com", &reset_link(&token)))
+            .await
+            .unwrap();
+
+        let sent = mailer.sent_messages();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, "buyer@example.com");
+        assert_eq!(token_from(&sent[0]), token);
+
+        let claims = AccountEmailService::validate_action_token(&token_from(&sent[0]), PASSWORD_RESET_PURPOSE)
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, user_id);
+
+        let lifetime = claims.exp as i64 - claims.iat as i64;
+        assert_eq!(lifetime, PASSWORD_RESET_LIFETIME_HOURS * 60 * 60);
+    }
+
+    #[actix_web::test]
+    async fn rejects_a_reset_token_used_for_another_purpose() {
+        use_test_secret();
+
+        let token = AccountEmailService::mint_password_reset_token(Uuid::new_v4()).await.unwrap();
+
+        assert!(AccountEmailService::validate_action_token(&token, EMAIL_VERIFICATION_PURPOSE).await.is_err());
+        assert!(AccountEmailService::validate_action_token(&token, LOCATION_CONFIRMATION_PURPOSE).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn rejects_an_expired_reset_token() {
+        use_test_secret();
+
+        // Well past jsonwebtoken's default leeway.
+        let token = JwtService::generate_action_token(
+            Uuid::new_v4(),
+            PASSWORD_RESET_PURPOSE,
+            None,
+            -chrono::Duration::hours(2),
+        ).await.unwrap();
+
+        assert!(AccountEmailService::validate_action_token(&token, PASSWORD_RESET_PURPOSE).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn rejects_a_tampered_reset_token() {
+        use_test_secret();
+
+        let mut token = AccountEmailService::mint_password_reset_token(Uuid::new_v4()).await.unwrap();
+        token.push('x');
+
+        assert!(AccountEmailService::validate_action_token(&token, PASSWORD_RESET_PURPOSE).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn a_password_change_retires_older_reset_links() {
+        use_test_secret();
+
+        let token = AccountEmailService::mint_password_reset_token(Uuid::new_v4()).await.unwrap();
+        let claims = AccountEmailService::validate_action_token(&token, PASSWORD_RESET_PURPOSE).await.unwrap();
+        let issued_at = DateTime::from_timestamp(claims.iat as i64, 0).unwrap();
+
+        assert!(!AccountEmailService::predates_password_change(&claims, None));
+        assert!(!AccountEmailService::predates_password_change(&claims, Some(issued_at - chrono::Duration::minutes(5))));
+        assert!(AccountEmailService::predates_password_change(&claims, Some(issued_at + chrono::Duration::seconds(1))));
+    }
+}
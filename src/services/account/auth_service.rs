@@ -1,15 +1,28 @@
+use sea_orm::{Set, TransactionTrait};
 use crate::app_state::AppState;
-use crate::entities::{users};
-use crate::handlers::account::auth_handler::RegisterRequest;
-use crate::services::account::jwt_service::JwtService;
+use crate::entities::{known_login_locations, users};
+use crate::entities::users::ACCOUNT_STATUS_ACTIVE;
+use crate::errors::AppError;
+use crate::handlers::account::auth_handler::{ChangePasswordRequest, RegisterRequest};
+use crate::handlers::account::mfa_handler::MFALoginRequest;
+use crate::services::account::account_email_service::AccountEmailService;
+use crate::services::account::jwt_service::{Claims, JwtService};
+use crate::services::account::login_history_service::{LoginAttempt, LoginHistoryService, UntrustedLocationPolicy};
+use crate::services::account::lockout_service::LockoutService;
+use crate::services::account::mfa_service::MfaService;
+use crate::services::account::session_service::{IssuedSession, SessionService};
+use crate::services::account::token_blacklist_service::TokenBlacklistService;
 use crate::services::account::user_service::UserService;
-use crate::services::admin::admin_service::AdminService;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
 use crate::utils::validator_util::ValidatorUtil;
 
 #[derive(Debug)]
 pub struct AuthenticatedUser {
     pub user: users::Model,
     pub token: String,
+    pub refresh_token: Option<String>,
 }
 
 pub struct AuthService {
@@ -25,90 +38,432 @@ impl AuthService {
     pub async fn authenticate_user(
         &self,
         email: &str, 
-        password: &str) -> Result<AuthenticatedUser, String> {
-        let authenticated_user;
-        
+        password: &str,
+        client: &ClientInfo) -> Result<AuthenticatedUser, AppError> {
         if email.is_empty() || password.is_empty() {
-            return Err("Username and password cannot be empty".to_string());
+            return Err(AppError::validation("Username and password cannot be empty"));
         }
         
         match ValidatorUtil::validate_email(email) {
             Ok(_) => {}
-            Err(e) => return Err(e.to_string()),
+            Err(e) => return Err(AppError::field("email", e.to_string())),
         }
 
         let user_service = UserService::new(self.state.clone());
-        
-        match user_service.get_user_by_email(&email).await {
-            Ok(Some(user)) => {
-                if bcrypt::verify(password, &user.password_hash).unwrap_or(false) {
-                    
-                    let token = match user.totp_enabled {
-                        true => JwtService::generate_temporary_token(user.id).await,
-                        false => {
-                            match AdminService::new(self.state.clone()).is_admin(&user.id).await {
-                                Ok(true) => JwtService::generate_admin_token(user.id).await,
-                                Ok(false) => JwtService::generate_access_token(user.id).await,
-                                Err(e) => return Err(e.to_string()),
-                            }
-                        }
-                    }.map_err(|_| "Error generating token".to_string())?;
-                    
-                    authenticated_user = AuthenticatedUser {
-                        user: user.clone(),
-                        token,
-                    };
-
-                    Ok(authenticated_user)
-                } else {
-                    Err("Invalid username or password".to_string())
-                }
-            }
-            Ok(None) => {
-                Err("Invalid username or password".to_string())
-            }
-            Err(_) => {
-                Err("Invalid username or password".to_string())
+        let history_service = LoginHistoryService::new(self.state.clone());
+
+        let lockout_service = LockoutService::new(self.state.clone());
+
+        let user = match user_service.get_user_by_email(&email).await {
+            Ok(Some(user)) => user,
+            Ok(None) | Err(_) => {
+                LockoutService::burn_password_check(password);
+
+                history_service.record_attempt(LoginAttempt {
+                    user_id: None,
+                    client,
+                    login_method: "password",
+                    success: false,
+                    failure_reason: Some("unknown_email"),
+                    location_id: None,
+                    session_id: None,
+                    is_suspicious: false,
+                }).await?;
+
+                return Err(AppError::Unauthorized("Invalid username or password".to_string()));
             }
+        };
+
+        // A locked account answers exactly like a wrong password, so lockouts can't be
+        // used to discover which emails are registered.
+        if LockoutService::is_locked(&user) {
+            LockoutService::burn_password_check(password);
+
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(user.id),
+                client,
+                login_method: "password",
+                success: false,
+                failure_reason: Some("account_locked"),
+                location_id: None,
+                session_id: None,
+                is_suspicious: false,
+            }).await?;
+
+            return Err(AppError::Unauthorized("Invalid username or password".to_string()));
         }
+
+        if !bcrypt::verify(password, &user.password_hash).unwrap_or(false) {
+            lockout_service.register_failure(user.id).await?;
+
+            let location = history_service.find_location(user.id, client).await?;
+
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(user.id),
+                client,
+                login_method: "password",
+                success: false,
+                failure_reason: Some("invalid_password"),
+                location_id: location.as_ref().map(|location| location.id),
+                session_id: None,
+                is_suspicious: location.map_or(true, |location| !location.is_trusted),
+            }).await?;
+
+            return Err(AppError::Unauthorized("Invalid username or password".to_string()));
+        }
+
+        // Only reported once the password checks out, so it doesn't reveal anything about
+        // accounts the caller can't already sign in to.
+        if user.account_status != ACCOUNT_STATUS_ACTIVE {
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(user.id),
+                client,
+                login_method: "password",
+                success: false,
+                failure_reason: Some("account_inactive"),
+                location_id: None,
+                session_id: None,
+                is_suspicious: false,
+            }).await?;
+
+            return Err(AppError::Forbidden(format!("This account has been {}", user.account_status)));
+        }
+
+        let location = history_service.record_location(user.id, client).await?;
+        let is_suspicious = !location.is_trusted;
+
+        if user.totp_enabled || user.sms_mfa_enabled {
+            let token = JwtService::generate_temporary_token(user.id)
+                .await
+                .map_err(|e| AppError::Internal(format!("Error generating token: {}", e)))?;
+
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(user.id),
+                client,
+                login_method: "password",
+                success: true,
+                failure_reason: None,
+                location_id: Some(location.id),
+                session_id: None,
+                is_suspicious,
+            }).await?;
+
+            return Ok(AuthenticatedUser {
+                user,
+                token,
+                refresh_token: None,
+            });
+        }
+
+        self.enforce_location_policy(&user, &location, client, "password").await?;
+
+        let session = SessionService::new(self.state.clone())
+            .create_session(user.id, Some(location.id), client)
+            .await?;
+
+        history_service.record_attempt(LoginAttempt {
+            user_id: Some(user.id),
+            client,
+            login_method: "password",
+            success: true,
+            failure_reason: None,
+            location_id: Some(location.id),
+            session_id: Some(session.session_id),
+            is_suspicious,
+        }).await?;
+
+        lockout_service.reset(&user).await?;
+
+        let user = user_service.update_user_field(&user.id, |user| {
+            user.last_login_at = Set(Some(chrono::Utc::now()));
+        }).await?;
+
+        Ok(AuthenticatedUser {
+            user,
+            token: session.access_token,
+            refresh_token: Some(session.refresh_token),
+        })
     }
     
+    /// Applies `UNTRUSTED_LOCATION_POLICY` to a sign-in without MFA from a location the
+    /// user hasn't trusted yet. Shared by every login method, so no sign-in route is a way
+    /// around it. A blocked attempt is recorded before the error is returned.
+    pub async fn enforce_location_policy(
+        &self,
+        user: &users::Model,
+        location: &known_login_locations::Model,
+        client: &ClientInfo,
+        login_method: &str,
+    ) -> Result<(), AppError> {
+        if location.is_trusted {
+            return Ok(());
+        }
+
+        let (failure_reason, message) = match UntrustedLocationPolicy::from_config() {
+            UntrustedLocationPolicy::Allow => return Ok(()),
+            UntrustedLocationPolicy::RequireMfa => (
+                "untrusted_location",
+                "Logins from new locations require MFA. Enable MFA or sign in from a trusted device",
+            ),
+            UntrustedLocationPolicy::RequireEmailConfirmation => {
+                AccountEmailService::new(self.state.clone())
+                    .send_location_confirmation(user, location)
+                    .await?;
+
+                (
+                    "location_unconfirmed",
+                    "We've emailed you a link to confirm this new sign-in location",
+                )
+            }
+        };
+
+        LoginHistoryService::new(self.state.clone())
+            .record_attempt(LoginAttempt {
+                user_id: Some(user.id),
+                client,
+                login_method,
+                success: false,
+                failure_reason: Some(failure_reason),
+                location_id: Some(location.id),
+                session_id: None,
+                is_suspicious: true,
+            })
+            .await?;
+
+        Err(AppError::Forbidden(message.to_string()))
+    }
+
     pub async fn register_user(
         &self,
         request: RegisterRequest,
-    ) -> Result<(), String> {
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
         match ValidatorUtil::validate_email(&request.email) {
             Ok(_) => {},
-            Err(e) => return Err(e.to_string()),
+            Err(e) => return Err(AppError::field("email", e.to_string())),
         }
 
         match ValidatorUtil::validate_password(&request.password) {
             Ok(_) => {},
-            Err(e) => return Err(e.to_string()),
+            Err(e) => return Err(AppError::field("password", e.to_string())),
         }
 
         if request.password != request.confirm_password {
-            return Err("Passwords do not match".to_string());
+            return Err(AppError::field("confirmPassword", "Passwords do not match"));
         }
         
         match ValidatorUtil::validate_username(&request.username) {
             Ok(_) => {},
-            Err(e) => return Err(e.to_string()),
+            Err(e) => return Err(AppError::field("username", e.to_string())),
         }
         
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let user = UserService::new(self.state.clone())
+            .create_user(&txn, request)
+            .await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(user.id),
+            client,
+            action: "auth.registered",
+            target_type: "user",
+            target_id: Some(user.id.to_string()),
+            old_values: None,
+            new_values: Some(serde_json::json!({
+                "email": user.email,
+                "username": user.username,
+            })),
+            metadata: None,
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit registration: {}", e)))?;
+
+        if let Err(e) = AccountEmailService::new(self.state.clone()).send_verification_email(&user).await {
+            MessageUtil::error(&format!("Failed to send verification email to user {}: {}", user.id, e));
+        }
+
+        Ok(())
+    }
+
+    /// Exchanges a temporary MFA token for a full session. The temporary token is
+    /// blacklisted as consumed, so it cannot be replayed for a second login.
+    pub async fn complete_mfa_login(
+        &self,
+        claims: &Claims,
+        request: MFALoginRequest,
+        client: &ClientInfo,
+    ) -> Result<IssuedSession, AppError> {
+        let login_method = if request.is_backup_code {
+            "backup_code"
+        } else if request.is_sms_code {
+            "sms"
+        } else {
+            "totp"
+        };
+        let history_service = LoginHistoryService::new(self.state.clone());
+        let location = history_service.find_location(claims.sub, client).await?;
+        let is_suspicious = location.as_ref().map_or(true, |location| !location.is_trusted);
+
+        let user_service = UserService::new(self.state.clone());
+        let lockout_service = LockoutService::new(self.state.clone());
+
+        let user = user_service.get_user_by_id(&claims.sub)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if LockoutService::is_locked(&user) {
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(claims.sub),
+                client,
+                login_method,
+                success: false,
+                failure_reason: Some("account_locked"),
+                location_id: location.as_ref().map(|location| location.id),
+                session_id: None,
+                is_suspicious,
+            }).await?;
+
+            return Err(AppError::Unauthorized("MFA code is invalid".to_string()));
+        }
+
+        if user.account_status != ACCOUNT_STATUS_ACTIVE {
+            return Err(AppError::Forbidden(format!("This account has been {}", user.account_status)));
+        }
+
+        let mfa_service = MfaService::new(self.state.clone());
+
+        if let Err(e) = mfa_service.verify_code(&claims.sub, request).await {
+            lockout_service.register_failure(claims.sub).await?;
+
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(claims.sub),
+                client,
+                login_method,
+                success: false,
+                failure_reason: Some("invalid_mfa_code"),
+                location_id: location.as_ref().map(|location| location.id),
+                session_id: None,
+                is_suspicious,
+            }).await?;
+
+            return Err(e);
+        }
+
+        let blacklist_service = TokenBlacklistService::new(self.state.clone());
+
+        if !blacklist_service.revoke(claims, "consumed").await? {
+            return Err(AppError::Unauthorized("MFA token has already been used".to_string()));
+        }
+
+        // Passing MFA from a location proves it belongs to the user.
+        let location = match location {
+            Some(location) => Some(history_service.verify_location(location).await?),
+            None => None,
+        };
+
+        let session = SessionService::new(self.state.clone())
+            .create_session(claims.sub, location.as_ref().map(|location| location.id), client)
+            .await?;
+
+        history_service.record_attempt(LoginAttempt {
+            user_id: Some(claims.sub),
+            client,
+            login_method,
+            success: true,
+            failure_reason: None,
+            location_id: location.as_ref().map(|location| location.id),
+            session_id: Some(session.session_id),
+            is_suspicious,
+        }).await?;
+
+        lockout_service.reset(&user).await?;
+
+        user_service.update_user_field(&claims.sub, |user| {
+            user.last_login_at = Set(Some(chrono::Utc::now()));
+        }).await?;
+
+        Ok(session)
+    }
+
+    pub async fn change_password(
+        &self,
+        claims: &Claims,
+        request: ChangePasswordRequest,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
         let user_service = UserService::new(self.state.clone());
-        match user_service.create_user(request).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.to_string()),
+
+        let user = user_service.get_user_by_id(&claims.sub)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if !bcrypt::verify(&request.current_password, &user.password_hash).unwrap_or(false) {
+            return Err(AppError::field("currentPassword", "Current password is incorrect"));
+        }
+
+        match ValidatorUtil::validate_password(&request.new_password) {
+            Ok(_) => {},
+            Err(e) => return Err(AppError::field("newPassword", e.to_string())),
         }
+
+        if request.new_password != request.confirm_password {
+            return Err(AppError::field("confirmPassword", "Passwords do not match"));
+        }
+
+        let password_hash = bcrypt::hash(&request.new_password, bcrypt::DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        UserService::update_user_field_on(&txn, &user.id, |user| {
+            user.password_hash = Set(password_hash);
+            user.password_changed_at = Set(Some(chrono::Utc::now()));
+        }).await?;
+
+        SessionService::new(self.state.clone())
+            .revoke_all_sessions_on(&txn, user.id, "password_change")
+            .await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(user.id),
+            client,
+            action: "auth.password_changed",
+            target_type: "user",
+            target_id: Some(user.id.to_string()),
+            old_values: None,
+            new_values: None,
+            metadata: None,
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit password change: {}", e)))?;
+
+        TokenBlacklistService::new(self.state.clone())
+            .revoke(claims, "password_change")
+            .await?;
+
+        Ok(())
     }
 
-    pub fn logout(&self, token: &str) -> Result<String, String> {
-        //blacklist the token or perform any necessary cleanup
-        if token.is_empty() {
-            return Err("Token cannot be empty".to_string());
+    pub async fn logout(&self, claims: &Claims) -> Result<String, AppError> {
+        if let Some(session_id) = claims.sid {
+            SessionService::new(self.state.clone())
+                .revoke_session(claims.sub, session_id, "logout")
+                .await?;
         }
 
+        TokenBlacklistService::new(self.state.clone())
+            .revoke(claims, "logout")
+            .await?;
+
         Ok("Logout successful".to_string())
     }
 }
\ No newline at end of file
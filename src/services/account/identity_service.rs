@@ -0,0 +1,262 @@
+use sea_orm::*;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{user_identities, users};
+use crate::entities::users::ACCOUNT_STATUS_ACTIVE;
+use crate::errors::AppError;
+use crate::services::account::auth_service::{AuthService, AuthenticatedUser};
+use crate::services::account::jwt_service::JwtService;
+use crate::services::account::login_history_service::{LoginAttempt, LoginHistoryService};
+use crate::services::account::session_service::SessionService;
+use crate::services::account::user_service::UserService;
+use crate::services::integrations::oauth_service::ExternalIdentity;
+use crate::utils::request_util::ClientInfo;
+
+pub struct IdentityService {
+    state: AppState,
+}
+
+impl IdentityService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Signs in the user linked to `identity`, creating an account on first use. An
+    /// existing password account with the same email is never taken over automatically;
+    /// its owner has to sign in and link the provider themselves.
+    pub async fn login_with_identity(
+        &self,
+        identity: ExternalIdentity,
+        client: &ClientInfo,
+    ) -> Result<AuthenticatedUser, AppError> {
+        let db = &self.state.db;
+        let user_service = UserService::new(self.state.clone());
+        let login_method = format!("oauth_{}", identity.provider.name());
+
+        let user = match self.find_identity(&identity).await? {
+            Some(linked) => {
+                let user_id = linked.user_id;
+
+                let mut linked: user_identities::ActiveModel = linked.into();
+                linked.last_login_at = Set(Some(chrono::Utc::now()));
+                linked.update(db)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to update identity: {}", e)))?;
+
+                user_service.get_user_by_id(&user_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?
+            }
+            None => self.create_user_from_identity(&identity).await?,
+        };
+
+        if user.account_status != ACCOUNT_STATUS_ACTIVE {
+            return Err(AppError::Forbidden(format!("This account has been {}", user.account_status)));
+        }
+
+        let history_service = LoginHistoryService::new(self.state.clone());
+        let location = history_service.record_location(user.id, client).await?;
+        let is_suspicious = !location.is_trusted;
+
+        if user.totp_enabled || user.sms_mfa_enabled {
+            let token = JwtService::generate_temporary_token(user.id)
+                .await
+                .map_err(|e| AppError::Internal(format!("Error generating token: {}", e)))?;
+
+            history_service.record_attempt(LoginAttempt {
+                user_id: Some(user.id),
+                client,
+                login_method: &login_method,
+                success: true,
+                failure_reason: None,
+                location_id: Some(location.id),
+                session_id: None,
+                is_suspicious,
+            }).await?;
+
+            return Ok(AuthenticatedUser {
+                user,
+                token,
+                refresh_token: None,
+            });
+        }
+
+        AuthService::new(self.state.clone())
+            .enforce_location_policy(&user, &location, client, &login_method)
+            .await?;
+
+        let session = SessionService::new(self.state.clone())
+            .create_session(user.id, Some(location.id), client)
+            .await?;
+
+        history_service.record_attempt(LoginAttempt {
+            user_id: Some(user.id),
+            client,
+            login_method: &login_method,
+            success: true,
+            failure_reason: None,
+            location_id: Some(location.id),
+            session_id: Some(session.session_id),
+            is_suspicious,
+        }).await?;
+
+        let user = user_service.update_user_field(&user.id, |user| {
+            user.last_login_at = Set(Some(chrono::Utc::now()));
+        }).await?;
+
+        Ok(AuthenticatedUser {
+            user,
+            token: session.access_token,
+            refresh_token: Some(session.refresh_token),
+        })
+    }
+
+    pub async fn link_identity(
+        &self,
+        user_id: Uuid,
+        identity: ExternalIdentity,
+    ) -> Result<user_identities::Model, AppError> {
+        if let Some(linked) = self.find_identity(&identity).await? {
+            if linked.user_id == user_id {
+                return Ok(linked);
+            }
+
+            return Err(AppError::Conflict(format!("This {} account is already linked to another user", identity.provider.name())));
+        }
+
+        let already_linked = user_identities::Entity::find()
+            .filter(user_identities::Column::UserId.eq(user_id))
+            .filter(user_identities::Column::Provider.eq(identity.provider.name()))
+            .count(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch identities: {}", e)))? > 0;
+
+        if already_linked {
+            return Err(AppError::Conflict(format!("You have already linked a {} account", identity.provider.name())));
+        }
+
+        self.insert_identity(user_id, &identity, &self.state.db).await
+    }
+
+    pub async fn get_identities(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<user_identities::Model>, AppError> {
+        user_identities::Entity::find()
+            .filter(user_identities::Column::UserId.eq(user_id))
+            .order_by_asc(user_identities::Column::CreatedAt)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch identities: {}", e)))
+    }
+
+    /// Accounts created through a provider have no password the user knows, so the last
+    /// identity can only go once a verified email makes a password reset possible.
+    pub async fn unlink_identity(
+        &self,
+        user_id: Uuid,
+        identity_id: Uuid,
+    ) -> Result<(), AppError> {
+        let identities = self.get_identities(user_id).await?;
+
+        let identity = identities.iter()
+            .find(|identity| identity.id == identity_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("Identity not found".to_string()))?;
+
+        if identities.len() == 1 {
+            let user = UserService::new(self.state.clone())
+                .get_user_by_id(&user_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+            if !user.email_verified {
+                return Err(AppError::validation("Verify your email before removing your last sign-in provider"));
+            }
+        }
+
+        identity.delete(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to unlink identity: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn find_identity(
+        &self,
+        identity: &ExternalIdentity,
+    ) -> Result<Option<user_identities::Model>, AppError> {
+        user_identities::Entity::find()
+            .filter(user_identities::Column::Provider.eq(identity.provider.name()))
+            .filter(user_identities::Column::ProviderSubject.eq(identity.subject.clone()))
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch identity: {}", e)))
+    }
+
+    async fn create_user_from_identity(
+        &self,
+        identity: &ExternalIdentity,
+    ) -> Result<users::Model, AppError> {
+        let email = match (&identity.email, identity.email_verified) {
+            (Some(email), true) => email.clone(),
+            _ => return Err(AppError::validation(format!("Your {} account has no verified email address", identity.provider.name()))),
+        };
+
+        let user_service = UserService::new(self.state.clone());
+
+        if user_service.get_user_by_email(&email).await?.is_some() {
+            return Err(AppError::Conflict(format!(
+                "An account with this email already exists. Sign in with your password and link {} from your account settings",
+                identity.provider.name()
+            )));
+        }
+
+        // The account gets a random password nobody knows; the user can set one through
+        // the password reset flow.
+        let password_hash = bcrypt::hash(hex::encode(rand::random::<[u8; 32]>()), bcrypt::DEFAULT_COST)
+            .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let user = users::ActiveModel {
+            email: Set(email),
+            password_hash: Set(password_hash),
+            email_verified: Set(true),
+            email_verified_at: Set(Some(chrono::Utc::now())),
+            ..users::ActiveModel::new()
+        }
+            .insert(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create user: {}", e)))?;
+
+        self.insert_identity(user.id, identity, &txn).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit user: {}", e)))?;
+
+        Ok(user)
+    }
+
+    async fn insert_identity<C: ConnectionTrait>(
+        &self,
+        user_id: Uuid,
+        identity: &ExternalIdentity,
+        conn: &C,
+    ) -> Result<user_identities::Model, AppError> {
+        user_identities::ActiveModel {
+            user_id: Set(user_id),
+            provider: Set(identity.provider.name().to_string()),
+            provider_subject: Set(identity.subject.clone()),
+            email: Set(identity.email.clone()),
+            display_name: Set(identity.display_name.clone()),
+            ..user_identities::ActiveModel::new()
+        }
+            .insert(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to link identity: {}", e)))
+    }
+}
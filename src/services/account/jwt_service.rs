@@ -8,6 +8,10 @@ use jsonwebtoken::{decode, DecodingKey, Validation, encode};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::app_state::AppState;
+use crate::errors::AppError;
+
+/// Access tokens are short-lived; clients renew them with the session's refresh token.
+pub const ACCESS_TOKEN_MINUTES: i64 = 15;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -18,6 +22,20 @@ pub struct Claims {
     pub jti: String,
     pub roles: Vec<String>,
     pub permissions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_id: Option<Uuid>,
+}
+
+impl Claims {
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|granted| granted == "all" || granted == permission)
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|granted| granted == role)
+    }
 }
 
 impl FromRequest for Claims {
@@ -27,9 +45,9 @@ impl FromRequest for Claims {
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
         match req.extensions().get::<Claims>() {
             Some(claims) => ready(Ok(claims.clone())),
-            None => ready(Err(actix_web::error::ErrorUnauthorized(
-                "No authentication token found"
-            ))),
+            None => ready(Err(
+                AppError::Unauthorized("No authentication token found".to_string()).into()
+            )),
         }
     }
 }
@@ -43,54 +61,66 @@ impl JwtService {
         Self { state }
     }
     
-    pub async fn generate_admin_token(user_id: Uuid) -> Result<String, String> {
+    pub async fn generate_admin_token(
+        user_id: Uuid,
+        session_id: Uuid,
+        roles: Vec<String>,
+    ) -> Result<String, AppError> {
         let claims = Claims {
             sub: user_id,
             purpose: "admin".to_string(),
-            exp: (chrono::Utc::now() + chrono::Duration::hours(3)).timestamp() as usize,
+            exp: (chrono::Utc::now() + chrono::Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
             iat: chrono::Utc::now().timestamp() as usize,
             jti: Uuid::new_v4().to_string(),
-            roles: vec!["admin".to_string()],
+            roles,
             permissions: vec!["all".to_string()],
+            sid: Some(session_id),
+            resource_id: None,
         };
         
         let secret = env::var("JWT_SECRET")
-            .map_err(|e| format!("JWT_SECRET must be set: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("JWT_SECRET must be set: {}", e)))?;
         
         let token = encode(
             &jsonwebtoken::Header::default(),
             &claims,
             &jsonwebtoken::EncodingKey::from_secret(secret.as_ref())
-        ).map_err(|e| e.to_string())?;
+        ).map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
         
         Ok(token)
     }
 
-    pub async fn generate_access_token(user_id: Uuid) -> Result<String, String> {
-        
+    pub async fn generate_access_token(
+        user_id: Uuid,
+        session_id: Uuid,
+        roles: Vec<String>,
+        permissions: Vec<String>,
+    ) -> Result<String, AppError> {
         let claims = Claims {
             sub: user_id,
             purpose: "access".to_string(),
-            exp: (chrono::Utc::now() + chrono::Duration::hours(3)).timestamp() as usize,
+            exp: (chrono::Utc::now() + chrono::Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
             iat: chrono::Utc::now().timestamp() as usize,
             jti: Uuid::new_v4().to_string(),
-            roles: vec!["user".to_string()],
-            permissions: vec!["none".to_string()],
+            roles,
+            permissions,
+            sid: Some(session_id),
+            resource_id: None,
         };
         
         let secret = env::var("JWT_SECRET")
-            .map_err(|e| format!("JWT_SECRET must be set: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("JWT_SECRET must be set: {}", e)))?;
         
         let token = encode(
             &jsonwebtoken::Header::default(),
             &claims,
             &jsonwebtoken::EncodingKey::from_secret(secret.as_ref())
-        ).map_err(|e| e.to_string())?;
+        ).map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
         
         Ok(token)
     }
     
-    pub async fn generate_temporary_token(user_id: Uuid) -> Result<String, String> {
+    pub async fn generate_temporary_token(user_id: Uuid) -> Result<String, AppError> {
         let claims = Claims {
             sub: user_id,
             purpose: "temporary".to_string(),
@@ -99,31 +129,65 @@ impl JwtService {
             jti: Uuid::new_v4().to_string(),
             roles: vec!["user".to_string()],
             permissions: vec!["none".to_string()],
+            sid: None,
+            resource_id: None,
         };
         
         let secret = env::var("JWT_SECRET")
-            .map_err(|e| format!("JWT_SECRET must be set: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("JWT_SECRET must be set: {}", e)))?;
         
         let token = encode(
             &jsonwebtoken::Header::default(),
             &claims,
             &jsonwebtoken::EncodingKey::from_secret(secret.as_ref())
-        ).map_err(|e| e.to_string())?;
+        ).map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
         
         Ok(token)
     }
 
-    pub async fn validate_token(token: &str) -> Result<Claims, String> {
+    /// Signs a single-purpose token (email verification, password reset, ...). These
+    /// purposes are rejected by `AuthMiddleware`, so they can never authenticate a request.
+    pub async fn generate_action_token(
+        user_id: Uuid,
+        purpose: &str,
+        resource_id: Option<Uuid>,
+        lifetime: chrono::Duration,
+    ) -> Result<String, AppError> {
+        let claims = Claims {
+            sub: user_id,
+            purpose: purpose.to_string(),
+            exp: (chrono::Utc::now() + lifetime).timestamp() as usize,
+            iat: chrono::Utc::now().timestamp() as usize,
+            jti: Uuid::new_v4().to_string(),
+            roles: vec![],
+            permissions: vec![],
+            sid: None,
+            resource_id,
+        };
+
+        let secret = env::var("JWT_SECRET")
+            .map_err(|e| AppError::Internal(format!("JWT_SECRET must be set: {}", e)))?;
+
+        let token = encode(
+            &jsonwebtoken::Header::default(),
+            &claims,
+            &jsonwebtoken::EncodingKey::from_secret(secret.as_ref())
+        ).map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
+
+        Ok(token)
+    }
+
+    pub async fn validate_token(token: &str) -> Result<Claims, AppError> {
         let secret = env::var("JWT_SECRET")
-            .map_err(|_| "JWT_SECRET must be set")?;
+            .map_err(|_| AppError::Internal("JWT_SECRET must be set".to_string()))?;
 
         let token = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &Validation::default())
-            .map_err(|e| format!("Invalid token: {}", e))?;
+            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?;
         
         Ok(token.claims)
     }
     
-    pub async fn extract_user_id_from_token(token: &str) -> Result<Uuid, String> {
+    pub async fn extract_user_id_from_token(token: &str) -> Result<Uuid, AppError> {
         let claims = Self::validate_token(token).await?;
         Ok(claims.sub)
     }
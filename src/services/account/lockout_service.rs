@@ -0,0 +1,135 @@
+use std::sync::OnceLock;
+use sea_orm::*;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::users;
+use crate::errors::AppError;
+
+/// Failures allowed before the account starts locking.
+const FREE_ATTEMPTS: i64 = 5;
+const BASE_LOCKOUT_SECONDS: i64 = 60;
+const MAX_LOCKOUT_SECONDS: i64 = 24 * 60 * 60;
+
+static DUMMY_PASSWORD_HASH: OnceLock<String> = OnceLock::new();
+
+pub struct LockoutService {
+    state: AppState,
+}
+
+impl LockoutService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub fn is_locked(user: &users::Model) -> bool {
+        user.locked_until
+            .map_or(false, |locked_until| locked_until > chrono::Utc::now())
+    }
+
+    /// Counts a failed password or MFA attempt. Once the free attempts are used up, every
+    /// further failure locks the account for twice as long as the last, up to a day.
+    pub async fn register_failure(
+        &self,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let user = users::Entity::find_by_id(user_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let attempts = user.login_attempts.unwrap_or(0) + 1;
+
+        let mut user: users::ActiveModel = user.into();
+        user.login_attempts = Set(Some(attempts));
+
+        if let Some(lockout) = Self::lockout_for(attempts) {
+            user.locked_until = Set(Some(chrono::Utc::now() + lockout));
+        }
+
+        user.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to record failed attempt: {}", e)))?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit failed attempt: {}", e)))
+    }
+
+    pub async fn reset(
+        &self,
+        user: &users::Model,
+    ) -> Result<(), AppError> {
+        Self::reset_on(&self.state.db, user).await
+    }
+
+    pub async fn reset_on<C: ConnectionTrait>(
+        conn: &C,
+        user: &users::Model,
+    ) -> Result<(), AppError> {
+        if user.login_attempts.unwrap_or(0) == 0 && user.locked_until.is_none() {
+            return Ok(());
+        }
+
+        let mut user: users::ActiveModel = user.clone().into();
+        user.login_attempts = Set(Some(0));
+        user.locked_until = Set(None);
+
+        user.update(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to reset failed attempts: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Runs a bcrypt verification against a throwaway hash so that requests for unknown
+    /// emails take as long as requests with a wrong password.
+    pub fn burn_password_check(password: &str) {
+        let hash = DUMMY_PASSWORD_HASH.get_or_init(|| {
+            bcrypt::hash("not-a-real-password", bcrypt::DEFAULT_COST).unwrap_or_default()
+        });
+
+        let _ = bcrypt::verify(password, hash);
+    }
+
+    /// How long `attempts` consecutive failures lock the account for, if at all.
+    fn lockout_for(attempts: i64) -> Option<chrono::Duration> {
+        (attempts >= FREE_ATTEMPTS).then(|| chrono::Duration::seconds(Self::lockout_seconds(attempts)))
+    }
+
+    fn lockout_seconds(attempts: i64) -> i64 {
+        let exponent = (attempts - FREE_ATTEMPTS).clamp(0, 20) as u32;
+        (BASE_LOCKOUT_SECONDS * 2_i64.pow(exponent)).min(MAX_LOCKOUT_SECONDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_a_few_free_attempts() {
+        for attempts in 1..FREE_ATTEMPTS {
+            assert_eq!(LockoutService::lockout_for(attempts), None);
+        }
+
+        assert_eq!(LockoutService::lockout_for(FREE_ATTEMPTS), Some(chrono::Duration::seconds(BASE_LOCKOUT_SECONDS)));
+    }
+
+    #[test]
+    fn each_further_failure_doubles_the_lockout() {
+        assert_eq!(LockoutService::lockout_seconds(FREE_ATTEMPTS + 1), BASE_LOCKOUT_SECONDS * 2);
+        assert_eq!(LockoutService::lockout_seconds(FREE_ATTEMPTS + 3), BASE_LOCKOUT_SECONDS * 8);
+    }
+
+    #[test]
+    fn lockout_is_capped_at_a_day() {
+        assert_eq!(LockoutService::lockout_seconds(FREE_ATTEMPTS + 20), MAX_LOCKOUT_SECONDS);
+        assert_eq!(LockoutService::lockout_seconds(i64::MAX), MAX_LOCKOUT_SECONDS);
+    }
+}
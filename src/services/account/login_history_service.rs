@@ -0,0 +1,282 @@
+use sea_orm::*;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::{known_login_locations, login_history};
+use crate::errors::AppError;
+use crate::utils::request_util::ClientInfo;
+
+/// Trust levels stored on `known_login_locations.trust_level`.
+pub const TRUST_LEVEL_UNKNOWN: i32 = 0;
+pub const TRUST_LEVEL_VERIFIED: i32 = 1;
+pub const TRUST_LEVEL_USER_TRUSTED: i32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UntrustedLocationPolicy {
+    Allow,
+    RequireMfa,
+    RequireEmailConfirmation,
+}
+
+impl UntrustedLocationPolicy {
+    pub fn from_config() -> Self {
+        Self::from_name(&Config::get().untrusted_location_policy)
+    }
+
+    /// Anything unrecognised falls back to `Allow`.
+    fn from_name(name: &str) -> Self {
+        match name {
+            "require_mfa" => UntrustedLocationPolicy::RequireMfa,
+            "require_email_confirmation" => UntrustedLocationPolicy::RequireEmailConfirmation,
+            _ => UntrustedLocationPolicy::Allow,
+        }
+    }
+}
+
+pub struct LoginAttempt<'a> {
+    pub user_id: Option<Uuid>,
+    pub client: &'a ClientInfo,
+    pub login_method: &'a str,
+    pub success: bool,
+    pub failure_reason: Option<&'a str>,
+    pub location_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    pub is_suspicious: bool,
+}
+
+pub struct LoginHistoryService {
+    state: AppState,
+}
+
+impl LoginHistoryService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn record_attempt(
+        &self,
+        attempt: LoginAttempt<'_>,
+    ) -> Result<login_history::Model, AppError> {
+        login_history::ActiveModel {
+            user_id: Set(attempt.user_id),
+            ip_address: Set(attempt.client.ip_address.clone().unwrap_or_default()),
+            user_agent: Set(attempt.client.user_agent.clone()),
+            device_fingerprint: Set(attempt.client.device_fingerprint.clone()),
+            login_method: Set(attempt.login_method.to_string()),
+            success: Set(attempt.success),
+            failure_reason: Set(attempt.failure_reason.map(|reason| reason.to_string())),
+            location_id: Set(attempt.location_id),
+            session_id: Set(attempt.session_id),
+            is_suspicious: Set(attempt.is_suspicious),
+            ..login_history::ActiveModel::new()
+        }
+            .insert(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to record login attempt: {}", e)))
+    }
+
+    /// Upserts the location a successful login came from and bumps its login count. A
+    /// user's very first location is trusted automatically; any later unknown one is not.
+    pub async fn record_location(
+        &self,
+        user_id: Uuid,
+        client: &ClientInfo,
+    ) -> Result<known_login_locations::Model, AppError> {
+        let db = &self.state.db;
+        let now = chrono::Utc::now();
+
+        if let Some(location) = self.find_location(user_id, client).await? {
+            let login_count = location.login_count + 1;
+
+            let mut location: known_login_locations::ActiveModel = location.into();
+            location.login_count = Set(login_count);
+            location.last_seen_at = Set(now);
+            location.user_agent = Set(client.user_agent.clone());
+
+            return location.update(db)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to update login location: {}", e)));
+        }
+
+        let is_first_location = known_login_locations::Entity::find()
+            .filter(known_login_locations::Column::UserId.eq(user_id))
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count login locations: {}", e)))? == 0;
+
+        known_login_locations::ActiveModel {
+            user_id: Set(user_id),
+            ip_address: Set(client.ip_address.clone().unwrap_or_default()),
+            user_agent: Set(client.user_agent.clone()),
+            device_fingerprint: Set(client.device_fingerprint.clone()),
+            is_trusted: Set(is_first_location),
+            trust_level: Set(if is_first_location { TRUST_LEVEL_VERIFIED } else { TRUST_LEVEL_UNKNOWN }),
+            ..known_login_locations::ActiveModel::new()
+        }
+            .insert(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to record login location: {}", e)))
+    }
+
+    /// Finds the active location matching the client. Devices that send a fingerprint are
+    /// matched on it, so a roaming laptop stays one device; others fall back to IP address.
+    pub async fn find_location(
+        &self,
+        user_id: Uuid,
+        client: &ClientInfo,
+    ) -> Result<Option<known_login_locations::Model>, AppError> {
+        Self::location_query(user_id, client)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch login location: {}", e)))
+    }
+
+    fn location_query(
+        user_id: Uuid,
+        client: &ClientInfo,
+    ) -> Select<known_login_locations::Entity> {
+        let query = known_login_locations::Entity::find()
+            .filter(known_login_locations::Column::UserId.eq(user_id))
+            .filter(known_login_locations::Column::IsActive.eq(true));
+
+        match &client.device_fingerprint {
+            Some(fingerprint) => query.filter(known_login_locations::Column::DeviceFingerprint.eq(fingerprint.clone())),
+            None => query
+                .filter(known_login_locations::Column::DeviceFingerprint.is_null())
+                .filter(known_login_locations::Column::IpAddress.eq(client.ip_address.clone().unwrap_or_default())),
+        }
+    }
+
+    /// Marks a location as verified after the user proved themselves from it, e.g. with MFA.
+    pub async fn verify_location(
+        &self,
+        location: known_login_locations::Model,
+    ) -> Result<known_login_locations::Model, AppError> {
+        if location.is_trusted {
+            return Ok(location);
+        }
+
+        let mut location: known_login_locations::ActiveModel = location.into();
+        location.is_trusted = Set(true);
+        location.trust_level = Set(TRUST_LEVEL_VERIFIED);
+
+        location.update(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to verify login location: {}", e)))
+    }
+
+    pub async fn get_devices(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<known_login_locations::Model>, AppError> {
+        known_login_locations::Entity::find()
+            .filter(known_login_locations::Column::UserId.eq(user_id))
+            .filter(known_login_locations::Column::IsActive.eq(true))
+            .order_by_desc(known_login_locations::Column::LastSeenAt)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch devices: {}", e)))
+    }
+
+    pub async fn trust_device(
+        &self,
+        user_id: Uuid,
+        location_id: Uuid,
+    ) -> Result<known_login_locations::Model, AppError> {
+        let location = self.get_device(user_id, location_id).await?;
+
+        let mut location: known_login_locations::ActiveModel = location.into();
+        location.is_trusted = Set(true);
+        location.trust_level = Set(TRUST_LEVEL_USER_TRUSTED);
+
+        location.update(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to trust device: {}", e)))
+    }
+
+    /// Forgets a device; the next login from it is treated as coming from a new location.
+    pub async fn forget_device(
+        &self,
+        user_id: Uuid,
+        location_id: Uuid,
+    ) -> Result<(), AppError> {
+        let location = self.get_device(user_id, location_id).await?;
+
+        let mut location: known_login_locations::ActiveModel = location.into();
+        location.is_active = Set(false);
+        location.is_trusted = Set(false);
+        location.trust_level = Set(TRUST_LEVEL_UNKNOWN);
+
+        location.update(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to forget device: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get_device(
+        &self,
+        user_id: Uuid,
+        location_id: Uuid,
+    ) -> Result<known_login_locations::Model, AppError> {
+        known_login_locations::Entity::find_by_id(location_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch device: {}", e)))?
+            .filter(|location| location.user_id == user_id && location.is_active)
+            .ok_or_else(|| AppError::NotFound("Device not found".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, QueryTrait};
+    use super::*;
+
+    fn client(ip_address: &str, device_fingerprint: Option<&str>) -> ClientInfo {
+        ClientInfo {
+            ip_address: Some(ip_address.to_string()),
+            device_fingerprint: device_fingerprint.map(str::to_string),
+            ..ClientInfo::default()
+        }
+    }
+
+    fn location_sql(client: &ClientInfo) -> String {
+        LoginHistoryService::location_query(Uuid::nil(), client)
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn fingerprinted_devices_are_matched_on_the_fingerprint() {
+        let sql = location_sql(&client("198.51.100.1", Some("laptop")));
+
+        assert!(sql.contains(r#""device_fingerprint" = 'laptop'"#), "{}", sql);
+        assert!(!sql.contains("198.51.100.1"), "{}", sql);
+    }
+
+    #[test]
+    fn other_clients_are_matched_on_their_ip_address() {
+        let sql = location_sql(&client("198.51.100.1", None));
+
+        assert!(sql.contains(r#""device_fingerprint" IS NULL"#), "{}", sql);
+        assert!(sql.contains(r#""ip_address" = '198.51.100.1'"#), "{}", sql);
+    }
+
+    #[test]
+    fn forgotten_locations_are_never_matched() {
+        let sql = location_sql(&client("198.51.100.1", None));
+
+        assert!(sql.contains(r#""is_active" = TRUE"#), "{}", sql);
+    }
+
+    #[test]
+    fn unknown_policies_allow_the_login() {
+        assert_eq!(UntrustedLocationPolicy::from_name("require_mfa"), UntrustedLocationPolicy::RequireMfa);
+        assert_eq!(
+            UntrustedLocationPolicy::from_name("require_email_confirmation"),
+            UntrustedLocationPolicy::RequireEmailConfirmation
+        );
+        assert_eq!(UntrustedLocationPolicy::from_name("block"), UntrustedLocationPolicy::Allow);
+    }
+}
@@ -3,9 +3,13 @@ use sea_orm::*;
 use totp_rs::Secret;
 use uuid::Uuid;
 use crate::app_state::AppState;
+use crate::errors::AppError;
+use crate::services::account::phone_verification_service::PhoneVerificationService;
 use crate::services::account::user_service::UserService;
-use crate::entities::mfa_backup_codes;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::entities::{mfa_backup_codes, users};
 use crate::handlers::account::mfa_handler::{MFALoginRequest, MFARequest};
+use crate::utils::request_util::ClientInfo;
 
 pub struct MfaService {
     state: AppState,
@@ -19,7 +23,7 @@ impl MfaService {
     pub async fn generate_user_secret(
         &self,
         user_id: &Uuid
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let secret = Secret::generate_secret();
         let secret_base32 = secret.to_encoded().to_string();
 
@@ -30,7 +34,7 @@ impl MfaService {
             |user| {
                 user.totp_secret = Set(Some(secret_base32.clone()));
             }
-        ).await.map_err(|e| format!("Failed to update user secret: {}", e))?;
+        ).await.map_err(|e| AppError::Internal(format!("Failed to update user secret: {}", e)))?;
         
         Ok(())
     }
@@ -38,17 +42,17 @@ impl MfaService {
     pub async fn generate_qr_code(
         &self,
         user_id: &Uuid
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    ) -> Result<String, AppError> {
         let user_service = UserService::new(self.state.clone());
 
         let user = user_service.get_user_by_id(user_id).await
-            .map_err(|e| format!("Failed to fetch user: {}", e))?
-            .ok_or("User not found")?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
         
         let user_label: &str = user.email.as_ref();
         
         let secret_base32: &str = user.totp_secret.as_ref()
-            .ok_or("TOTP secret not set for user")?;
+            .ok_or_else(|| AppError::validation("TOTP secret not set for user"))?;
         
         let secret = Secret::Encoded(secret_base32.to_string());
         
@@ -57,13 +61,14 @@ impl MfaService {
             6,
             1,
             30,
-            secret.to_bytes()?,
+            secret.to_bytes()
+                .map_err(|e| AppError::Internal(format!("Invalid TOTP secret: {}", e)))?,
             Some("TCGEmporium".to_string()),
             user_label.to_string(),
-        );
+        ).map_err(|e| AppError::Internal(format!("Failed to create TOTP: {}", e)))?;
         
-        let qr_code = totp.unwrap().get_qr_base64()
-            .map_err(|e| format!("Failed to generate QR code: {}", e))?;
+        let qr_code = totp.get_qr_base64()
+            .map_err(|e| AppError::Internal(format!("Failed to generate QR code: {}", e)))?;
         
         Ok(qr_code)
     }
@@ -73,7 +78,7 @@ impl MfaService {
         user_email: &str,
         totp_code: &str,
         secret_base32: &str
-    ) -> Result<bool, String> {
+    ) -> Result<bool, AppError> {
         let secret = Secret::Encoded(secret_base32.to_string());
         
         let totp = totp_rs::TOTP::new(
@@ -82,13 +87,13 @@ impl MfaService {
             1,
             30,
             secret.to_bytes()
-            .map_err(|e| format!("Failed to parse code: {}", e))?,
+            .map_err(|e| AppError::Internal(format!("Failed to parse code: {}", e)))?,
             Some("TCGEmporium".to_string()),
             user_email.to_string(),
-        ).map_err(|e| format!("Failed to generate totp: {}", e))?;
+        ).map_err(|e| AppError::Internal(format!("Failed to generate totp: {}", e)))?;
 
         let is_valid = totp.check_current(totp_code)
-            .map_err(|e| format!("Failed to validate totp: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to validate totp: {}", e)))?;
         
         Ok(is_valid)
     }
@@ -96,16 +101,16 @@ impl MfaService {
     pub async fn verify_backup_code(
         &self,
         user_id: &Uuid, backup_code: &str
-    ) -> Result<bool, String> {
+    ) -> Result<bool, AppError> {
         let backup_codes = self.get_backup_codes(&user_id)
             .await
-            .map_err(|e| format!("Failed to fetch backup codes: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch backup codes: {}", e)))?;
         
         for code in backup_codes {
             let code_hashed = &code.code_hash;
             
             let correct = bcrypt::verify(backup_code, &code_hashed)
-                .map_err(|e| format!("Failed to verify backup code: {}", e))?;
+                .map_err(|e| AppError::Internal(format!("Failed to verify backup code: {}", e)))?;
             
             if correct && code.used_at.is_none() {
                 let db = &self.state.db;
@@ -114,92 +119,80 @@ impl MfaService {
                 active_code.used_at = Set(Some(chrono::Utc::now()));
                 
                 active_code.update(db).await
-                    .map_err(|e| format!("Failed to update backup code: {}", e))?;
+                    .map_err(|e| AppError::Internal(format!("Failed to update backup code: {}", e)))?;
                 
                 return Ok(true);
             }
         }
         
-        Err("Invalid backup code".to_string())
+        Err(AppError::Unauthorized("Invalid backup code".to_string()))
     }
 
     pub async fn enable_mfa_for_user(
         &self,
         user_id: &Uuid,
-        code: &str
-    ) -> Result<(), String> {
+        code: &str,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
         let user_service = UserService::new(self.state.clone());
 
         let user = user_service.get_user_by_id(&user_id)
             .await
-            .map_err(|e| format!("Failed to fetch user: {}", e))?
-            .ok_or_else(||"User not found")?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         if user.totp_enabled {
-            return Err("MFA is already enabled for this user".to_string());
+            return Err(AppError::Conflict("MFA is already enabled for this user".to_string()));
         }
 
         let totp_secret = user.totp_secret
             .as_ref()
-            .ok_or_else(|| "MFA secret not set for user")?;
+            .ok_or_else(|| AppError::validation("MFA secret not set for user"))?;
         
         self.verify_totp_code(&user.email, code, totp_secret)
             .await
-            .map_err(|e| format!("Failed to verify TOTP code: {}", e))?;
-        
-        user_service.update_user_field(
-            &user_id,
-            |user| {
-                user.totp_enabled = Set(true);
-            }
-        )
-        .await
-        .map_err(|e| format!("Failed to enable MFA for user: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to verify TOTP code: {}", e)))?;
         
-        Ok(())
+        self.switch_factor(user_id, client, "mfa.totp_enabled", "totp_enabled", true, |user| {
+            user.totp_enabled = Set(true);
+        }).await
     }
     
     pub async fn disable_mfa_for_user(
         &self,
         user_id: &Uuid,
         request: MFARequest,
-    ) -> Result<(), String> {
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
         let user_service = UserService::new(self.state.clone());
 
         let user = user_service.get_user_by_id(&user_id)
             .await
-            .map_err(|e| format!("Failed to fetch user: {}", e))?
-            .ok_or_else(|| "User not found")?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         if !user.totp_enabled {
-            return Err("MFA is not enabled for this user".to_string());
+            return Err(AppError::validation("MFA is not enabled for this user"));
         }
 
         let totp_secret = user.totp_secret
             .as_ref()
-            .ok_or_else(|| "MFA secret not set for user")?;
+            .ok_or_else(|| AppError::validation("MFA secret not set for user"))?;
         
         self.verify_totp_code(&user.email, &request.mfa_code, totp_secret)
             .await
-            .map_err(|e| format!("Failed to verify TOTP code: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to verify TOTP code: {}", e)))?;
         
-        user_service.update_user_field(
-            &user_id,
-            |user| {
-                user.totp_enabled = Set(false);
-                user.totp_secret = Set(None);
-            }
-        )
-        .await
-        .map_err(|e| format!("Failed to disable MFA for user: {}", e))?;
-        
-        Ok(())
+        self.switch_factor(user_id, client, "mfa.totp_disabled", "totp_enabled", false, |user| {
+            user.totp_enabled = Set(false);
+            user.totp_secret = Set(None);
+        }).await
     }
     
     async fn generate_backup_codes(
         &self,
         user_id: &Uuid
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let db = &self.state.db;
         
         let mut codes = Vec::new();
@@ -207,7 +200,7 @@ impl MfaService {
         for i in 0..8 {
             let code = format!("{:08}", rand::random::<u32>() % 100_000_000);
             let code_hash = bcrypt::hash(&code, 4)
-                .map_err(|e| format!("Failed to hash backup code: {}", e))?;
+                .map_err(|e| AppError::Internal(format!("Failed to hash backup code: {}", e)))?;
             
             let backup_code = mfa_backup_codes::ActiveModel {
                 id: Set(Uuid::new_v4()),
@@ -221,7 +214,7 @@ impl MfaService {
             };
             
             let inserted_code = backup_code.insert(db).await
-                .map_err(|e| format!("Failed to insert backup code: {}", e))?;
+                .map_err(|e| AppError::Internal(format!("Failed to insert backup code: {}", e)))?;
             
             codes.push(inserted_code);
         }
@@ -232,14 +225,14 @@ impl MfaService {
     async fn get_backup_codes(
         &self,
         user_id: &Uuid
-    ) -> Result<Vec<mfa_backup_codes::Model>, String> {
+    ) -> Result<Vec<mfa_backup_codes::Model>, AppError> {
         let db = &self.state.db;
         
         let backup_codes = mfa_backup_codes::Entity::find()
             .filter(mfa_backup_codes::Column::UserId.eq(*user_id))
             .all(db)
             .await
-            .map_err(|e| format!("Failed to fetch backup codes: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch backup codes: {}", e)))?;
         
         Ok(backup_codes)
     }
@@ -247,28 +240,127 @@ impl MfaService {
     async fn clear_backup_codes(
         &self,
         user_id: &Uuid
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let db = &self.state.db;
         
         mfa_backup_codes::Entity::delete_many()
             .filter(mfa_backup_codes::Column::UserId.eq(*user_id))
             .exec(db)
             .await
-            .map_err(|e| format!("Failed to clear backup codes: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to clear backup codes: {}", e)))?;
         
         Ok(())
     }
 
+    /// SMS codes go to the user's verified phone number, so one must be on file first.
+    pub async fn enable_sms_mfa_for_user(
+        &self,
+        user_id: &Uuid,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        let user_service = UserService::new(self.state.clone());
+
+        let user = user_service.get_user_by_id(&user_id)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if user.sms_mfa_enabled {
+            return Err(AppError::Conflict("SMS verification is already enabled for this user".to_string()));
+        }
+
+        if !user.phone_verified || user.phone_number.is_none() {
+            return Err(AppError::validation("Verify a phone number before enabling SMS verification"));
+        }
+
+        self.switch_factor(user_id, client, "mfa.sms_enabled", "sms_mfa_enabled", true, |user| {
+            user.sms_mfa_enabled = Set(true);
+        }).await
+    }
+
+    pub async fn disable_sms_mfa_for_user(
+        &self,
+        user_id: &Uuid,
+        request: MFARequest,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        let user_service = UserService::new(self.state.clone());
+
+        let user = user_service.get_user_by_id(&user_id)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if !user.sms_mfa_enabled {
+            return Err(AppError::validation("SMS verification is not enabled for this user"));
+        }
+
+        PhoneVerificationService::new(self.state.clone())
+            .verify_mfa_code(user.id, &request.mfa_code)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to verify SMS code: {}", e)))?;
+
+        self.switch_factor(user_id, client, "mfa.sms_disabled", "sms_mfa_enabled", false, |user| {
+            user.sms_mfa_enabled = Set(false);
+        }).await
+    }
+
+    /// Switches one of the user's second factors on or off and records it in the same
+    /// transaction.
+    async fn switch_factor<F>(
+        &self,
+        user_id: &Uuid,
+        client: &ClientInfo,
+        action: &str,
+        setting: &str,
+        enabled: bool,
+        update_fn: F,
+    ) -> Result<(), AppError>
+    where
+        F: FnOnce(&mut users::ActiveModel),
+    {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        UserService::update_user_field_on(&txn, user_id, update_fn).await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(*user_id),
+            client,
+            action,
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: Some(serde_json::json!({ setting: !enabled })),
+            new_values: Some(serde_json::json!({ setting: enabled })),
+            metadata: None,
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit MFA settings: {}", e)))
+    }
+
     pub async fn verify_code(
         &self,
         user_id: &Uuid,
         request: MFALoginRequest
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let user_service = UserService::new(self.state.clone());
         let user = user_service.get_user_by_id(&user_id)
             .await
-            .map_err(|e| format!("Failed to fetch user: {}", e))?
-            .ok_or_else(|| "User not found")?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if request.is_sms_code {
+            if !user.sms_mfa_enabled {
+                return Err(AppError::validation("SMS verification is not enabled for this user"));
+            }
+
+            return PhoneVerificationService::new(self.state.clone())
+                .verify_mfa_code(user.id, &request.mfa_code)
+                .await;
+        }
 
         if let Some(secret) = user.totp_secret {
             let is_valid = if request.is_backup_code {
@@ -286,12 +378,12 @@ impl MfaService {
 
             match is_valid {
                 Ok(true) => Ok(()),
-                Ok(false) => Err("MFA code is invalid".to_string()),
-                Err(e) => Err(format!("Failed to verify code: {}", e)),
+                Ok(false) => Err(AppError::Unauthorized("MFA code is invalid".to_string())),
+                Err(e) => Err(AppError::Internal(format!("Failed to verify code: {}", e))),
             }
 
         } else {
-            Err("MFA is not enabled for this user".to_string())
+            Err(AppError::validation("MFA is not enabled for this user"))
         }
     }
 }
\ No newline at end of file
@@ -2,3 +2,10 @@ pub mod auth_service;
 pub mod jwt_service;
 pub mod mfa_service;
 pub mod user_service;
+pub mod token_blacklist_service;
+pub mod session_service;
+pub mod login_history_service;
+pub mod lockout_service;
+pub mod account_email_service;
+pub mod phone_verification_service;
+pub mod identity_service;
@@ -0,0 +1,303 @@
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{phone_verification_codes, users};
+use crate::errors::AppError;
+use crate::services::account::user_service::UserService;
+use crate::services::integrations::twilio_service::SmsMessage;
+use crate::utils::validator_util::ValidatorUtil;
+
+pub const PURPOSE_PHONE_VERIFICATION: &str = "phone_verification";
+pub const PURPOSE_MFA: &str = "mfa";
+
+const CODE_LIFETIME_MINUTES: i64 = 10;
+const MAX_ATTEMPTS: i32 = 5;
+const RESEND_COOLDOWN_SECONDS: i64 = 60;
+const MAX_CODES_PER_HOUR: u64 = 5;
+
+pub struct PhoneVerificationService {
+    state: AppState,
+}
+
+impl PhoneVerificationService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Texts a code to a number the user wants to add. The number is only stored on the
+    /// user once the code comes back. While SMS verification is on, moving it to another
+    /// number also takes a code sent to the current one, so a stolen session can't redirect
+    /// the second factor.
+    pub async fn start_phone_verification(
+        &self,
+        user_id: Uuid,
+        phone_number: &str,
+        mfa_code: Option<&str>,
+    ) -> Result<(), AppError> {
+        match ValidatorUtil::validate_phone_number(phone_number) {
+            Ok(_) => {}
+            Err(e) => return Err(AppError::field("phoneNumber", e.to_string())),
+        }
+
+        let user = UserService::new(self.state.clone())
+            .get_user_by_id(&user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        if user.sms_mfa_enabled && user.phone_number.as_deref() != Some(phone_number) {
+            let mfa_code = mfa_code.ok_or_else(|| AppError::field(
+                "mfaCode",
+                "Enter the code sent to your current phone number to change it",
+            ))?;
+
+            self.verify_mfa_code(user_id, mfa_code).await?;
+        }
+
+        let taken = users::Entity::find()
+            .filter(users::Column::PhoneNumber.eq(phone_number))
+            .filter(users::Column::PhoneVerified.eq(true))
+            .filter(users::Column::Id.ne(user_id))
+            .count(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to check phone number: {}", e)))? > 0;
+
+        if taken {
+            return Err(AppError::Conflict("Phone number is already in use".to_string()));
+        }
+
+        self.send_code(user_id, phone_number, PURPOSE_PHONE_VERIFICATION).await
+    }
+
+    pub async fn confirm_phone_verification(
+        &self,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<users::Model, AppError> {
+        let verification = self.check_code(user_id, PURPOSE_PHONE_VERIFICATION, code).await?;
+
+        UserService::new(self.state.clone())
+            .update_user_field(&user_id, |user| {
+                user.phone_number = Set(Some(verification.phone_number.clone()));
+                user.phone_verified = Set(true);
+                user.phone_verified_at = Set(Some(chrono::Utc::now()));
+            })
+            .await
+    }
+
+    /// Texts a login code to the user's verified number.
+    pub async fn send_mfa_code(
+        &self,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        let user = UserService::new(self.state.clone())
+            .get_user_by_id(&user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let phone_number = match (&user.phone_number, user.phone_verified, user.sms_mfa_enabled) {
+            (Some(phone_number), true, true) => phone_number.clone(),
+            _ => return Err(AppError::validation("SMS verification is not enabled for this user")),
+        };
+
+        self.send_code(user_id, &phone_number, PURPOSE_MFA).await
+    }
+
+    pub async fn verify_mfa_code(
+        &self,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<(), AppError> {
+        self.check_code(user_id, PURPOSE_MFA, code).await?;
+
+        Ok(())
+    }
+
+    async fn send_code(
+        &self,
+        user_id: Uuid,
+        phone_number: &str,
+        purpose: &str,
+    ) -> Result<(), AppError> {
+        let db = &self.state.db;
+        let now = chrono::Utc::now();
+
+        let recent_codes = phone_verification_codes::Entity::find()
+            .filter(phone_verification_codes::Column::UserId.eq(user_id))
+            .filter(phone_verification_codes::Column::CreatedAt.gt(now - chrono::Duration::hours(1)))
+            .order_by_desc(phone_verification_codes::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch verification codes: {}", e)))?;
+
+        Self::check_send_limits(&recent_codes, purpose, now)?;
+
+        // Only the newest code for a purpose is usable.
+        phone_verification_codes::Entity::update_many()
+            .col_expr(phone_verification_codes::Column::ConsumedAt, Expr::value(now))
+            .filter(phone_verification_codes::Column::UserId.eq(user_id))
+            .filter(phone_verification_codes::Column::Purpose.eq(purpose))
+            .filter(phone_verification_codes::Column::ConsumedAt.is_null())
+            .exec(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to invalidate previous codes: {}", e)))?;
+
+        let code = format!("{:06}", rand::random::<u32>() % 1_000_000);
+
+        phone_verification_codes::ActiveModel {
+            user_id: Set(user_id),
+            phone_number: Set(phone_number.to_string()),
+            purpose: Set(purpose.to_string()),
+            code_hash: Set(Self::hash_code(&code)),
+            expires_at: Set(now + chrono::Duration::minutes(CODE_LIFETIME_MINUTES)),
+            ..phone_verification_codes::ActiveModel::new()
+        }
+            .insert(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to store verification code: {}", e)))?;
+
+        self.state.sms_sender.send(SmsMessage {
+            to: phone_number.to_string(),
+            body: format!(
+                "Your TCGEmporium code is {}. It expires in {} minutes.",
+                code, CODE_LIFETIME_MINUTES
+            ),
+        }).await
+    }
+
+    /// Checks `code` against the user's newest live code for `purpose` and consumes it.
+    /// Each code allows a handful of guesses before it is burnt.
+    async fn check_code(
+        &self,
+        user_id: Uuid,
+        purpose: &str,
+        code: &str,
+    ) -> Result<phone_verification_codes::Model, AppError> {
+        let db = &self.state.db;
+        let now = chrono::Utc::now();
+
+        let verification = phone_verification_codes::Entity::find()
+            .filter(phone_verification_codes::Column::UserId.eq(user_id))
+            .filter(phone_verification_codes::Column::Purpose.eq(purpose))
+            .filter(phone_verification_codes::Column::ConsumedAt.is_null())
+            .filter(phone_verification_codes::Column::ExpiresAt.gt(now))
+            .order_by_desc(phone_verification_codes::Column::CreatedAt)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch verification code: {}", e)))?
+            .ok_or_else(|| AppError::Unauthorized("Verification code is invalid or has expired".to_string()))?;
+
+        if verification.attempts >= MAX_ATTEMPTS {
+            return Err(AppError::validation("Too many incorrect attempts, please request a new code"));
+        }
+
+        if verification.code_hash != Self::hash_code(code) {
+            phone_verification_codes::Entity::update_many()
+                .col_expr(
+                    phone_verification_codes::Column::Attempts,
+                    Expr::col(phone_verification_codes::Column::Attempts).add(1),
+                )
+                .filter(phone_verification_codes::Column::Id.eq(verification.id))
+                .exec(db)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to record attempt: {}", e)))?;
+
+            return Err(AppError::Unauthorized("Verification code is invalid or has expired".to_string()));
+        }
+
+        let result = phone_verification_codes::Entity::update_many()
+            .col_expr(phone_verification_codes::Column::ConsumedAt, Expr::value(now))
+            .filter(phone_verification_codes::Column::Id.eq(verification.id))
+            .filter(phone_verification_codes::Column::ConsumedAt.is_null())
+            .exec(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to consume verification code: {}", e)))?;
+
+        if result.rows_affected == 0 {
+            return Err(AppError::Unauthorized("Verification code is invalid or has expired".to_string()));
+        }
+
+        Ok(verification)
+    }
+
+    /// `recent_codes` are the user's codes from the last hour, newest first. The cooldown
+    /// is per purpose: changing a number takes an MFA code and then a verification code in
+    /// quick succession.
+    fn check_send_limits(
+        recent_codes: &[phone_verification_codes::Model],
+        purpose: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), AppError> {
+        if let Some(latest) = recent_codes.iter().find(|recent| recent.purpose == purpose) {
+            if latest.created_at > now - chrono::Duration::seconds(RESEND_COOLDOWN_SECONDS) {
+                return Err(AppError::validation("Please wait a minute before requesting another code"));
+            }
+        }
+
+        if recent_codes.len() as u64 >= MAX_CODES_PER_HOUR {
+            return Err(AppError::validation("Too many codes requested, please try again later"));
+        }
+
+        Ok(())
+    }
+
+    fn hash_code(code: &str) -> String {
+        hex::encode(Sha256::digest(code.trim().as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sent_code(purpose: &str, seconds_ago: i64) -> phone_verification_codes::Model {
+        let created_at = chrono::Utc::now() - chrono::Duration::seconds(seconds_ago);
+
+        phone_verification_codes::Model {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            phone_number: "+15555550100".to_string(),
+            purpose: purpose.to_string(),
+            code_hash: PhoneVerificationService::hash_code("123456"),
+            attempts: 0,
+            expires_at: created_at + chrono::Duration::minutes(CODE_LIFETIME_MINUTES),
+            consumed_at: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn resends_wait_out_the_cooldown() {
+        let now = chrono::Utc::now();
+
+        let recent = vec![sent_code(PURPOSE_MFA, 10)];
+        assert!(PhoneVerificationService::check_send_limits(&recent, PURPOSE_MFA, now).is_err());
+
+        let recent = vec![sent_code(PURPOSE_MFA, RESEND_COOLDOWN_SECONDS + 10)];
+        assert!(PhoneVerificationService::check_send_limits(&recent, PURPOSE_MFA, now).is_ok());
+    }
+
+    #[test]
+    fn cooldown_is_per_purpose() {
+        let recent = vec![sent_code(PURPOSE_MFA, 10)];
+
+        assert!(PhoneVerificationService::check_send_limits(&recent, PURPOSE_PHONE_VERIFICATION, chrono::Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn caps_the_codes_sent_per_hour() {
+        let recent: Vec<_> = (1..=MAX_CODES_PER_HOUR as i64)
+            .map(|i| sent_code(PURPOSE_PHONE_VERIFICATION, i * 600))
+            .collect();
+
+        assert!(PhoneVerificationService::check_send_limits(&recent, PURPOSE_MFA, chrono::Utc::now()).is_err());
+        assert!(PhoneVerificationService::check_send_limits(&recent[1..], PURPOSE_MFA, chrono::Utc::now()).is_ok());
+    }
+
+    #[test]
+    fn code_hash_ignores_surrounding_whitespace() {
+        assert_eq!(PhoneVerificationService::hash_code(" 123456\n"), PhoneVerificationService::hash_code("123456"));
+        assert_ne!(PhoneVerificationService::hash_code("123456"), PhoneVerificationService::hash_code("654321"));
+    }
+}
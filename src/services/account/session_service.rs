@@ -0,0 +1,427 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::user_sessions;
+use crate::entities::user_roles::UserRoleType;
+use crate::errors::AppError;
+use crate::services::account::jwt_service::{JwtService, ACCESS_TOKEN_MINUTES};
+use crate::services::account::token_blacklist_service::TokenBlacklistService;
+use crate::services::admin::admin_service::AdminService;
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
+
+/// `last_activity_at` is only rewritten once it is at least this stale, so a burst of
+/// requests costs a single UPDATE.
+const ACTIVITY_GRANULARITY_SECONDS: i64 = 60;
+/// Once this many sessions are remembered, the ones touched too long ago to matter are dropped.
+const MAX_TRACKED_SESSIONS: usize = 10_000;
+
+/// When this process last bumped each session, so requests inside the same minute don't
+/// even spawn a task.
+static LAST_TOUCHED: OnceLock<Mutex<HashMap<Uuid, Instant>>> = OnceLock::new();
+
+#[derive(Debug)]
+pub struct IssuedSession {
+    pub session_id: Uuid,
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_activity_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub current: bool,
+}
+
+pub struct SessionService {
+    state: AppState,
+}
+
+impl SessionService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Opens a session and issues its first access/refresh token pair. The refresh token
+    /// is `{session_id}.{secret}`; only a SHA-256 of the secret is stored.
+    pub async fn create_session(
+        &self,
+        user_id: Uuid,
+        location_id: Option<Uuid>,
+        client: &ClientInfo,
+    ) -> Result<IssuedSession, AppError> {
+        let session_id = Uuid::new_v4();
+        let secret = Self::generate_secret();
+        let (access_token, access_jti) = self.issue_access_token(user_id, session_id).await?;
+
+        user_sessions::ActiveModel {
+            id: Set(session_id),
+            user_id: Set(user_id),
+            session_token: Set(access_jti),
+            refresh_token: Set(Some(Self::hash_secret(&secret))),
+            ip_address: Set(client.ip_address.clone()),
+            user_agent: Set(client.user_agent.clone()),
+            location_id: Set(location_id),
+            expires_at: Set(chrono::Utc::now() + chrono::Duration::days(Config::get().session_lifetime_days)),
+            ..user_sessions::ActiveModel::new()
+        }
+            .insert(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create session: {}", e)))?;
+
+        Ok(IssuedSession {
+            session_id,
+            access_token,
+            refresh_token: format!("{}.{}", session_id, secret),
+        })
+    }
+
+    /// Rotates the session's refresh token and issues a new access token. Presenting a
+    /// refresh token that has already been rotated means it leaked, so the whole session
+    /// is revoked.
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+        client: &ClientInfo,
+    ) -> Result<IssuedSession, AppError> {
+        let (session_id, secret) = Self::parse_refresh_token(refresh_token)?;
+
+        let session = user_sessions::Entity::find_by_id(session_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch session: {}", e)))?
+            .ok_or_else(|| AppError::Unauthorized("Invalid refresh token".to_string()))?;
+
+        if !session.is_active || session.expires_at < chrono::Utc::now() {
+            return Err(AppError::Unauthorized("Session has expired".to_string()));
+        }
+
+        let presented_hash = Self::hash_secret(secret);
+
+        if Self::is_reused(&session, &presented_hash) {
+            self.revoke(&self.state.db, &session, "refresh_token_reuse").await?;
+            return Err(AppError::Unauthorized("Refresh token has already been used".to_string()));
+        }
+
+        let new_secret = Self::generate_secret();
+        let (access_token, access_jti) = self.issue_access_token(session.user_id, session.id).await?;
+
+        // Matching on the old hash makes rotation single-winner when two refreshes race.
+        let result = user_sessions::Entity::update_many()
+            .col_expr(user_sessions::Column::RefreshToken, Expr::value(Self::hash_secret(&new_secret)))
+            .col_expr(user_sessions::Column::SessionToken, Expr::value(access_jti))
+            .col_expr(user_sessions::Column::IpAddress, Expr::value(client.ip_address.clone()))
+            .col_expr(user_sessions::Column::UserAgent, Expr::value(client.user_agent.clone()))
+            .col_expr(user_sessions::Column::LastActivityAt, Expr::value(chrono::Utc::now()))
+            .filter(user_sessions::Column::Id.eq(session.id))
+            .filter(user_sessions::Column::IsActive.eq(true))
+            .filter(user_sessions::Column::RefreshToken.eq(presented_hash))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to rotate refresh token: {}", e)))?;
+
+        if result.rows_affected == 0 {
+            self.revoke(&self.state.db, &session, "refresh_token_reuse").await?;
+            return Err(AppError::Unauthorized("Refresh token has already been used".to_string()));
+        }
+
+        Ok(IssuedSession {
+            session_id: session.id,
+            access_token,
+            refresh_token: format!("{}.{}", session.id, new_secret),
+        })
+    }
+
+    pub async fn get_active_sessions(
+        &self,
+        user_id: Uuid,
+        current_session_id: Option<Uuid>,
+    ) -> Result<Vec<SessionResponse>, AppError> {
+        let sessions = user_sessions::Entity::find()
+            .filter(user_sessions::Column::UserId.eq(user_id))
+            .filter(user_sessions::Column::IsActive.eq(true))
+            .filter(user_sessions::Column::ExpiresAt.gt(chrono::Utc::now()))
+            .order_by_desc(user_sessions::Column::LastActivityAt)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch sessions: {}", e)))?;
+
+        Ok(sessions.into_iter()
+            .map(|session| SessionResponse {
+                current: Some(session.id) == current_session_id,
+                id: session.id,
+                ip_address: session.ip_address,
+                user_agent: session.user_agent,
+                created_at: session.created_at,
+                last_activity_at: session.last_activity_at,
+                expires_at: session.expires_at,
+            })
+            .collect())
+    }
+
+    pub async fn revoke_session(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        let session = user_sessions::Entity::find_by_id(session_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch session: {}", e)))?
+            .filter(|session| session.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound("Session not found".to_string()))?;
+
+        self.revoke(&self.state.db, &session, reason).await
+    }
+
+    pub async fn revoke_all_sessions(
+        &self,
+        user_id: Uuid,
+        reason: &str,
+    ) -> Result<usize, AppError> {
+        self.revoke_all_sessions_on(&self.state.db, user_id, reason).await
+    }
+
+    pub async fn revoke_all_sessions_on<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        user_id: Uuid,
+        reason: &str,
+    ) -> Result<usize, AppError> {
+        let sessions = user_sessions::Entity::find()
+            .filter(user_sessions::Column::UserId.eq(user_id))
+            .filter(user_sessions::Column::IsActive.eq(true))
+            .all(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch sessions: {}", e)))?;
+
+        for session in &sessions {
+            self.revoke(conn, session, reason).await?;
+        }
+
+        Ok(sessions.len())
+    }
+
+    /// Bumps `last_activity_at` in the background so authenticated requests never wait on it.
+    /// Other instances may still bump the same session, which the conditional UPDATE absorbs.
+    pub fn touch_session(state: AppState, session_id: Uuid) {
+        let due = LAST_TOUCHED.get_or_init(Default::default)
+            .lock()
+            .map(|mut touched| Self::claim_touch(&mut touched, session_id, Instant::now()))
+            .unwrap_or(true);
+
+        if !due {
+            return;
+        }
+
+        actix_web::rt::spawn(async move {
+            let now = chrono::Utc::now();
+
+            let touched = user_sessions::Entity::update_many()
+                .col_expr(user_sessions::Column::LastActivityAt, Expr::value(now))
+                .filter(user_sessions::Column::Id.eq(session_id))
+                .filter(user_sessions::Column::IsActive.eq(true))
+                .filter(user_sessions::Column::LastActivityAt.lt(now - chrono::Duration::seconds(ACTIVITY_GRANULARITY_SECONDS)))
+                .exec(&state.db)
+                .await;
+
+            if let Err(e) = touched {
+                MessageUtil::error(&format!("Failed to record activity for session {}: {}", session_id, e));
+            }
+        });
+    }
+
+    /// Returns whether the session is due for a touch and, if so, remembers it as touched.
+    fn claim_touch(
+        touched: &mut HashMap<Uuid, Instant>,
+        session_id: Uuid,
+        now: Instant,
+    ) -> bool {
+        let granularity = Duration::from_secs(ACTIVITY_GRANULARITY_SECONDS as u64);
+
+        if touched.get(&session_id).map_or(false, |last| now.duration_since(*last) < granularity) {
+            return false;
+        }
+
+        if touched.len() >= MAX_TRACKED_SESSIONS {
+            touched.retain(|_, last| now.duration_since(*last) < granularity);
+        }
+
+        touched.insert(session_id, now);
+        true
+    }
+
+    async fn revoke<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        session: &user_sessions::Model,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        user_sessions::Entity::update_many()
+            .col_expr(user_sessions::Column::IsActive, Expr::value(false))
+            .col_expr(user_sessions::Column::RefreshToken, Expr::value(Option::<String>::None))
+            .filter(user_sessions::Column::Id.eq(session.id))
+            .exec(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to revoke session: {}", e)))?;
+
+        // The session's current access token would otherwise stay valid until it expires.
+        TokenBlacklistService::new(self.state.clone())
+            .revoke_jti_on(
+                conn,
+                &session.session_token,
+                session.user_id,
+                "access",
+                chrono::Utc::now() + chrono::Duration::minutes(ACCESS_TOKEN_MINUTES),
+                reason,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Roles are read from `user_roles` on every issue, so grants and revocations reach
+    /// the token at the next refresh.
+    async fn issue_access_token(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(String, String), AppError> {
+        let active_roles = AdminService::new(self.state.clone())
+            .get_active_roles(&user_id)
+            .await?;
+
+        let mut roles = vec!["user".to_string()];
+        let mut permissions: Vec<String> = Vec::new();
+
+        for role in &active_roles {
+            roles.push(role.as_str().to_string());
+
+            for permission in role.permissions() {
+                if !permissions.iter().any(|granted| granted == permission) {
+                    permissions.push(permission.to_string());
+                }
+            }
+        }
+
+        let token = if active_roles.contains(&UserRoleType::Admin) {
+            JwtService::generate_admin_token(user_id, session_id, roles).await
+        } else {
+            JwtService::generate_access_token(user_id, session_id, roles, permissions).await
+        }.map_err(|e| AppError::Internal(format!("Error generating token: {}", e)))?;
+
+        let claims = JwtService::validate_token(&token).await?;
+
+        Ok((token, claims.jti))
+    }
+
+    /// Refresh tokens are `<session id>.<secret>`.
+    fn parse_refresh_token(refresh_token: &str) -> Result<(Uuid, &str), AppError> {
+        let invalid = || AppError::Unauthorized("Invalid refresh token".to_string());
+
+        let (session_id, secret) = refresh_token.split_once('.').ok_or_else(invalid)?;
+        let session_id = Uuid::parse_str(session_id).map_err(|_| invalid())?;
+
+        Ok((session_id, secret))
+    }
+
+    /// Only the newest refresh token of a session is accepted; any other one for it has
+    /// already been rotated away.
+    fn is_reused(session: &user_sessions::Model, presented_hash: &str) -> bool {
+        session.refresh_token.as_deref() != Some(presented_hash)
+    }
+
+    fn generate_secret() -> String {
+        hex::encode(rand::random::<[u8; 32]>())
+    }
+
+    fn hash_secret(secret: &str) -> String {
+        hex::encode(Sha256::digest(secret.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(refresh_secret: Option<&str>) -> user_sessions::Model {
+        let now = chrono::Utc::now();
+
+        user_sessions::Model {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            session_token: "jti".to_string(),
+            refresh_token: refresh_secret.map(SessionService::hash_secret),
+            ip_address: None,
+            user_agent: None,
+            location_id: None,
+            is_active: true,
+            created_at: now,
+            last_activity_at: now,
+            expires_at: now + chrono::Duration::days(30),
+        }
+    }
+
+    #[test]
+    fn parses_refresh_tokens() {
+        let session_id = Uuid::new_v4();
+        let (parsed, secret) = SessionService::parse_refresh_token(&format!("{}.abc", session_id)).unwrap();
+
+        assert_eq!((parsed, secret), (session_id, "abc"));
+        assert!(SessionService::parse_refresh_token("abc").is_err());
+        assert!(SessionService::parse_refresh_token("not-a-uuid.abc").is_err());
+    }
+
+    #[test]
+    fn only_the_newest_refresh_token_is_accepted() {
+        let current = SessionService::generate_secret();
+        let rotated = SessionService::generate_secret();
+        let session = session(Some(&current));
+
+        assert!(!SessionService::is_reused(&session, &SessionService::hash_secret(&current)));
+        assert!(SessionService::is_reused(&session, &SessionService::hash_secret(&rotated)));
+    }
+
+    #[test]
+    fn sessions_without_a_refresh_token_reject_every_one() {
+        let secret = SessionService::generate_secret();
+
+        assert!(SessionService::is_reused(&session(None), &SessionService::hash_secret(&secret)));
+    }
+
+    #[test]
+    fn touches_a_session_at_most_once_a_minute() {
+        let mut touched = HashMap::new();
+        let session_id = Uuid::new_v4();
+        let start = Instant::now();
+
+        assert!(SessionService::claim_touch(&mut touched, session_id, start));
+        assert!(!SessionService::claim_touch(&mut touched, session_id, start + Duration::from_secs(30)));
+        assert!(SessionService::claim_touch(&mut touched, session_id, start + Duration::from_secs(61)));
+        assert!(SessionService::claim_touch(&mut touched, Uuid::new_v4(), start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn forgets_stale_sessions_once_the_map_is_full() {
+        let start = Instant::now();
+        let mut touched: HashMap<Uuid, Instant> = (0..MAX_TRACKED_SESSIONS)
+            .map(|_| (Uuid::new_v4(), start))
+            .collect();
+
+        assert!(SessionService::claim_touch(&mut touched, Uuid::new_v4(), start + Duration::from_secs(120)));
+        assert_eq!(touched.len(), 1);
+    }
+}
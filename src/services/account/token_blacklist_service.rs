@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+use sea_orm::sea_query::OnConflict;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::token_blacklist;
+use crate::errors::AppError;
+use crate::services::account::jwt_service::Claims;
+use crate::utils::message_util::MessageUtil;
+
+/// How long a "not revoked" answer is trusted before the table is asked again. Revocations
+/// made by this process update the cache immediately; this only bounds how long a
+/// revocation made by another instance can go unnoticed.
+const NEGATIVE_CACHE_TTL: Duration = Duration::from_secs(30);
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+struct CacheEntry {
+    revoked: bool,
+    cached_until: Instant,
+}
+
+#[derive(Default)]
+pub struct TokenBlacklistCache {
+    entries: RwLock<HashMap<String, CacheEntry>>,
+}
+
+impl TokenBlacklistCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self, jti: &str) -> Option<bool> {
+        let entries = self.entries.read().ok()?;
+
+        entries.get(jti)
+            .filter(|entry| entry.cached_until > Instant::now())
+            .map(|entry| entry.revoked)
+    }
+
+    fn insert(&self, jti: &str, revoked: bool, ttl: Duration) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(jti.to_string(), CacheEntry {
+                revoked,
+                cached_until: Instant::now() + ttl,
+            });
+        }
+    }
+
+    fn prune(&self) {
+        if let Ok(mut entries) = self.entries.write() {
+            let now = Instant::now();
+            entries.retain(|_, entry| entry.cached_until > now);
+        }
+    }
+}
+
+pub struct TokenBlacklistService {
+    state: AppState,
+}
+
+impl TokenBlacklistService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Blacklists the token's JTI until it would have expired anyway. Returns `false` if
+    /// the token was already blacklisted, which callers use to make consumption single-use.
+    pub async fn revoke(
+        &self,
+        claims: &Claims,
+        reason: &str,
+    ) -> Result<bool, AppError> {
+        let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
+            .unwrap_or_else(|| chrono::Utc::now());
+
+        self.revoke_jti(&claims.jti, claims.sub, &claims.purpose, expires_at, reason).await
+    }
+
+    /// Blacklists a JTI known only from storage, such as the current access token of a
+    /// session being revoked.
+    pub async fn revoke_jti(
+        &self,
+        jti: &str,
+        user_id: Uuid,
+        token_type: &str,
+        expires_at: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<bool, AppError> {
+        self.revoke_jti_on(&self.state.db, jti, user_id, token_type, expires_at, reason).await
+    }
+
+    /// Blacklists the JTI on `conn`. The local cache is updated straight away, so a
+    /// rolled-back revocation still keeps the token out here until the entry expires.
+    pub async fn revoke_jti_on<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        jti: &str,
+        user_id: Uuid,
+        token_type: &str,
+        expires_at: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<bool, AppError> {
+        let entry = token_blacklist::ActiveModel {
+            jti: Set(jti.to_string()),
+            user_id: Set(user_id),
+            token_type: Set(token_type.to_string()),
+            expires_at: Set(expires_at),
+            reason: Set(Some(reason.to_string())),
+            ..token_blacklist::ActiveModel::new()
+        };
+
+        let inserted = token_blacklist::Entity::insert(entry)
+            .on_conflict(
+                OnConflict::column(token_blacklist::Column::Jti)
+                    .do_nothing()
+                    .to_owned()
+            )
+            .exec_without_returning(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to blacklist token: {}", e)))?;
+
+        let remaining = (expires_at - chrono::Utc::now()).num_seconds().max(0) as u64;
+        self.state.token_blacklist_cache.insert(jti, true, Duration::from_secs(remaining));
+
+        Ok(inserted > 0)
+    }
+
+    pub async fn is_revoked(
+        &self,
+        claims: &Claims,
+    ) -> Result<bool, AppError> {
+        let cache = &self.state.token_blacklist_cache;
+
+        if let Some(revoked) = cache.get(&claims.jti) {
+            return Ok(revoked);
+        }
+
+        let revoked = token_blacklist::Entity::find_by_id(claims.jti.clone())
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to check token blacklist: {}", e)))?
+            .is_some();
+
+        let ttl = if revoked {
+            Self::remaining_lifetime(claims)
+        } else {
+            NEGATIVE_CACHE_TTL
+        };
+
+        cache.insert(&claims.jti, revoked, ttl);
+
+        Ok(revoked)
+    }
+
+    /// Drops blacklist rows for tokens that have expired, since `exp` rejects them anyway.
+    pub async fn purge_expired(&self) -> Result<u64, AppError> {
+        self.state.token_blacklist_cache.prune();
+
+        let result = token_blacklist::Entity::delete_many()
+            .filter(token_blacklist::Column::ExpiresAt.lt(chrono::Utc::now()))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to purge token blacklist: {}", e)))?;
+
+        Ok(result.rows_affected)
+    }
+
+    pub fn start_purge_job(state: AppState) {
+        tokio::spawn(async move {
+            let blacklist_service = TokenBlacklistService::new(state);
+            let mut ticker = tokio::time::interval(PURGE_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                match blacklist_service.purge_expired().await {
+                    Ok(0) => {}
+                    Ok(count) => MessageUtil::info(&format!("Purged {} expired blacklisted token(s)", count)),
+                    Err(e) => MessageUtil::error(&format!("Failed to purge token blacklist: {}", e)),
+                }
+            }
+        });
+    }
+
+    fn remaining_lifetime(claims: &Claims) -> Duration {
+        let remaining = claims.exp as i64 - chrono::Utc::now().timestamp();
+        Duration::from_secs(remaining.max(0) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(exp: i64) -> Claims {
+        Claims {
+            sub: Uuid::nil(),
+            purpose: "access".to_string(),
+            exp: exp as usize,
+            iat: 0,
+            jti: "jti".to_string(),
+            roles: Vec::new(),
+            permissions: Vec::new(),
+            sid: None,
+            resource_id: None,
+        }
+    }
+
+    #[test]
+    fn cache_answers_until_the_entry_lapses() {
+        let cache = TokenBlacklistCache::new();
+
+        cache.insert("revoked", true, Duration::from_secs(60));
+        cache.insert("live", false, Duration::from_secs(60));
+        cache.insert("lapsed", true, Duration::ZERO);
+
+        assert_eq!(cache.get("revoked"), Some(true));
+        assert_eq!(cache.get("live"), Some(false));
+        assert_eq!(cache.get("lapsed"), None);
+        assert_eq!(cache.get("unknown"), None);
+    }
+
+    #[test]
+    fn prune_drops_lapsed_entries() {
+        let cache = TokenBlacklistCache::new();
+
+        cache.insert("revoked", true, Duration::from_secs(60));
+        cache.insert("lapsed", true, Duration::ZERO);
+        cache.prune();
+
+        let entries = cache.entries.read().unwrap();
+        assert!(entries.contains_key("revoked"));
+        assert!(!entries.contains_key("lapsed"));
+    }
+
+    #[test]
+    fn revocations_are_cached_for_the_rest_of_the_token_lifetime() {
+        let now = chrono::Utc::now().timestamp();
+
+        let remaining = TokenBlacklistService::remaining_lifetime(&claims(now + 600));
+        assert!(remaining <= Duration::from_secs(600) && remaining >= Duration::from_secs(590));
+
+        assert_eq!(TokenBlacklistService::remaining_lifetime(&claims(now - 600)), Duration::ZERO);
+    }
+}
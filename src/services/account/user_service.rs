@@ -4,6 +4,7 @@ use uuid::Uuid;
 use chrono::Utc;
 use crate::app_state::AppState;
 use crate::entities::users;
+use crate::errors::AppError;
 use crate::handlers::account::auth_handler::RegisterRequest;
 
 pub struct UserService {
@@ -15,20 +16,21 @@ impl UserService {
         Self { state }
     }
     
-    pub async fn create_user(
+    pub async fn create_user<C: ConnectionTrait>(
         &self,
+        conn: &C,
         request: RegisterRequest,
-    ) -> Result<users::Model, String> {
+    ) -> Result<users::Model, AppError> {
         if let Some(existing_user) = self.get_user_by_email(&request.email).await? {
-            return Err("Email already in use".to_string());
+            return Err(AppError::Conflict("Email already in use".to_string()));
         }
         
         if let Some(existing_user) = self.get_user_by_username(&request.username).await? {
-            return Err("Username already in use".to_string());
+            return Err(AppError::Conflict("Username already in use".to_string()));
         }
         
         let password_hash = bcrypt::hash(&request.password, bcrypt::DEFAULT_COST)
-            .map_err(|e| format!("Failed to hash password: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to hash password: {}", e)))?;
 
         let user = users::ActiveModel {
             username: Set(Some(request.username)),
@@ -39,8 +41,8 @@ impl UserService {
             ..Default::default()
         };
 
-        let user = user.insert(&self.state.db).await
-            .map_err(|e| format!("Failed to create user: {}", e))?;
+        let user = user.insert(conn).await
+            .map_err(|e| AppError::Internal(format!("Failed to create user: {}", e)))?;
         
         Ok(user)
     }
@@ -48,48 +50,61 @@ impl UserService {
     pub async fn get_user_by_id(
         &self,
         user_id: &Uuid,
-    ) -> Result<Option<users::Model>, String> {
+    ) -> Result<Option<users::Model>, AppError> {
         users::Entity::find_by_id(*user_id)
             .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch user: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))
     }
     
     pub async fn get_user_by_email(
         &self,
         email: &str,
-    ) -> Result<Option<users::Model>, String> {
+    ) -> Result<Option<users::Model>, AppError> {
         users::Entity::find()
             .filter(users::Column::Email.eq(email))
             .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch user by email: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user by email: {}", e)))
     }
     
     pub async fn get_user_by_username(
         &self,
         username: &str,
-    ) -> Result<Option<users::Model>, String> {
+    ) -> Result<Option<users::Model>, AppError> {
         users::Entity::find()
             .filter(users::Column::Username.eq(username))
             .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch user by username: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user by username: {}", e)))
     }
 
     pub async fn update_user_field<F>(
         &self,
         user_id: &Uuid,
         update_fn: F,
-    ) -> Result<users::Model, String>
+    ) -> Result<users::Model, AppError>
     where
         F: FnOnce(&mut users::ActiveModel),
+    {
+        Self::update_user_field_on(&self.state.db, user_id, update_fn).await
+    }
+
+    /// Same as `update_user_field`, but on `conn` so the update can share a transaction.
+    pub async fn update_user_field_on<C, F>(
+        conn: &C,
+        user_id: &Uuid,
+        update_fn: F,
+    ) -> Result<users::Model, AppError>
+    where
+        C: ConnectionTrait,
+        F: FnOnce(&mut users::ActiveModel),
     {
         let user = users::Entity::find_by_id(*user_id)
-            .one(&self.state.db)
+            .one(conn)
             .await
-            .map_err(|e| format!("Failed to fetch user: {}", e))?
-            .ok_or_else(|| "User not found".to_string())?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         let mut user_update: users::ActiveModel = user.into();
 
@@ -98,9 +113,9 @@ impl UserService {
         update_fn(&mut user_update);
 
         let updated_user = user_update
-            .update(&self.state.db)
+            .update(conn)
             .await
-            .map_err(|e| format!("Failed to update user: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to update user: {}", e)))?;
         
         Ok(updated_user)
     }
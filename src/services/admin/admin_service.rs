@@ -3,6 +3,7 @@ use uuid::Uuid;
 use crate::app_state::AppState;
 use crate::entities::{user_roles};
 use crate::entities::user_roles::UserRoleType;
+use crate::errors::AppError;
 
 pub struct AdminService {
     state: AppState,
@@ -16,14 +17,14 @@ impl AdminService {
     pub async fn is_admin(
         &self,
         user_id: &Uuid
-    ) -> Result<bool, String> {
+    ) -> Result<bool, AppError> {
         let admin_role = user_roles::Entity::find()
             .filter(user_roles::Column::UserId.eq(*user_id))
             .filter(user_roles::Column::Role.eq(UserRoleType::Admin))
             .filter(user_roles::Column::IsActive.eq(true))
             .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to check user role: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to check user role: {}", e)))?;
 
         Ok(admin_role.is_some())
     }
@@ -31,15 +32,28 @@ impl AdminService {
     pub async fn is_moderator(
         &self,
         user_id: &Uuid
-    ) -> Result<bool, String> {
+    ) -> Result<bool, AppError> {
         let admin_role = user_roles::Entity::find()
             .filter(user_roles::Column::UserId.eq(*user_id))
             .filter(user_roles::Column::Role.eq(UserRoleType::Moderator))
             .filter(user_roles::Column::IsActive.eq(true))
             .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to check user role: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to check user role: {}", e)))?;
 
         Ok(admin_role.is_some())
     }
+
+    pub async fn get_active_roles(
+        &self,
+        user_id: &Uuid
+    ) -> Result<Vec<UserRoleType>, AppError> {
+        user_roles::Entity::find()
+            .filter(user_roles::Column::UserId.eq(*user_id))
+            .filter(user_roles::Column::IsActive.eq(true))
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user roles: {}", e)))
+            .map(|roles| roles.into_iter().map(|role| role.role).collect())
+    }
 }
\ No newline at end of file
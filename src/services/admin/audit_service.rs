@@ -0,0 +1,185 @@
+use sea_orm::*;
+use serde::Serialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::audit_events;
+use crate::errors::AppError;
+use crate::handlers::admin::audit_handler::AuditEventQuery;
+use crate::utils::request_util::ClientInfo;
+
+const MAX_EXPORT_ROWS: u64 = 10_000;
+
+/// One change worth keeping a record of. `old_values` and `new_values` hold the parts of
+/// the target that changed, `metadata` anything else useful, like a reason.
+pub struct AuditEvent<'a> {
+    pub actor_id: Option<Uuid>,
+    pub client: &'a ClientInfo,
+    pub action: &'a str,
+    pub target_type: &'a str,
+    pub target_id: Option<String>,
+    pub old_values: Option<serde_json::Value>,
+    pub new_values: Option<serde_json::Value>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditSearchResult {
+    pub events: Vec<audit_events::Model>,
+    pub total: u64,
+}
+
+#[derive(Serialize)]
+struct AuditExportRow<'a> {
+    id: Uuid,
+    created_at: String,
+    actor_id: Option<Uuid>,
+    action: &'a str,
+    target_type: &'a str,
+    target_id: &'a str,
+    old_values: String,
+    new_values: String,
+    metadata: String,
+    ip_address: &'a str,
+    request_id: Option<Uuid>,
+}
+
+pub struct AuditService {
+    state: AppState,
+}
+
+impl AuditService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn record(
+        &self,
+        event: AuditEvent<'_>,
+    ) -> Result<audit_events::Model, AppError> {
+        Self::record_on(&self.state.db, event).await
+    }
+
+    /// Records the event on `conn`, so it commits or rolls back with the change it describes.
+    pub async fn record_on<C: ConnectionTrait>(
+        conn: &C,
+        event: AuditEvent<'_>,
+    ) -> Result<audit_events::Model, AppError> {
+        audit_events::ActiveModel {
+            actor_id: Set(event.actor_id),
+            action: Set(event.action.to_string()),
+            target_type: Set(event.target_type.to_string()),
+            target_id: Set(event.target_id),
+            old_values: Set(event.old_values),
+            new_values: Set(event.new_values),
+            metadata: Set(event.metadata),
+            ip_address: Set(event.client.ip_address.clone()),
+            request_id: Set(event.client.request_id),
+            ..audit_events::ActiveModel::new()
+        }
+            .insert(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to record audit event: {}", e)))
+    }
+
+    pub async fn search(
+        &self,
+        query: &AuditEventQuery,
+        offset: u64,
+        limit: u64,
+    ) -> Result<AuditSearchResult, AppError> {
+        let db = &self.state.db;
+        let select = Self::filtered(query);
+
+        let total = select.clone()
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count audit events: {}", e)))?;
+
+        let events = select
+            .order_by_desc(audit_events::Column::CreatedAt)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch audit events: {}", e)))?;
+
+        Ok(AuditSearchResult { events, total })
+    }
+
+    /// Writes the matching events as CSV, newest first, up to `MAX_EXPORT_ROWS`.
+    pub async fn export(
+        &self,
+        query: &AuditEventQuery,
+    ) -> Result<Vec<u8>, AppError> {
+        let events = Self::filtered(query)
+            .order_by_desc(audit_events::Column::CreatedAt)
+            .limit(MAX_EXPORT_ROWS)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch audit events: {}", e)))?;
+
+        let json = |value: &Option<serde_json::Value>| {
+            value.as_ref().map(|value| value.to_string()).unwrap_or_default()
+        };
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        for event in &events {
+            writer.serialize(AuditExportRow {
+                id: event.id,
+                created_at: event.created_at.to_rfc3339(),
+                actor_id: event.actor_id,
+                action: &event.action,
+                target_type: &event.target_type,
+                target_id: event.target_id.as_deref().unwrap_or(""),
+                old_values: json(&event.old_values),
+                new_values: json(&event.new_values),
+                metadata: json(&event.metadata),
+                ip_address: event.ip_address.as_deref().unwrap_or(""),
+                request_id: event.request_id,
+            }).map_err(|e| AppError::Internal(format!("Failed to write CSV row: {}", e)))?;
+        }
+
+        writer.into_inner()
+            .map_err(|e| AppError::Internal(format!("Failed to write CSV: {}", e)))
+    }
+
+    fn filtered(query: &AuditEventQuery) -> Select<audit_events::Entity> {
+        let mut select = audit_events::Entity::find();
+
+        if let Some(actor_id) = query.actor_id {
+            select = select.filter(audit_events::Column::ActorId.eq(actor_id));
+        }
+
+        // `action=listing.` matches every listing action.
+        if let Some(action) = query.action.as_deref().filter(|action| !action.is_empty()) {
+            select = if action.ends_with('.') {
+                select.filter(audit_events::Column::Action.starts_with(action))
+            } else {
+                select.filter(audit_events::Column::Action.eq(action))
+            };
+        }
+
+        if let Some(target_type) = query.target_type.as_deref().filter(|target_type| !target_type.is_empty()) {
+            select = select.filter(audit_events::Column::TargetType.eq(target_type));
+        }
+
+        if let Some(target_id) = query.target_id.as_deref().filter(|target_id| !target_id.is_empty()) {
+            select = select.filter(audit_events::Column::TargetId.eq(target_id));
+        }
+
+        if let Some(request_id) = query.request_id {
+            select = select.filter(audit_events::Column::RequestId.eq(request_id));
+        }
+
+        if let Some(from) = query.from {
+            select = select.filter(audit_events::Column::CreatedAt.gte(from));
+        }
+
+        if let Some(to) = query.to {
+            select = select.filter(audit_events::Column::CreatedAt.lt(to));
+        }
+
+        select
+    }
+}
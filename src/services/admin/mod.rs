@@ -1 +1,3 @@
-pub mod admin_service;
\ No newline at end of file
+pub mod admin_service;
+pub mod audit_service;
+pub mod user_admin_service;
\ No newline at end of file
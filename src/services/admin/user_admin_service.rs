@@ -0,0 +1,501 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, Func};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{login_history, mfa_backup_codes, user_roles, user_sessions, users};
+use crate::entities::user_roles::{string_to_role, UserRoleType};
+use crate::entities::users::{ACCOUNT_STATUS_ACTIVE, ACCOUNT_STATUS_BANNED, ACCOUNT_STATUS_SUSPENDED};
+use crate::errors::AppError;
+use crate::services::account::lockout_service::LockoutService;
+use crate::services::account::session_service::SessionService;
+use crate::services::account::user_service::UserService;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::utils::request_util::ClientInfo;
+
+const RECENT_LOGINS_LIMIT: u64 = 20;
+
+#[derive(Debug, Serialize)]
+pub struct AdminUserSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub username: Option<String>,
+    pub account_status: String,
+    pub email_verified: bool,
+    pub totp_enabled: bool,
+    pub sms_mfa_enabled: bool,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<users::Model> for AdminUserSummary {
+    fn from(user: users::Model) -> Self {
+        Self {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            account_status: user.account_status,
+            email_verified: user.email_verified,
+            totp_enabled: user.totp_enabled,
+            sms_mfa_enabled: user.sms_mfa_enabled,
+            locked_until: user.locked_until,
+            last_login_at: user.last_login_at,
+            created_at: user.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminRoleResponse {
+    pub role: String,
+    pub granted_at: DateTime<FixedOffset>,
+    pub granted_by: Option<Uuid>,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminUserProfile {
+    pub user: AdminUserSummary,
+    pub phone_number: Option<String>,
+    pub phone_verified: bool,
+    pub verified_seller: bool,
+    pub login_attempts: Option<i64>,
+    pub roles: Vec<AdminRoleResponse>,
+    pub active_sessions: u64,
+    pub recent_logins: Vec<login_history::Model>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserSearchResult {
+    pub users: Vec<AdminUserSummary>,
+    pub total: u64,
+}
+
+pub struct UserAdminService {
+    state: AppState,
+}
+
+impl UserAdminService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Case-insensitive match on email or username, optionally narrowed to one status.
+    pub async fn search_users(
+        &self,
+        query: Option<&str>,
+        status: Option<&str>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<UserSearchResult, AppError> {
+        let db = &self.state.db;
+        let mut select = users::Entity::find();
+
+        if let Some(query) = query.map(str::trim).filter(|query| !query.is_empty()) {
+            let pattern = format!("%{}%", escape_like(&query.to_lowercase()));
+
+            select = select.filter(
+                Condition::any()
+                    .add(Expr::expr(Func::lower(Expr::col(users::Column::Email))).like(pattern.clone()))
+                    .add(Expr::expr(Func::lower(Expr::col(users::Column::Username))).like(pattern))
+            );
+        }
+
+        if let Some(status) = status {
+            select = select.filter(users::Column::AccountStatus.eq(status));
+        }
+
+        let total = select.clone()
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count users: {}", e)))?;
+
+        let users = select
+            .order_by_desc(users::Column::CreatedAt)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to search users: {}", e)))?;
+
+        Ok(UserSearchResult {
+            users: users.into_iter().map(AdminUserSummary::from).collect(),
+            total,
+        })
+    }
+
+    pub async fn get_user_profile(
+        &self,
+        user_id: Uuid,
+    ) -> Result<AdminUserProfile, AppError> {
+        let db = &self.state.db;
+        let user = self.get_user(user_id).await?;
+
+        let roles = user_roles::Entity::find()
+            .filter(user_roles::Column::UserId.eq(user_id))
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user roles: {}", e)))?
+            .into_iter()
+            .map(|role| AdminRoleResponse {
+                role: role.role.as_str().to_string(),
+                granted_at: role.granted_at,
+                granted_by: role.granted_by,
+                is_active: role.is_active,
+            })
+            .collect();
+
+        let active_sessions = user_sessions::Entity::find()
+            .filter(user_sessions::Column::UserId.eq(user_id))
+            .filter(user_sessions::Column::IsActive.eq(true))
+            .filter(user_sessions::Column::ExpiresAt.gt(Utc::now()))
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count sessions: {}", e)))?;
+
+        let recent_logins = login_history::Entity::find()
+            .filter(login_history::Column::UserId.eq(user_id))
+            .order_by_desc(login_history::Column::AttemptedAt)
+            .limit(RECENT_LOGINS_LIMIT)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch login history: {}", e)))?;
+
+        Ok(AdminUserProfile {
+            phone_number: user.phone_number.clone(),
+            phone_verified: user.phone_verified,
+            verified_seller: user.verified_seller,
+            login_attempts: user.login_attempts,
+            user: user.into(),
+            roles,
+            active_sessions,
+            recent_logins,
+        })
+    }
+
+    /// Suspending or banning an account also ends all of its sessions.
+    pub async fn set_account_status(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        user_id: Uuid,
+        status: &str,
+        reason: Option<String>,
+    ) -> Result<AdminUserSummary, AppError> {
+        if ![ACCOUNT_STATUS_ACTIVE, ACCOUNT_STATUS_SUSPENDED, ACCOUNT_STATUS_BANNED].contains(&status) {
+            return Err(AppError::field("status", format!("Invalid account status: {}", status)));
+        }
+
+        if actor_id == user_id && status != ACCOUNT_STATUS_ACTIVE {
+            return Err(AppError::Forbidden("You cannot suspend or ban your own account".to_string()));
+        }
+
+        let user = self.get_user(user_id).await?;
+        let previous_status = user.account_status.clone();
+
+        let txn = self.begin().await?;
+
+        let user = UserService::update_user_field_on(&txn, &user.id, |user| {
+            user.account_status = Set(status.to_string());
+        }).await?;
+
+        if status != ACCOUNT_STATUS_ACTIVE {
+            SessionService::new(self.state.clone())
+                .revoke_all_sessions_on(&txn, user_id, status)
+                .await?;
+        }
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "user.status_changed",
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: Some(serde_json::json!({ "account_status": previous_status })),
+            new_values: Some(serde_json::json!({ "account_status": status })),
+            metadata: Some(serde_json::json!({ "reason": reason })),
+        }).await?;
+
+        Self::commit(txn).await?;
+
+        Ok(user.into())
+    }
+
+    pub async fn unlock_user(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        let user = self.get_user(user_id).await?;
+
+        let txn = self.begin().await?;
+
+        LockoutService::reset_on(&txn, &user).await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "user.unlocked",
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: Some(serde_json::json!({
+                "locked_until": user.locked_until,
+                "login_attempts": user.login_attempts,
+            })),
+            new_values: None,
+            metadata: None,
+        }).await?;
+
+        Self::commit(txn).await
+    }
+
+    pub async fn force_logout(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        user_id: Uuid,
+    ) -> Result<usize, AppError> {
+        self.get_user(user_id).await?;
+
+        let txn = self.begin().await?;
+
+        let revoked = SessionService::new(self.state.clone())
+            .revoke_all_sessions_on(&txn, user_id, "admin_logout")
+            .await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "user.sessions_revoked",
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: None,
+            new_values: None,
+            metadata: Some(serde_json::json!({ "revoked": revoked })),
+        }).await?;
+
+        Self::commit(txn).await?;
+
+        Ok(revoked)
+    }
+
+    /// Grants a role, reactivating an earlier grant if there is one. Takes effect at the
+    /// user's next token refresh.
+    pub async fn grant_role(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        user_id: Uuid,
+        role: &str,
+    ) -> Result<(), AppError> {
+        let role = string_to_role(role)
+            .ok_or_else(|| AppError::field("role", format!("Invalid role: {}", role)))?;
+
+        self.get_user(user_id).await?;
+
+        let txn = self.begin().await?;
+        let existing = Self::find_role(&txn, user_id, &role).await?;
+
+        match existing {
+            Some(existing) if existing.is_active => {
+                return Err(AppError::Conflict(format!("User already has the {} role", role.as_str())));
+            }
+            Some(existing) => {
+                let mut existing: user_roles::ActiveModel = existing.into();
+                existing.is_active = Set(true);
+                existing.granted_by = Set(Some(actor_id));
+                existing.granted_at = Set(Utc::now().into());
+
+                existing.update(&txn)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to grant role: {}", e)))?;
+            }
+            None => {
+                user_roles::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    user_id: Set(user_id),
+                    role: Set(role.clone()),
+                    granted_at: Set(Utc::now().into()),
+                    granted_by: Set(Some(actor_id)),
+                    is_active: Set(true),
+                }
+                    .insert(&txn)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to grant role: {}", e)))?;
+            }
+        }
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "user.role_granted",
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: None,
+            new_values: Some(serde_json::json!({ "role": role.as_str() })),
+            metadata: None,
+        }).await?;
+
+        Self::commit(txn).await
+    }
+
+    pub async fn revoke_role(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        user_id: Uuid,
+        role: &str,
+    ) -> Result<(), AppError> {
+        let role = string_to_role(role)
+            .ok_or_else(|| AppError::field("role", format!("Invalid role: {}", role)))?;
+
+        if actor_id == user_id && role == UserRoleType::Admin {
+            return Err(AppError::Forbidden("You cannot revoke your own admin role".to_string()));
+        }
+
+        let txn = self.begin().await?;
+
+        let existing = Self::find_role(&txn, user_id, &role)
+            .await?
+            .filter(|existing| existing.is_active)
+            .ok_or_else(|| AppError::NotFound(format!("User does not have the {} role", role.as_str())))?;
+
+        let mut existing: user_roles::ActiveModel = existing.into();
+        existing.is_active = Set(false);
+
+        existing.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to revoke role: {}", e)))?;
+
+        // Issued tokens carry the user's roles, so they'd keep the revoked privileges
+        // until they expired.
+        SessionService::new(self.state.clone())
+            .revoke_all_sessions_on(&txn, user_id, "role_revoked")
+            .await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "user.role_revoked",
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: Some(serde_json::json!({ "role": role.as_str() })),
+            new_values: None,
+            metadata: None,
+        }).await?;
+
+        Self::commit(txn).await
+    }
+
+    /// Turns off every second factor for a user who has lost access to theirs.
+    pub async fn reset_mfa(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        let user = self.get_user(user_id).await?;
+
+        let txn = self.begin().await?;
+
+        UserService::update_user_field_on(&txn, &user_id, |user| {
+            user.totp_enabled = Set(false);
+            user.totp_secret = Set(None);
+            user.sms_mfa_enabled = Set(false);
+        }).await?;
+
+        mfa_backup_codes::Entity::delete_many()
+            .filter(mfa_backup_codes::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to clear backup codes: {}", e)))?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "user.mfa_reset",
+            target_type: "user",
+            target_id: Some(user_id.to_string()),
+            old_values: Some(serde_json::json!({
+                "totp_enabled": user.totp_enabled,
+                "sms_mfa_enabled": user.sms_mfa_enabled,
+            })),
+            new_values: Some(serde_json::json!({
+                "totp_enabled": false,
+                "sms_mfa_enabled": false,
+            })),
+            metadata: None,
+        }).await?;
+
+        Self::commit(txn).await
+    }
+
+    async fn get_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<users::Model, AppError> {
+        UserService::new(self.state.clone())
+            .get_user_by_id(&user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+    }
+
+    async fn find_role<C: ConnectionTrait>(
+        conn: &C,
+        user_id: Uuid,
+        role: &UserRoleType,
+    ) -> Result<Option<user_roles::Model>, AppError> {
+        user_roles::Entity::find()
+            .filter(user_roles::Column::UserId.eq(user_id))
+            .filter(user_roles::Column::Role.eq(role.clone()))
+            .lock_exclusive()
+            .one(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user role: {}", e)))
+    }
+
+    /// Every admin change is written together with its audit event, so neither can land
+    /// without the other.
+    async fn begin(&self) -> Result<DatabaseTransaction, AppError> {
+        self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))
+    }
+
+    async fn commit(txn: DatabaseTransaction) -> Result<(), AppError> {
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit user change: {}", e)))
+    }
+}
+
+/// Escapes `LIKE` wildcards so a search for `a_b` or `50%` matches them literally.
+/// Postgres treats backslash as the escape character by default.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_like_wildcards() {
+        assert_eq!(escape_like("plain"), "plain");
+        assert_eq!(escape_like("50%_off"), "50\\%\\_off");
+        assert_eq!(escape_like("back\\slash"), "back\\\\slash");
+    }
+}
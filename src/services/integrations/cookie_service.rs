@@ -1,5 +1,10 @@
 use actix_web::cookie::{Cookie, SameSite};
 use actix_web::cookie::time::Duration;
+use crate::config::config::Config;
+use crate::services::account::jwt_service::ACCESS_TOKEN_MINUTES;
+
+const REFRESH_COOKIE_PATH: &str = "/api/v1/public/auth";
+const OAUTH_COOKIE_PATH: &str = "/api/v1/public/auth/oauth";
 
 pub struct CookieService;
 
@@ -11,7 +16,53 @@ impl CookieService {
             .secure(true)
             .same_site(SameSite::None)
             .path("/")
-            .max_age(Duration::hours(3))
+            .max_age(Duration::minutes(ACCESS_TOKEN_MINUTES))
+            .finish()
+    }
+
+    pub fn refresh_cookie(token: &str) -> Cookie<'static> {
+        Cookie::build("refresh_token".to_string(), token.to_string())
+            .domain("localhost")
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::None)
+            .path(REFRESH_COOKIE_PATH)
+            .max_age(Duration::days(Config::get().session_lifetime_days))
+            .finish()
+    }
+
+    pub fn clear_refresh_cookie() -> Cookie<'static> {
+        Cookie::build("refresh_token".to_string(), String::new())
+            .domain("localhost")
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::None)
+            .path(REFRESH_COOKIE_PATH)
+            .max_age(Duration::seconds(-1))
+            .finish()
+    }
+
+    /// Lax rather than None: the provider sends the user back with a top-level redirect,
+    /// which Lax cookies survive, and nothing else needs this cookie.
+    pub fn oauth_flow_cookie(flow: &str) -> Cookie<'static> {
+        Cookie::build("oauth_flow".to_string(), flow.to_string())
+            .domain("localhost")
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::Lax)
+            .path(OAUTH_COOKIE_PATH)
+            .max_age(Duration::minutes(10))
+            .finish()
+    }
+
+    pub fn clear_oauth_flow_cookie() -> Cookie<'static> {
+        Cookie::build("oauth_flow".to_string(), String::new())
+            .domain("localhost")
+            .http_only(true)
+            .secure(true)
+            .same_site(SameSite::Lax)
+            .path(OAUTH_COOKIE_PATH)
+            .max_age(Duration::seconds(-1))
             .finish()
     }
 
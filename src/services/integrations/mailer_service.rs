@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use crate::config::config::Config;
+use crate::errors::{AppError, UpstreamService};
+
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: EmailMessage) -> Result<(), AppError>;
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        from: &str,
+    ) -> Result<Self, AppError> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| AppError::Internal(format!("Failed to configure SMTP transport: {}", e)))?
+            .port(port)
+            .credentials(Credentials::new(username.to_string(), password.to_string()))
+            .build();
+
+        let from = from.parse::<Mailbox>()
+            .map_err(|e| AppError::Internal(format!("Invalid sender address: {}", e)))?;
+
+        Ok(Self { transport, from })
+    }
+
+    pub fn from_config() -> Result<Self, AppError> {
+        let config = Config::get();
+
+        Self::new(
+            &config.smtp_host,
+            config.smtp_port,
+            &config.smtp_username,
+            &config.smtp_password,
+            &config.mail_from,
+        )
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: EmailMessage) -> Result<(), AppError> {
+        let to = message.to.parse::<Mailbox>()
+            .map_err(|e| AppError::field("email", format!("Invalid recipient address: {}", e)))?;
+
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject)
+            .body(message.body)
+            .map_err(|e| AppError::Internal(format!("Failed to build email: {}", e)))?;
+
+        self.transport.send(email)
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Mail, format!("Failed to send email: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Keeps sent mail in memory instead of delivering it; used in development and tests.
+#[derive(Default)]
+pub struct InMemoryMailer {
+    sent: Mutex<Vec<EmailMessage>>,
+}
+
+impl InMemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sent_messages(&self) -> Vec<EmailMessage> {
+        self.sent.lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Mailer for InMemoryMailer {
+    async fn send(&self, message: EmailMessage) -> Result<(), AppError> {
+        self.sent.lock()
+            .map_err(|_| AppError::Internal("Mailer outbox is poisoned".to_string()))?
+            .push(message);
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::time::Duration;
 use meilisearch_sdk::client::Client;
 use meilisearch_sdk::search::SearchResults;
 use sea_orm::EntityTrait;
@@ -7,6 +8,17 @@ use uuid::Uuid;
 use crate::app_state::AppState;
 use crate::entities::{products, listings};
 use crate::entities::products::ProductCategory;
+use crate::errors::{AppError, UpstreamService};
+use crate::services::integrations::redis_service::CacheService;
+
+const TRENDING_LIMIT: usize = 10;
+const TRENDING_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TrendingProducts {
+    pub hits: Vec<SearchableProduct>,
+    pub estimated_total_hits: Option<usize>,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SearchableProduct {
@@ -36,7 +48,7 @@ pub struct MeilisearchService {
 impl MeilisearchService {
     pub fn new(state: AppState) -> Self { Self { state } }
 
-    pub async fn validate_indexes(&self) -> Result<(), String> {
+    pub async fn validate_indexes(&self) -> Result<(), AppError> {
         let client = self.state.meilisearch_client.as_ref();
 
         if let Err(e) = client.get_index("products").await {
@@ -50,46 +62,103 @@ impl MeilisearchService {
         Ok(())
     }
 
-    pub async fn index_product(&self, product: &products::Model) -> Result<(), String> {
-        let searchable_product = SearchableProduct {
-            id: product.id.to_string(),
-            name: product.name.clone(),
-            game: Some(product.game.clone()),
-            set: product.set.clone(),
-            category: self.category_to_string(&product.category),
-            subcategory: product.subcategory.clone(),
-            metadata: product.metadata.clone(),
-        };
+    pub async fn index_product(&self, product: &products::Model) -> Result<(), AppError> {
+        self.index_products(std::slice::from_ref(product)).await
+    }
+
+    /// Sends products to the index in one request, for bulk changes such as imports.
+    pub async fn index_products(&self, products: &[products::Model]) -> Result<(), AppError> {
+        if products.is_empty() {
+            return Ok(());
+        }
+
+        let searchable_products: Vec<SearchableProduct> = products.iter()
+            .map(|product| SearchableProduct {
+                id: product.id.to_string(),
+                name: product.name.clone(),
+                game: Some(product.game.clone()),
+                set: product.set.clone(),
+                category: self.category_to_string(&product.category),
+                subcategory: product.subcategory.clone(),
+                metadata: product.metadata.clone(),
+            })
+            .collect();
 
         let products_index = self.state.meilisearch_client.as_ref().clone().index("products");
         products_index
-            .add_documents(&[searchable_product], Some("id"))
+            .add_documents(&searchable_products, Some("id"))
             .await
-            .map_err(|e| format!("Failed to index product: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to index products: {}", e)))?;
 
         Ok(())
     }
 
-    pub async fn index_listing(&self, listing: &listings::Model, product: &products::Model) -> Result<(), String> {
-        let searchable_listing = SearchableListing {
-            id: listing.id.to_string(),
-            product_name: product.name.clone(),
-            price: listing.price as f64,
-            condition: format!("{:?}", listing.condition),
-            game: Some(product.game.clone()),
-            set: product.set.clone(),
-        };
+    pub async fn remove_product(&self, product_id: &Uuid) -> Result<(), AppError> {
+        let products_index = self.state.meilisearch_client.as_ref().clone().index("products");
+        products_index
+            .delete_document(product_id.to_string())
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to remove product from index: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn index_listing(&self, listing: &listings::Model, product: &products::Model) -> Result<(), AppError> {
+        self.index_listings(&[(listing.clone(), product.clone())]).await
+    }
+
+    pub async fn index_listings(&self, entries: &[(listings::Model, products::Model)]) -> Result<(), AppError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let searchable_listings: Vec<SearchableListing> = entries.iter()
+            .map(|(listing, product)| SearchableListing {
+                id: listing.id.to_string(),
+                product_name: product.name.clone(),
+                price: listing.price as f64,
+                condition: format!("{:?}", listing.condition),
+                game: Some(product.game.clone()),
+                set: product.set.clone(),
+            })
+            .collect();
 
         let listings_index = self.state.meilisearch_client.as_ref().clone().index("listings");
         listings_index
-            .add_documents(&[searchable_listing], Some("id"))
+            .add_documents(&searchable_listings, Some("id"))
             .await
-            .map_err(|e| format!("Failed to index listing: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to index listings: {}", e)))?;
 
         Ok(())
     }
 
-    pub async fn search_products(&self, query: &str, filters: Option<&str>) -> Result<SearchResults<SearchableProduct>, String> {
+    pub async fn remove_listings(&self, listing_ids: &[Uuid]) -> Result<(), AppError> {
+        if listing_ids.is_empty() {
+            return Ok(());
+        }
+
+        let ids: Vec<String> = listing_ids.iter().map(Uuid::to_string).collect();
+
+        let listings_index = self.state.meilisearch_client.as_ref().clone().index("listings");
+        listings_index
+            .delete_documents(&ids)
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to remove listings from index: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn remove_listing(&self, listing_id: &Uuid) -> Result<(), AppError> {
+        let listings_index = self.state.meilisearch_client.as_ref().clone().index("listings");
+        listings_index
+            .delete_document(listing_id.to_string())
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to remove listing from index: {}", e)))?;
+
+        Ok(())
+    }
+
+    pub async fn search_products(&self, query: &str, filters: Option<&str>) -> Result<SearchResults<SearchableProduct>, AppError> {
         let products_index = self.state.meilisearch_client.as_ref().clone().index("products");
         let mut search = products_index.search();
 
@@ -100,7 +169,7 @@ impl MeilisearchService {
 
         search.execute::<SearchableProduct>()
             .await
-            .map_err(|e| format!("Search failed: {}", e))
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Search failed: {}", e)))
     }
 
     pub async fn search_products_paginated(
@@ -110,7 +179,7 @@ impl MeilisearchService {
         offset: usize,
         limit: usize,
         sort: Option<&[&str]>
-    ) -> Result<SearchResults<SearchableProduct>, String> {
+    ) -> Result<SearchResults<SearchableProduct>, AppError> {
         let products_index = self.state.meilisearch_client.index("products");
         let mut search = products_index.search();
 
@@ -129,7 +198,7 @@ impl MeilisearchService {
 
         search.execute::<SearchableProduct>()
             .await
-            .map_err(|e| format!("Search failed: {}", e))
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Search failed: {}", e)))
     }
 
     pub async fn search_products_by_category(
@@ -139,7 +208,7 @@ impl MeilisearchService {
         game: Option<&str>,
         offset: usize,
         limit: usize,
-    ) -> Result<SearchResults<SearchableProduct>, String> {
+    ) -> Result<SearchResults<SearchableProduct>, AppError> {
         let mut filters = Vec::new();
 
         if let Some(cat) = category {
@@ -169,7 +238,7 @@ impl MeilisearchService {
         &self,
         offset: usize,
         limit: usize,
-    ) -> Result<SearchResults<SearchableProduct>, String> {
+    ) -> Result<SearchResults<SearchableProduct>, AppError> {
         let products_index = self.state.meilisearch_client.index("products");
         let mut search = products_index.search();
 
@@ -179,10 +248,31 @@ impl MeilisearchService {
 
         search.execute::<SearchableProduct>()
             .await
-            .map_err(|e| format!("Search failed: {}", e))
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Search failed: {}", e)))
+    }
+
+    /// The top trending products as shown on the landing page, served from the cache.
+    pub async fn get_trending_products(&self) -> Result<TrendingProducts, AppError> {
+        let cache_service = CacheService::new(self.state.clone());
+        let cache_key = CacheService::trending_products_key();
+
+        if let Some(trending) = cache_service.get::<TrendingProducts>(&cache_key).await {
+            return Ok(trending);
+        }
+
+        let results = self.search_products_trending(0, TRENDING_LIMIT).await?;
+
+        let trending = TrendingProducts {
+            estimated_total_hits: results.estimated_total_hits,
+            hits: results.hits.into_iter().map(|hit| hit.result).collect(),
+        };
+
+        cache_service.set(&cache_key, &trending, TRENDING_CACHE_TTL).await;
+
+        Ok(trending)
     }
 
-    pub async fn search_listings(&self, query: &str, filters: Option<&str>) -> Result<SearchResults<SearchableListing>, String> {
+    pub async fn search_listings(&self, query: &str, filters: Option<&str>) -> Result<SearchResults<SearchableListing>, AppError> {
         let listings_index = self.state.meilisearch_client.as_ref().clone().index("listings");
         let mut search = listings_index.search();
 
@@ -193,7 +283,7 @@ impl MeilisearchService {
 
         search.execute::<SearchableListing>()
             .await
-            .map_err(|e| format!("Search failed: {}", e))
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Search failed: {}", e)))
     }
 
     pub async fn search_listings_paginated(
@@ -203,7 +293,7 @@ impl MeilisearchService {
         offset: usize,
         limit: usize,
         sort: Option<&[&str]>
-    ) -> Result<SearchResults<SearchableListing>, String> {
+    ) -> Result<SearchResults<SearchableListing>, AppError> {
         let listings_index = self.state.meilisearch_client.index("listings");
         let mut search = listings_index.search();
 
@@ -222,7 +312,7 @@ impl MeilisearchService {
 
         search.execute::<SearchableListing>()
             .await
-            .map_err(|e| format!("Search failed: {}", e))
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Search failed: {}", e)))
     }
 
     pub fn get_available_categories(&self) -> Vec<&'static str> {
@@ -242,50 +332,50 @@ impl MeilisearchService {
         products::string_to_product_category(category_str)
     }
 
-    async fn setup_products_index(&self) -> Result<(), String> {
+    async fn setup_products_index(&self) -> Result<(), AppError> {
         let client = self.state.meilisearch_client.as_ref().clone();
         let products_index = client.index("products");
 
         products_index
             .set_filterable_attributes(["game", "set", "category", "subcategory"])
             .await
-            .map_err(|e| format!("Failed to set filterable attributes for products: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to set filterable attributes for products: {}", e)))?;
 
         products_index
             .set_searchable_attributes(["name", "game", "set", "category", "subcategory"])
             .await
-            .map_err(|e| format!("Failed to set searchable attributes for products: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to set searchable attributes for products: {}", e)))?;
 
         products_index
             .set_sortable_attributes(["name", "game", "category"])
             .await
-            .map_err(|e| format!("Failed to set sortable attributes for products: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to set sortable attributes for products: {}", e)))?;
 
         Ok(())
     }
 
-    async fn setup_listings_index(&self) -> Result<(), String> {
+    async fn setup_listings_index(&self) -> Result<(), AppError> {
         let client = self.state.meilisearch_client.as_ref().clone();
         let listings_index = client.index("listings");
 
         listings_index
             .set_filterable_attributes(["game", "set", "condition", "price", "language"])
             .await
-            .map_err(|e| format!("Failed to set filterable attributes for listings: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to set filterable attributes for listings: {}", e)))?;
 
         listings_index
             .set_searchable_attributes(["product_name", "game", "set"])
             .await
-            .map_err(|e| format!("Failed to set searchable attributes for listings: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Meilisearch, format!("Failed to set searchable attributes for listings: {}", e)))?;
 
         Ok(())
     }
 
     //used for debugging and initial indexing, will be moved to CLI later
-    async fn index_all_products(&self) -> Result<(), String> {
+    async fn index_all_products(&self) -> Result<(), AppError> {
         let products = products::Entity::find().all(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch products: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch products: {}", e)))?;
 
         for product in products {
             self.index_product(&product).await?;
@@ -1,7 +1,9 @@
 pub mod stripe_service;
+pub mod stripe_webhook_service;
 pub mod twilio_service;
 pub mod oauth_service;
 pub mod r2_service;
 pub mod redis_service;
 pub mod cookie_service;
-pub mod meilisearch_service;
\ No newline at end of file
+pub mod meilisearch_service;
+pub mod mailer_service;
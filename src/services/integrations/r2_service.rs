@@ -9,6 +9,7 @@ use image::codecs::jpeg::JpegEncoder;
 use image::DynamicImage;
 use image::ImageFormat::Jpeg;
 use crate::app_state::AppState;
+use crate::errors::{AppError, UpstreamService};
 
 pub struct R2Client {
     client: Client,
@@ -84,10 +85,11 @@ impl R2Service {
         product_id: &str,
         image_data: &[u8],
         user_id: &str,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<(), AppError> {
         let timestamp = Utc::now();
 
-        let img = image::load_from_memory(image_data)?;
+        let img = image::load_from_memory(image_data)
+            .map_err(|e| AppError::field("image", format!("Unsupported image: {}", e)))?;
         let original = img.clone();
         let medium = img.resize(600, 450, image::imageops::FilterType::Lanczos3);
         let thumbnail = img.resize(200, 150, image::imageops::FilterType::Lanczos3);
@@ -122,8 +124,9 @@ impl R2Service {
         image_path: &str,
         user_id: &str,
         timestamp: &DateTime<Utc>,
-    ) -> Result<(), anyhow::Error> {
-        let img = image::load_from_memory(image_data)?;
+    ) -> Result<(), AppError> {
+        let img = image::load_from_memory(image_data)
+            .map_err(|e| AppError::field("image", format!("Unsupported image: {}", e)))?;
         let original = img.clone();
         let thumbnail = img.resize(300, 225, image::imageops::FilterType::Lanczos3);
 
@@ -155,7 +158,7 @@ impl R2Service {
         key: &str,
         content_type: &str,
         metadata: Option<std::collections::HashMap<String, String>>,
-    ) -> Result<String, anyhow::Error> {
+    ) -> Result<String, AppError> {
         let mut put_object = self
             .state
             .r2_client
@@ -172,17 +175,20 @@ impl R2Service {
             }
         }
 
-        let _result = put_object.send().await?;
+        put_object.send()
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::R2, format!("Failed to upload {}: {}", key, e)))?;
+
         Ok(key.to_string())
     }
 
-    pub async fn delete_product_images(&self, product_id: &str) -> Result<(), anyhow::Error> {
+    pub async fn delete_product_images(&self, product_id: &str) -> Result<(), AppError> {
         let prefix = format!("products/{}/", product_id);
         let bucket_name = "images";
         self.delete_objects_with_prefix(bucket_name, &prefix).await
     }
 
-    pub async fn delete_listing_images(&self, listing_id: &str) -> Result<(), anyhow::Error> {
+    pub async fn delete_listing_images(&self, listing_id: &str) -> Result<(), AppError> {
         let prefix = format!("listings/{}/", listing_id);
         let bucket_name = "images";
         self.delete_objects_with_prefix(bucket_name, &prefix).await
@@ -192,7 +198,7 @@ impl R2Service {
         &self,
         bucket_name: &str,
         prefix: &str
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<(), AppError> {
         let list_response = self
             .state
             .r2_client
@@ -201,7 +207,8 @@ impl R2Service {
             .bucket(bucket_name)
             .prefix(prefix)
             .send()
-            .await?;
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::R2, format!("Failed to list {}: {}", prefix, e)))?;
 
         if let Some(contents) = list_response.contents {
             for object in contents {
@@ -213,7 +220,8 @@ impl R2Service {
                         .bucket(bucket_name)
                         .key(key)
                         .send()
-                        .await?;
+                        .await
+                        .map_err(|e| AppError::Upstream(UpstreamService::R2, format!("Failed to delete {}: {}", key, e)))?;
                 }
             }
         }
@@ -229,7 +237,7 @@ impl R2Service {
         variant_name: &str,
         front_image: Option<&[u8]>,
         back_image: Option<&[u8]>,
-    ) -> Result<VariantImageUrls, anyhow::Error> {
+    ) -> Result<VariantImageUrls, AppError> {
         let timestamp = Utc::now();
         let sanitized_game = sanitize_for_path(game);
 
@@ -285,8 +293,9 @@ impl R2Service {
         face: &str,
         bucket_name: &str,
         metadata: std::collections::HashMap<String, String>,
-    ) -> Result<ImageSizeUrls, anyhow::Error> {
-        let img = image::load_from_memory(image_data)?;
+    ) -> Result<ImageSizeUrls, AppError> {
+        let img = image::load_from_memory(image_data)
+            .map_err(|e| AppError::field("image", format!("Unsupported image: {}", e)))?;
         let original = img.clone();
         let medium = img.resize(600, 450, image::imageops::FilterType::Lanczos3);
         let thumbnail = img.resize(200, 150, image::imageops::FilterType::Lanczos3);
@@ -318,7 +327,7 @@ impl R2Service {
         product_id: Uuid,
         game: &str,
         variant_id: Option<&str>,
-    ) -> Result<(), anyhow::Error> {
+    ) -> Result<(), AppError> {
         let sanitized_game = sanitize_for_path(game);
         let bucket_name = "images";
 
@@ -360,13 +369,14 @@ impl R2Service {
     }
 }
 
-fn image_to_jpeg_bytes(img: &image::DynamicImage, quality: u8) -> Result<Vec<u8>, anyhow::Error> {
+fn image_to_jpeg_bytes(img: &image::DynamicImage, quality: u8) -> Result<Vec<u8>, AppError> {
     let mut bytes: Vec<u8> = Vec::new();
     let mut cursor = Cursor::new(&mut bytes);
 
     let mut encoder = JpegEncoder::new_with_quality(&mut cursor, quality);
 
-    encoder.encode_image(img)?;
+    encoder.encode_image(img)
+        .map_err(|e| AppError::Internal(format!("Failed to encode image: {}", e)))?;
 
     Ok(bytes)
 }
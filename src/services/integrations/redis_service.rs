@@ -0,0 +1,326 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use deadpool_redis::redis::{AsyncCommands, Script};
+use deadpool_redis::{Pool, Runtime};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::errors::{AppError, UpstreamService};
+use crate::utils::message_util::MessageUtil;
+
+/// Keys are only swept once a table grows past this, keeping the hot path to one entry.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Trims the window, counts what is left and records the hit only if there is room, all
+/// in one round trip so concurrent requests can't both take the last slot. Returns 0 when
+/// allowed, otherwise the milliseconds until the oldest hit leaves the window.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local max_requests = tonumber(ARGV[3])
+redis.call('ZREMRANGEBYSCORE', KEYS[1], 0, now - window)
+if redis.call('ZCARD', KEYS[1]) >= max_requests then
+    local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+    return math.max(1, tonumber(oldest[2]) + window - now)
+end
+redis.call('ZADD', KEYS[1], now, ARGV[4])
+redis.call('PEXPIRE', KEYS[1], window)
+return 0
+"#;
+
+/// Process-local stand-in for Redis, used when `REDIS_URL` is not set. Fine for a single
+/// instance; with several, each keeps its own cache and rate limits.
+#[derive(Default)]
+pub struct InMemoryCache {
+    entries: Mutex<HashMap<String, (String, Instant)>>,
+    windows: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl InMemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        let entries = self.entries.lock().ok()?;
+
+        entries.get(key)
+            .filter(|(_, expires_at)| *expires_at > Instant::now())
+            .map(|(value, _)| value.clone())
+    }
+
+    fn set(&self, key: &str, value: String, ttl: Duration) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let now = Instant::now();
+
+            if entries.len() > SWEEP_THRESHOLD {
+                entries.retain(|_, (_, expires_at)| *expires_at > now);
+            }
+
+            entries.insert(key.to_string(), (value, now + ttl));
+        }
+    }
+
+    fn delete(&self, keys: &[String]) {
+        if let Ok(mut entries) = self.entries.lock() {
+            for key in keys {
+                entries.remove(key);
+            }
+        }
+    }
+
+    fn hit(&self, key: &str, max_requests: usize, window: Duration) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = match self.windows.lock() {
+            Ok(windows) => windows,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if windows.len() > SWEEP_THRESHOLD {
+            windows.retain(|_, hits| hits.back().map_or(false, |last| now.duration_since(*last) < window));
+        }
+
+        let hits = windows.entry(key.to_string()).or_default();
+
+        while hits.front().map_or(false, |first| now.duration_since(*first) >= window) {
+            hits.pop_front();
+        }
+
+        if hits.len() >= max_requests {
+            let oldest = hits.front().copied().unwrap_or(now);
+            return Err(window.saturating_sub(now.duration_since(oldest)));
+        }
+
+        hits.push_back(now);
+        Ok(())
+    }
+}
+
+pub enum CacheClient {
+    Redis(Pool),
+    InMemory(InMemoryCache),
+}
+
+impl CacheClient {
+    pub fn from_config() -> Result<Self, AppError> {
+        let config = Config::get();
+
+        if config.redis_url.is_empty() {
+            MessageUtil::info("REDIS_URL is not set, caching and rate limits will be kept in memory");
+            return Ok(CacheClient::InMemory(InMemoryCache::new()));
+        }
+
+        let pool = deadpool_redis::Config::from_url(&config.redis_url)
+            .create_pool(Some(Runtime::Tokio1))
+            .map_err(|e| AppError::Internal(format!("Failed to create Redis pool: {}", e)))?;
+
+        Ok(CacheClient::Redis(pool))
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<String>, AppError> {
+        match self {
+            CacheClient::Redis(pool) => {
+                let mut conn = Self::connection(pool).await?;
+
+                conn.get(key)
+                    .await
+                    .map_err(|e| AppError::Upstream(UpstreamService::Redis, format!("Failed to read cache key '{}': {}", key, e)))
+            }
+            CacheClient::InMemory(cache) => Ok(cache.get(key)),
+        }
+    }
+
+    pub async fn set(&self, key: &str, value: String, ttl: Duration) -> Result<(), AppError> {
+        match self {
+            CacheClient::Redis(pool) => {
+                let mut conn = Self::connection(pool).await?;
+
+                conn.set_ex::<_, _, ()>(key, value, ttl.as_secs().max(1))
+                    .await
+                    .map_err(|e| AppError::Upstream(UpstreamService::Redis, format!("Failed to write cache key '{}': {}", key, e)))
+            }
+            CacheClient::InMemory(cache) => {
+                cache.set(key, value, ttl);
+                Ok(())
+            }
+        }
+    }
+
+    pub async fn delete(&self, keys: &[String]) -> Result<(), AppError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        match self {
+            CacheClient::Redis(pool) => {
+                let mut conn = Self::connection(pool).await?;
+
+                conn.del::<_, ()>(keys)
+                    .await
+                    .map_err(|e| AppError::Upstream(UpstreamService::Redis, format!("Failed to delete cache keys: {}", e)))
+            }
+            CacheClient::InMemory(cache) => {
+                cache.delete(keys);
+                Ok(())
+            }
+        }
+    }
+
+    /// Records a hit against a sliding window. The inner error is how long to wait when
+    /// `key` is already at `max_requests`.
+    pub async fn hit_window(
+        &self,
+        key: &str,
+        max_requests: usize,
+        window: Duration,
+    ) -> Result<Result<(), Duration>, AppError> {
+        match self {
+            CacheClient::Redis(pool) => {
+                let mut conn = Self::connection(pool).await?;
+                let now_ms = chrono::Utc::now().timestamp_millis();
+
+                let retry_after_ms: i64 = Script::new(SLIDING_WINDOW_SCRIPT)
+                    .key(key)
+                    .arg(now_ms)
+                    .arg(window.as_millis() as i64)
+                    .arg(max_requests)
+                    .arg(Uuid::new_v4().to_string())
+                    .invoke_async(&mut conn)
+                    .await
+                    .map_err(|e| AppError::Upstream(UpstreamService::Redis, format!("Failed to check rate limit: {}", e)))?;
+
+                if retry_after_ms > 0 {
+                    return Ok(Err(Duration::from_millis(retry_after_ms as u64)));
+                }
+
+                Ok(Ok(()))
+            }
+            CacheClient::InMemory(cache) => Ok(cache.hit(key, max_requests, window)),
+        }
+    }
+
+    async fn connection(pool: &Pool) -> Result<deadpool_redis::Connection, AppError> {
+        pool.get()
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Redis, format!("Failed to get Redis connection: {}", e)))
+    }
+}
+
+/// Typed, best-effort access to the cache. Read and write failures are logged and treated
+/// as misses, so an unavailable Redis slows requests down instead of failing them.
+pub struct CacheService {
+    state: AppState,
+}
+
+impl CacheService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub fn product_key(product_id: &Uuid) -> String {
+        format!("product:{}", product_id)
+    }
+
+    pub fn product_listings_key(product_id: &Uuid) -> String {
+        format!("product:{}:listings", product_id)
+    }
+
+    pub fn games_key() -> String {
+        "games".to_string()
+    }
+
+    pub fn game_sets_key(game_name: &str) -> String {
+        format!("games:{}:sets", game_name)
+    }
+
+    pub fn trending_products_key() -> String {
+        "search:trending".to_string()
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        match self.state.cache.get(key).await {
+            Ok(Some(value)) => serde_json::from_str(&value).ok(),
+            Ok(None) => None,
+            Err(e) => {
+                MessageUtil::error(&e.to_string());
+                None
+            }
+        }
+    }
+
+    pub async fn set<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        let value = match serde_json::to_string(value) {
+            Ok(value) => value,
+            Err(e) => {
+                MessageUtil::error(&format!("Failed to serialize cache value for '{}': {}", key, e));
+                return;
+            }
+        };
+
+        if let Err(e) = self.state.cache.set(key, value, ttl).await {
+            MessageUtil::error(&e.to_string());
+        }
+    }
+
+    pub async fn invalidate(&self, keys: &[String]) {
+        if let Err(e) = self.state.cache.delete(keys).await {
+            MessageUtil::error(&e.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_values_expire() {
+        let cache = InMemoryCache::new();
+
+        cache.set("fresh", "a".to_string(), Duration::from_secs(60));
+        cache.set("stale", "b".to_string(), Duration::ZERO);
+
+        assert_eq!(cache.get("fresh").as_deref(), Some("a"));
+        assert_eq!(cache.get("stale"), None);
+
+        cache.delete(&["fresh".to_string()]);
+        assert_eq!(cache.get("fresh"), None);
+    }
+
+    #[test]
+    fn rate_limit_allows_up_to_the_maximum() {
+        let cache = InMemoryCache::new();
+        let window = Duration::from_secs(60);
+
+        assert!(cache.hit("ip", 2, window).is_ok());
+        assert!(cache.hit("ip", 2, window).is_ok());
+
+        let retry_after = cache.hit("ip", 2, window).unwrap_err();
+        assert!(retry_after > Duration::ZERO && retry_after <= window);
+    }
+
+    #[test]
+    fn rate_limits_are_per_key() {
+        let cache = InMemoryCache::new();
+        let window = Duration::from_secs(60);
+
+        assert!(cache.hit("a", 1, window).is_ok());
+        assert!(cache.hit("a", 1, window).is_err());
+        assert!(cache.hit("b", 1, window).is_ok());
+    }
+
+    #[test]
+    fn hits_leave_the_window() {
+        let cache = InMemoryCache::new();
+        let window = Duration::from_millis(20);
+
+        assert!(cache.hit("ip", 1, window).is_ok());
+        assert!(cache.hit("ip", 1, window).is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(cache.hit("ip", 1, window).is_ok());
+    }
+}
@@ -5,8 +5,11 @@ use chrono::{DateTime, Utc};
 use reqwest::{Client, Response};
 use sea_orm::prelude::Decimal;
 use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
 use crate::app_state::AppState;
 use crate::config::config::Config;
+use crate::errors::{AppError, UpstreamService};
 
 pub struct StripeClient {
     client: Arc<Client>,
@@ -109,6 +112,85 @@ pub struct StripePrice {
     pub unit_amount_decimal: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripePaymentIntent {
+    pub id: String,
+    pub object: String,
+    pub amount: i64,
+    pub currency: String,
+    pub status: String,
+    pub client_secret: Option<String>,
+    pub latest_charge: Option<String>,
+    pub transfer_group: Option<String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    pub created: u64,
+    pub livemode: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripeAccount {
+    pub id: String,
+    pub object: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub charges_enabled: bool,
+    #[serde(default)]
+    pub payouts_enabled: bool,
+    #[serde(default)]
+    pub details_submitted: bool,
+    #[serde(default)]
+    pub capabilities: HashMap<String, String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl StripeAccount {
+    pub fn can_receive_payouts(&self) -> bool {
+        self.charges_enabled
+            && self.payouts_enabled
+            && self.capabilities.get("transfers").map(|status| status == "active").unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripeAccountLink {
+    pub object: String,
+    pub url: String,
+    pub created: u64,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripeTransfer {
+    pub id: String,
+    pub object: String,
+    pub amount: i64,
+    pub currency: String,
+    pub destination: String,
+    pub transfer_group: Option<String>,
+    pub source_transaction: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripeTransferReversal {
+    pub id: String,
+    pub object: String,
+    pub amount: i64,
+    pub currency: String,
+    pub transfer: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StripeRefund {
+    pub id: String,
+    pub object: String,
+    pub amount: i64,
+    pub currency: String,
+    pub payment_intent: Option<String>,
+    pub status: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProductWithPrice {
     pub product: StripeProduct,
@@ -124,11 +206,14 @@ impl StripeService {
         Self { state }
     }
     
+    /// Creates a product with a default price. Stripe replays the first response for a
+    /// repeated `idempotency_key`, so a retried job does not create a second product.
     pub async fn create_stripe_product(
         product_name: &str,
         product_description: Option<&str>,
         price: i64,
-    ) -> Result<StripeProduct, String> {
+        idempotency_key: &str,
+    ) -> Result<StripeProduct, AppError> {
         let key: &str = Config::get().stripe_key.as_ref();
         let client = Client::new();
 
@@ -147,26 +232,27 @@ impl StripeService {
             .post(product_url)
             .header("Authorization", format!("Bearer {}", key))
             .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Idempotency-Key", idempotency_key)
             .form(&product_params)
             .send()
             .await
-            .map_err(|e| format!("Failed to send product creation request: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send product creation request: {}", e)))?;
 
         if !product_response.status().is_success() {
             let error_text = product_response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("Stripe product creation error: {}", error_text));
+            return Err(AppError::Upstream(UpstreamService::Stripe, format!("Stripe product creation error: {}", error_text)));
         }
 
         let product: StripeProduct = product_response.json().await
-            .map_err(|e| format!("Failed to parse product response: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to parse product response: {}", e)))?;
 
         Ok(product)
     }
     
     pub async fn get_stripe_product(
         product_id: &str,
-    ) -> Result<StripeProduct, String> {
+    ) -> Result<StripeProduct, AppError> {
         let key: &str = Config::get().stripe_key.as_ref();
         let client = reqwest::Client::new();
         
@@ -177,22 +263,22 @@ impl StripeService {
             .header("Authorization", format!("Bearer {}", key))
             .send()
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send request: {}", e)))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("Stripe product retrieval error: {}", error_text));
+            return Err(AppError::Upstream(UpstreamService::Stripe, format!("Stripe product retrieval error: {}", error_text)));
         }
 
         response.json::<StripeProduct>().await
-            .map_err(|e| format!("Failed to parse product response: {}", e))
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to parse product response: {}", e)))
     }
     
     pub async fn delete_stripe_product(
         &self,
         product_id: &str,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let key: &str = Config::get().stripe_key.as_ref();
         let client = reqwest::Client::new();
 
@@ -203,16 +289,16 @@ impl StripeService {
             .header("Authorization", format!("Bearer {}", key))
             .send()
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send request: {}", e)))?;
         
         if !response.status().is_success() {
             let error_text = response.text().await
             .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("Stripe product retrieval error: {}", error_text));
+            return Err(AppError::Upstream(UpstreamService::Stripe, format!("Stripe product retrieval error: {}", error_text)));
         }
         
         let product = response.json::<StripeProduct>().await
-            .map_err(|e| format!("Failed to parse product response: {}", e));
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to parse product response: {}", e)));
         
         let price_url = format!("https://api.stripe.com/v1/prices/{}", product?.default_price);
 
@@ -221,12 +307,12 @@ impl StripeService {
             .header("Authorization", format!("Bearer {}", key))
             .send()
             .await
-            .map_err(|e| format!("Failed to send request: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send request: {}", e)))?;
 
         if !delete_price_response.status().is_success() {
             let error_text = delete_price_response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("Stripe product retrieval error: {}", error_text));
+            return Err(AppError::Upstream(UpstreamService::Stripe, format!("Stripe product retrieval error: {}", error_text)));
         }
         
         let delete_response = client
@@ -234,14 +320,214 @@ impl StripeService {
             .header("Authorization", format!("Bearer {}", key))
             .send()
             .await
-            .map_err(|e| format!("Failed to send delete request: {}", e))?;
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send delete request: {}", e)))?;
 
         if !delete_response.status().is_success() {
             let error_text = delete_response.text().await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(format!("Stripe product deletion error: {}", error_text));
+            return Err(AppError::Upstream(UpstreamService::Stripe, format!("Stripe product deletion error: {}", error_text)));
         }
 
         Ok(())
     }
+
+    pub async fn set_stripe_product_active(
+        &self,
+        product_id: &str,
+        active: bool,
+    ) -> Result<StripeProduct, AppError> {
+        let params = vec![("active".to_string(), active.to_string())];
+
+        self.post_form(&format!("products/{}", product_id), &params, None, "product update").await
+    }
+
+    pub async fn create_payment_intent(
+        &self,
+        amount: i64,
+        currency: &str,
+        transfer_group: &str,
+        metadata: &[(String, String)],
+        idempotency_key: &str,
+    ) -> Result<StripePaymentIntent, AppError> {
+        let mut params = vec![
+            ("amount".to_string(), amount.to_string()),
+            ("currency".to_string(), currency.to_string()),
+            ("automatic_payment_methods[enabled]".to_string(), "true".to_string()),
+            ("transfer_group".to_string(), transfer_group.to_string()),
+        ];
+
+        for (key, value) in metadata {
+            params.push((format!("metadata[{}]", key), value.clone()));
+        }
+
+        self.post_form("payment_intents", &params, Some(idempotency_key), "payment intent creation").await
+    }
+
+    pub async fn get_payment_intent(
+        &self,
+        payment_intent_id: &str,
+    ) -> Result<StripePaymentIntent, AppError> {
+        self.get(&format!("payment_intents/{}", payment_intent_id), "payment intent retrieval").await
+    }
+
+    pub async fn cancel_payment_intent(
+        &self,
+        payment_intent_id: &str,
+    ) -> Result<StripePaymentIntent, AppError> {
+        self.post_form(
+            &format!("payment_intents/{}/cancel", payment_intent_id),
+            &[],
+            None,
+            "payment intent cancellation",
+        ).await
+    }
+
+    pub async fn create_connected_account(
+        &self,
+        email: &str,
+        user_id: &Uuid,
+    ) -> Result<StripeAccount, AppError> {
+        let params = vec![
+            ("type".to_string(), "express".to_string()),
+            ("email".to_string(), email.to_string()),
+            ("capabilities[card_payments][requested]".to_string(), "true".to_string()),
+            ("capabilities[transfers][requested]".to_string(), "true".to_string()),
+            ("metadata[user_id]".to_string(), user_id.to_string()),
+        ];
+
+        self.post_form(
+            "accounts",
+            &params,
+            Some(&format!("connect-account-{}", user_id)),
+            "connected account creation",
+        ).await
+    }
+
+    pub async fn get_connected_account(
+        &self,
+        account_id: &str,
+    ) -> Result<StripeAccount, AppError> {
+        self.get(&format!("accounts/{}", account_id), "connected account retrieval").await
+    }
+
+    pub async fn create_account_link(
+        &self,
+        account_id: &str,
+        refresh_url: &str,
+        return_url: &str,
+    ) -> Result<StripeAccountLink, AppError> {
+        let params = vec![
+            ("account".to_string(), account_id.to_string()),
+            ("refresh_url".to_string(), refresh_url.to_string()),
+            ("return_url".to_string(), return_url.to_string()),
+            ("type".to_string(), "account_onboarding".to_string()),
+        ];
+
+        self.post_form("account_links", &params, None, "account link creation").await
+    }
+
+    pub async fn create_transfer(
+        &self,
+        amount: i64,
+        currency: &str,
+        destination: &str,
+        source_transaction: Option<&str>,
+        transfer_group: &str,
+        idempotency_key: &str,
+    ) -> Result<StripeTransfer, AppError> {
+        let mut params = vec![
+            ("amount".to_string(), amount.to_string()),
+            ("currency".to_string(), currency.to_string()),
+            ("destination".to_string(), destination.to_string()),
+            ("transfer_group".to_string(), transfer_group.to_string()),
+        ];
+
+        if let Some(source_transaction) = source_transaction {
+            params.push(("source_transaction".to_string(), source_transaction.to_string()));
+        }
+
+        self.post_form("transfers", &params, Some(idempotency_key), "transfer creation").await
+    }
+
+    /// Reverses the whole of a transfer, pulling the funds back from the connected account.
+    pub async fn create_transfer_reversal(
+        &self,
+        transfer_id: &str,
+        idempotency_key: &str,
+    ) -> Result<StripeTransferReversal, AppError> {
+        self.post_form(
+            &format!("transfers/{}/reversals", transfer_id),
+            &[],
+            Some(idempotency_key),
+            "transfer reversal",
+        ).await
+    }
+
+    /// Refunds the full amount of a payment intent.
+    pub async fn create_refund(
+        &self,
+        payment_intent_id: &str,
+        idempotency_key: &str,
+    ) -> Result<StripeRefund, AppError> {
+        let params = vec![("payment_intent".to_string(), payment_intent_id.to_string())];
+
+        self.post_form("refunds", &params, Some(idempotency_key), "refund creation").await
+    }
+
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        context: &str,
+    ) -> Result<T, AppError> {
+        let stripe_client = self.state.stripe_client.as_ref();
+
+        let response = stripe_client.client
+            .get(format!("{}/{}", stripe_client.base_url, path))
+            .header("Authorization", format!("Bearer {}", stripe_client.api_key))
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send {} request: {}", context, e)))?;
+
+        Self::parse_response(response, context).await
+    }
+
+    async fn post_form<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(String, String)],
+        idempotency_key: Option<&str>,
+        context: &str,
+    ) -> Result<T, AppError> {
+        let stripe_client = self.state.stripe_client.as_ref();
+
+        let mut request = stripe_client.client
+            .post(format!("{}/{}", stripe_client.base_url, path))
+            .header("Authorization", format!("Bearer {}", stripe_client.api_key))
+            .form(params);
+
+        if let Some(idempotency_key) = idempotency_key {
+            request = request.header("Idempotency-Key", idempotency_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to send {} request: {}", context, e)))?;
+
+        Self::parse_response(response, context).await
+    }
+
+    async fn parse_response<T: DeserializeOwned>(
+        response: Response,
+        context: &str,
+    ) -> Result<T, AppError> {
+        if !response.status().is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::Upstream(UpstreamService::Stripe, format!("Stripe {} error: {}", context, error_text)));
+        }
+
+        response.json::<T>().await
+            .map_err(|e| AppError::Upstream(UpstreamService::Stripe, format!("Failed to parse Stripe {} response: {}", context, e)))
+    }
 }
\ No newline at end of file
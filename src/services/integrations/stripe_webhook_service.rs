@@ -0,0 +1,480 @@
+use hmac::{Hmac, Mac};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveModelBehavior, DatabaseTransaction, EntityTrait, Set, TransactionTrait};
+use serde::Deserialize;
+use sha2::Sha256;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{orders, stripe_events};
+use crate::errors::AppError;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::integrations::stripe_service::StripeAccount;
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::services::marketplace::seller_service::SellerService;
+use crate::services::transactions::order_service::{OrderService, PaymentOutcome};
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SIGNATURE_TOLERANCE_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub created: i64,
+    #[serde(default)]
+    pub livemode: bool,
+    pub data: StripeEventData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeEventData {
+    pub object: serde_json::Value,
+}
+
+pub struct StripeWebhookService {
+    state: AppState,
+}
+
+impl StripeWebhookService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Parses a webhook payload after checking its `Stripe-Signature` header.
+    pub fn construct_event(
+        payload: &[u8],
+        signature_header: &str,
+        secret: &str,
+    ) -> Result<StripeEvent, AppError> {
+        Self::verify_signature(payload, signature_header, secret, chrono::Utc::now().timestamp())?;
+
+        serde_json::from_slice::<StripeEvent>(payload)
+            .map_err(|e| AppError::validation(format!("Failed to parse Stripe event: {}", e)))
+    }
+
+    /// Verifies the `t=...,v1=...` header against an HMAC-SHA256 of `"{t}.{payload}"`.
+    /// `now` is passed in so signed fixtures can be checked without touching the clock.
+    pub fn verify_signature(
+        payload: &[u8],
+        signature_header: &str,
+        secret: &str,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let mut timestamp: Option<i64> = None;
+        let mut signatures = Vec::new();
+
+        for part in signature_header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => {
+                    timestamp = value.parse().ok();
+                }
+                Some(("v1", value)) => {
+                    if let Ok(signature) = hex::decode(value) {
+                        signatures.push(signature);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let timestamp = timestamp.ok_or(AppError::validation("Stripe signature is missing a timestamp"))?;
+
+        if signatures.is_empty() {
+            return Err(AppError::validation("Stripe signature is missing a v1 signature"));
+        }
+
+        if (now - timestamp).abs() > SIGNATURE_TOLERANCE_SECONDS {
+            return Err(AppError::validation("Stripe signature timestamp is outside the tolerance window"));
+        }
+
+        for signature in signatures {
+            let mut mac = HmacSha256::new_from_slice(secret.as_bytes())
+                .map_err(|e| AppError::Internal(format!("Invalid webhook secret: {}", e)))?;
+            mac.update(timestamp.to_string().as_bytes());
+            mac.update(b".");
+            mac.update(payload);
+
+            if mac.verify_slice(&signature).is_ok() {
+                return Ok(());
+            }
+        }
+
+        Err(AppError::validation("Stripe signature does not match the payload"))
+    }
+
+    /// Records the event and applies it in one transaction. Returns `false` when the event
+    /// was already processed. A concurrent delivery of the same event blocks on the
+    /// uncommitted row until this one commits, and a failure or crash rolls both back so
+    /// Stripe's retry gets another chance at it. Money movements are queued as jobs on the
+    /// same transaction rather than made here, so a failed commit can't repeat them.
+    ///
+    /// An event that can never apply, like one for an unknown order, is logged and
+    /// acknowledged: its changes are rolled back to a savepoint, but the event is still
+    /// recorded so Stripe stops retrying it.
+    pub async fn process_event(
+        &self,
+        event: StripeEvent,
+    ) -> Result<bool, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let record = stripe_events::ActiveModel {
+            id: Set(event.id.clone()),
+            event_type: Set(event.event_type.clone()),
+            livemode: Set(event.livemode),
+            created_at: Set(chrono::DateTime::from_timestamp(event.created, 0)
+                .unwrap_or_else(|| chrono::Utc::now())),
+            ..stripe_events::ActiveModel::new()
+        };
+
+        let inserted = stripe_events::Entity::insert(record)
+            .on_conflict(
+                OnConflict::column(stripe_events::Column::Id)
+                    .do_nothing()
+                    .to_owned()
+            )
+            .exec_without_returning(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to record Stripe event: {}", e)))?;
+
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        let savepoint = txn.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start savepoint: {}", e)))?;
+
+        match self.handle_event(&savepoint, &event).await {
+            Ok(()) => {
+                savepoint.commit()
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to apply Stripe event: {}", e)))?;
+            }
+            Err(e) if can_never_succeed(&e) => {
+                savepoint.rollback()
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to roll back Stripe event: {}", e)))?;
+
+                MessageUtil::error(&format!(
+                    "Ignoring Stripe event {} ({}): {}",
+                    event.id, event.event_type, e
+                ));
+            }
+            Err(e) => return Err(e),
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit Stripe event: {}", e)))?;
+
+        Ok(true)
+    }
+
+    async fn handle_event(
+        &self,
+        txn: &DatabaseTransaction,
+        event: &StripeEvent,
+    ) -> Result<(), AppError> {
+        let object = &event.data.object;
+        let order_service = OrderService::new(self.state.clone());
+
+        match event.event_type.as_str() {
+            "payment_intent.succeeded" => {
+                let order_id = order_id_from_metadata(object)?;
+                let payment_intent_id = string_field(object, "id")?;
+
+                let order = order_service.get_order_by_payment_intent(payment_intent_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound(format!("No order found for payment intent {}", payment_intent_id)))?;
+
+                if order.id != order_id {
+                    return Err(AppError::validation("Payment intent metadata does not match its order"));
+                }
+
+                let amount_received = object.get("amount_received")
+                    .and_then(|value| value.as_i64())
+                    .unwrap_or(0);
+
+                if amount_received < order.total_amount {
+                    return Err(AppError::validation(format!(
+                        "Payment for order {} is short: received {}, expected {}",
+                        order.id, amount_received, order.total_amount
+                    )));
+                }
+
+                match order_service.mark_order_paid(txn, order.id).await? {
+                    PaymentOutcome::Paid(order) => {
+                        let charge_id = object.get("latest_charge")
+                            .and_then(|value| value.as_str())
+                            .map(str::to_string);
+
+                        JobService::enqueue(txn, Job::TransferOrderFunds { order_id: order.id, charge_id }).await?;
+                    }
+                    PaymentOutcome::OrderClosed(order) => {
+                        Self::refund_closed_order(txn, &order, payment_intent_id).await?;
+                    }
+                }
+            }
+            "payment_intent.canceled" => {
+                let order_id = order_id_from_metadata(object)?;
+                Self::require_order(txn, order_id).await?;
+                order_service.cancel_unpaid_order(txn, order_id).await?;
+            }
+            "payment_intent.payment_failed" => {
+                // The payment intent stays usable so the buyer can retry with another method.
+                MessageUtil::info(&format!(
+                    "Payment failed for payment intent {}",
+                    string_field(object, "id")?
+                ));
+            }
+            "charge.refunded" => {
+                let payment_intent_id = string_field(object, "payment_intent")?;
+                let (amount_refunded, fully_refunded) = refund_totals(object)?;
+
+                let order = order_service.get_order_by_payment_intent(payment_intent_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound(format!("No order found for payment intent {}", payment_intent_id)))?;
+
+                order_service.record_refund(txn, order.id, amount_refunded, fully_refunded).await?;
+            }
+            "charge.dispute.created" => {
+                let payment_intent_id = string_field(object, "payment_intent")?;
+
+                match order_service.get_order_by_payment_intent(payment_intent_id).await? {
+                    Some(order) => MessageUtil::error(&format!(
+                        "Dispute opened for order {} (payment intent {})",
+                        order.id, payment_intent_id
+                    )),
+                    None => MessageUtil::error(&format!(
+                        "Dispute opened for unknown payment intent {}",
+                        payment_intent_id
+                    )),
+                }
+            }
+            "account.updated" => {
+                let account = serde_json::from_value::<StripeAccount>(object.clone())
+                    .map_err(|e| AppError::validation(format!("Failed to parse Stripe account: {}", e)))?;
+
+                SellerService::new(self.state.clone())
+                    .sync_connected_account(txn, &account)
+                    .await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// A payment that lands after its order was cancelled, usually by the hold sweeper,
+    /// can't be applied because the stock may already be sold again. The refund is queued
+    /// and the event acknowledged, since retrying it can't help; a refund Stripe keeps
+    /// refusing ends up as a dead job for someone to look at.
+    async fn refund_closed_order(
+        txn: &DatabaseTransaction,
+        order: &orders::Model,
+        payment_intent_id: &str,
+    ) -> Result<(), AppError> {
+        MessageUtil::info(&format!(
+            "Payment intent {} succeeded for cancelled order {}, queueing a refund",
+            payment_intent_id, order.id
+        ));
+
+        JobService::enqueue(txn, Job::RefundLatePayment {
+            order_id: order.id,
+            payment_intent_id: payment_intent_id.to_string(),
+        }).await?;
+
+        AuditService::record_on(txn, AuditEvent {
+            actor_id: None,
+            client: &ClientInfo::default(),
+            action: "order.late_payment_received",
+            target_type: "order",
+            target_id: Some(order.id.to_string()),
+            old_values: None,
+            new_values: Some(serde_json::json!({ "status": order.status })),
+            metadata: Some(serde_json::json!({ "payment_intent_id": payment_intent_id })),
+        }).await?;
+
+        Ok(())
+    }
+
+    async fn require_order(
+        txn: &DatabaseTransaction,
+        order_id: Uuid,
+    ) -> Result<(), AppError> {
+        orders::Entity::find_by_id(order_id)
+            .one(txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch order: {}", e)))?
+            .ok_or_else(|| AppError::NotFound(format!("Order {} not found", order_id)))?;
+
+        Ok(())
+    }
+}
+
+/// Bad metadata and unknown orders won't fix themselves on a retry; anything else might
+/// be a passing outage, so Stripe is asked to deliver the event again.
+fn can_never_succeed(e: &AppError) -> bool {
+    matches!(e, AppError::Validation { .. } | AppError::NotFound(_))
+}
+
+/// How much of the charge has been refunded so far, and whether that is all of it.
+fn refund_totals(object: &serde_json::Value) -> Result<(i64, bool), AppError> {
+    let amount_refunded = object.get("amount_refunded")
+        .and_then(|value| value.as_i64())
+        .ok_or_else(|| AppError::validation("Stripe charge is missing 'amount_refunded'"))?;
+
+    let fully_refunded = object.get("refunded")
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+
+    Ok((amount_refunded, fully_refunded))
+}
+
+fn string_field<'a>(object: &'a serde_json::Value, field: &str) -> Result<&'a str, AppError> {
+    object.get(field)
+        .and_then(|value| value.as_str())
+        .ok_or_else(|| AppError::validation(format!("Stripe object is missing '{}'", field)))
+}
+
+fn order_id_from_metadata(object: &serde_json::Value) -> Result<Uuid, AppError> {
+    object.get("metadata")
+        .and_then(|metadata| metadata.get("order_id"))
+        .and_then(|value| value.as_str())
+        .ok_or(AppError::validation("Payment intent has no order_id metadata"))
+        .and_then(|value| Uuid::parse_str(value).map_err(|e| AppError::validation(format!("Invalid order_id metadata: {}", e))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "whsec_test_secret";
+    const NOW: i64 = 1_700_000_000;
+
+    const SUCCEEDED_FIXTURE: &str = r#"{
+        "id": "evt_1",
+        "type": "payment_intent.succeeded",
+        "created": 1700000000,
+        "livemode": false,
+        "data": {
+            "object": {
+                "id": "pi_1",
+                "amount_received": 2500,
+                "latest_charge": "ch_1",
+                "metadata": { "order_id": "6f1c1b1e-2f4a-4c55-9d7e-0d7e3c1f9a10" }
+            }
+        }
+    }"#;
+
+    fn sign(payload: &[u8], secret: &str, timestamp: i64) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(payload);
+
+        format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn accepts_a_signed_fixture_and_parses_it() {
+        let payload = SUCCEEDED_FIXTURE.as_bytes();
+        let header = sign(payload, SECRET, NOW);
+
+        StripeWebhookService::verify_signature(payload, &header, SECRET, NOW).unwrap();
+
+        let event = serde_json::from_slice::<StripeEvent>(payload).unwrap();
+        assert_eq!(event.id, "evt_1");
+        assert_eq!(event.event_type, "payment_intent.succeeded");
+        assert_eq!(string_field(&event.data.object, "id").unwrap(), "pi_1");
+        assert_eq!(
+            order_id_from_metadata(&event.data.object).unwrap(),
+            Uuid::parse_str("6f1c1b1e-2f4a-4c55-9d7e-0d7e3c1f9a10").unwrap(),
+        );
+    }
+
+    #[test]
+    fn accepts_any_matching_signature_while_secrets_roll() {
+        let payload = SUCCEEDED_FIXTURE.as_bytes();
+        let old = sign(payload, "whsec_old_secret", NOW);
+        let current = sign(payload, SECRET, NOW);
+        let header = format!("{},{}", old, current.split_once(',').unwrap().1);
+
+        StripeWebhookService::verify_signature(payload, &header, SECRET, NOW).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_tampered_payload() {
+        let header = sign(SUCCEEDED_FIXTURE.as_bytes(), SECRET, NOW);
+        let tampered = SUCCEEDED_FIXTURE.replace("2500", "250000");
+
+        assert!(StripeWebhookService::verify_signature(tampered.as_bytes(), &header, SECRET, NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_the_wrong_secret() {
+        let payload = SUCCEEDED_FIXTURE.as_bytes();
+        let header = sign(payload, "whsec_someone_else", NOW);
+
+        assert!(StripeWebhookService::verify_signature(payload, &header, SECRET, NOW).is_err());
+    }
+
+    #[test]
+    fn rejects_timestamps_outside_the_tolerance_window() {
+        let payload = SUCCEEDED_FIXTURE.as_bytes();
+        let stale = sign(payload, SECRET, NOW - SIGNATURE_TOLERANCE_SECONDS - 1);
+        let future = sign(payload, SECRET, NOW + SIGNATURE_TOLERANCE_SECONDS + 1);
+        let edge = sign(payload, SECRET, NOW - SIGNATURE_TOLERANCE_SECONDS);
+
+        assert!(StripeWebhookService::verify_signature(payload, &stale, SECRET, NOW).is_err());
+        assert!(StripeWebhookService::verify_signature(payload, &future, SECRET, NOW).is_err());
+        StripeWebhookService::verify_signature(payload, &edge, SECRET, NOW).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let payload = SUCCEEDED_FIXTURE.as_bytes();
+        let header = sign(payload, SECRET, NOW);
+        let signature = header.split_once(',').unwrap().1;
+
+        assert!(StripeWebhookService::verify_signature(payload, signature, SECRET, NOW).is_err());
+        assert!(StripeWebhookService::verify_signature(payload, &format!("t={}", NOW), SECRET, NOW).is_err());
+        assert!(StripeWebhookService::verify_signature(payload, &format!("t={},v1=not-hex", NOW), SECRET, NOW).is_err());
+        assert!(StripeWebhookService::verify_signature(payload, "", SECRET, NOW).is_err());
+    }
+
+    #[test]
+    fn acknowledges_events_that_can_never_apply() {
+        assert!(can_never_succeed(&AppError::NotFound("Order not found".to_string())));
+        assert!(can_never_succeed(&AppError::validation("Payment intent has no order_id metadata")));
+        assert!(!can_never_succeed(&AppError::Internal("connection reset".to_string())));
+        assert!(!can_never_succeed(&AppError::Upstream(crate::errors::UpstreamService::Stripe, "timeout".to_string())));
+    }
+
+    #[test]
+    fn reads_partial_and_full_refunds() {
+        let partial = serde_json::json!({ "payment_intent": "pi_1", "amount_refunded": 700, "refunded": false });
+        assert_eq!(refund_totals(&partial).unwrap(), (700, false));
+
+        let full = serde_json::json!({ "payment_intent": "pi_1", "amount_refunded": 2500, "refunded": true });
+        assert_eq!(refund_totals(&full).unwrap(), (2500, true));
+
+        let malformed = serde_json::json!({ "payment_intent": "pi_1", "refunded": true });
+        assert!(refund_totals(&malformed).is_err());
+    }
+
+    #[test]
+    fn rejects_payment_intents_without_order_metadata() {
+        let object = serde_json::json!({ "id": "pi_1", "metadata": {} });
+        assert!(order_id_from_metadata(&object).is_err());
+
+        let object = serde_json::json!({ "id": "pi_1", "metadata": { "order_id": "not-a-uuid" } });
+        assert!(order_id_from_metadata(&object).is_err());
+    }
+}
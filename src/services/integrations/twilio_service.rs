@@ -0,0 +1,113 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use async_trait::async_trait;
+use reqwest::Client;
+use crate::config::config::Config;
+use crate::errors::{AppError, UpstreamService};
+
+#[derive(Debug, Clone)]
+pub struct SmsMessage {
+    pub to: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait SmsSender: Send + Sync {
+    async fn send(&self, message: SmsMessage) -> Result<(), AppError>;
+}
+
+pub struct TwilioSmsSender {
+    client: Client,
+    account_sid: String,
+    auth_token: String,
+    from_number: String,
+    base_url: String,
+}
+
+impl TwilioSmsSender {
+    pub fn new(
+        account_sid: &str,
+        auth_token: &str,
+        from_number: &str,
+    ) -> Result<Self, AppError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(15))
+            .connect_timeout(Duration::from_secs(5))
+            .build()
+            .map_err(|e| AppError::Internal(format!("Failed to create Twilio client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            account_sid: account_sid.to_string(),
+            auth_token: auth_token.to_string(),
+            from_number: from_number.to_string(),
+            base_url: "https://api.twilio.com/2010-04-01".to_string(),
+        })
+    }
+
+    pub fn from_config() -> Result<Self, AppError> {
+        let config = Config::get();
+
+        Self::new(
+            &config.twilio_account_sid,
+            &config.twilio_auth_token,
+            &config.twilio_from_number,
+        )
+    }
+}
+
+#[async_trait]
+impl SmsSender for TwilioSmsSender {
+    async fn send(&self, message: SmsMessage) -> Result<(), AppError> {
+        let params = [
+            ("To", message.to),
+            ("From", self.from_number.clone()),
+            ("Body", message.body),
+        ];
+
+        let response = self.client
+            .post(format!("{}/Accounts/{}/Messages.json", self.base_url, self.account_sid))
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(UpstreamService::Twilio, format!("Failed to send SMS request: {}", e)))?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(AppError::Upstream(UpstreamService::Twilio, format!("Twilio SMS error: {}", error_text)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps sent texts in memory instead of delivering them; used in development and tests.
+#[derive(Default)]
+pub struct InMemorySmsSender {
+    sent: Mutex<Vec<SmsMessage>>,
+}
+
+impl InMemorySmsSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sent_messages(&self) -> Vec<SmsMessage> {
+        self.sent.lock()
+            .map(|sent| sent.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl SmsSender for InMemorySmsSender {
+    async fn send(&self, message: SmsMessage) -> Result<(), AppError> {
+        self.sent.lock()
+            .map_err(|_| AppError::Internal("SMS outbox is poisoned".to_string()))?
+            .push(message);
+
+        Ok(())
+    }
+}
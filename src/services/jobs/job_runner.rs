@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{listings, products};
+use crate::errors::AppError;
+use crate::services::integrations::meilisearch_service::MeilisearchService;
+use crate::services::integrations::r2_service::R2Service;
+use crate::services::integrations::stripe_service::StripeService;
+use crate::services::jobs::job_service::Job;
+use crate::services::transactions::order_service::OrderService;
+use crate::services::transactions::payout_service::PayoutService;
+
+/// Carries out a single job. Every job is safe to run more than once, since a worker can
+/// fail after the side effect happened but before the job was marked complete.
+pub struct JobRunner {
+    state: AppState,
+}
+
+impl JobRunner {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn run(&self, job: Job) -> Result<(), AppError> {
+        match job {
+            Job::IndexProducts { product_ids } => self.index_products(product_ids).await,
+            Job::RemoveProduct { product_id } => {
+                MeilisearchService::new(self.state.clone())
+                    .remove_product(&product_id)
+                    .await
+            }
+            Job::IndexListings { listing_ids } => self.index_listings(listing_ids).await,
+            Job::RemoveListings { listing_ids } => {
+                MeilisearchService::new(self.state.clone())
+                    .remove_listings(&listing_ids)
+                    .await
+            }
+            Job::CreateStripeProduct { listing_id } => self.create_stripe_product(listing_id).await,
+            Job::SetStripeProductActive { stripe_product_id, active } => {
+                StripeService::new(self.state.clone())
+                    .set_stripe_product_active(&stripe_product_id, active)
+                    .await
+                    .map(|_| ())
+            }
+            Job::DeleteStripeProduct { stripe_product_id } => {
+                StripeService::new(self.state.clone())
+                    .delete_stripe_product(&stripe_product_id)
+                    .await
+            }
+            Job::DeleteProductImages { product_id, game } => {
+                R2Service::new(self.state.clone())
+                    .delete_product_variant_images(product_id, &game, None)
+                    .await
+            }
+            Job::TransferOrderFunds { order_id, charge_id } => {
+                PayoutService::new(self.state.clone())
+                    .transfer_order_funds(order_id, charge_id.as_deref())
+                    .await
+            }
+            Job::ReverseOrderTransfers { order_id } => {
+                PayoutService::new(self.state.clone())
+                    .reverse_order_transfers(order_id)
+                    .await
+            }
+            Job::RefundLatePayment { order_id, payment_intent_id } => {
+                OrderService::new(self.state.clone())
+                    .refund_late_payment(order_id, &payment_intent_id)
+                    .await
+            }
+        }
+    }
+
+    /// Products deleted since the job was queued are skipped; their removal has its own job.
+    async fn index_products(
+        &self,
+        product_ids: Vec<Uuid>,
+    ) -> Result<(), AppError> {
+        let products = products::Entity::find()
+            .filter(products::Column::Id.is_in(product_ids))
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch products: {}", e)))?;
+
+        MeilisearchService::new(self.state.clone())
+            .index_products(&products)
+            .await
+    }
+
+    async fn index_listings(
+        &self,
+        listing_ids: Vec<Uuid>,
+    ) -> Result<(), AppError> {
+        let db = &self.state.db;
+
+        let listings = listings::Entity::find()
+            .filter(listings::Column::Id.is_in(listing_ids.clone()))
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listings: {}", e)))?;
+
+        let product_ids: Vec<Uuid> = listings.iter().map(|listing| listing.product_id).collect();
+
+        let products: HashMap<Uuid, products::Model> = products::Entity::find()
+            .filter(products::Column::Id.is_in(product_ids))
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch products: {}", e)))?
+            .into_iter()
+            .map(|product| (product.id, product))
+            .collect();
+
+        let mut indexed = Vec::new();
+
+        for listing in listings {
+            if listing.is_active() && listing.deleted_at.is_none() {
+                if let Some(product) = products.get(&listing.product_id) {
+                    indexed.push((listing, product.clone()));
+                }
+            }
+        }
+
+        let removed: Vec<Uuid> = listing_ids.into_iter()
+            .filter(|id| !indexed.iter().any(|(listing, _)| listing.id == *id))
+            .collect();
+
+        let search_service = MeilisearchService::new(self.state.clone());
+        search_service.index_listings(&indexed).await?;
+        search_service.remove_listings(&removed).await
+    }
+
+    async fn create_stripe_product(
+        &self,
+        listing_id: Uuid,
+    ) -> Result<(), AppError> {
+        let db = &self.state.db;
+
+        let listing = match listings::Entity::find_by_id(listing_id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+        {
+            Some(listing) if listing.stripe_product_id.is_none() && listing.deleted_at.is_none() => listing,
+            _ => return Ok(()),
+        };
+
+        let product = products::Entity::find_by_id(listing.product_id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+        let stripe_product = StripeService::create_stripe_product(
+            &product.name,
+            listing.description.as_deref(),
+            listing.price,
+            &format!("stripe-product-{}", listing.id),
+        ).await?;
+
+        // The listing may have been deleted while Stripe was answering; in that case the
+        // new product is surplus.
+        let result = listings::Entity::update_many()
+            .col_expr(listings::Column::StripeProductId, Expr::value(stripe_product.id.clone()))
+            .filter(listings::Column::Id.eq(listing.id))
+            .filter(listings::Column::StripeProductId.is_null())
+            .filter(listings::Column::DeletedAt.is_null())
+            .exec(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to store Stripe product: {}", e)))?;
+
+        if result.rows_affected == 0 {
+            StripeService::new(self.state.clone())
+                .delete_stripe_product(&stripe_product.id)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,478 @@
+use std::time::Duration;
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, LockBehavior, LockType};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::jobs;
+use crate::entities::jobs::{JOB_STATUS_COMPLETED, JOB_STATUS_DEAD, JOB_STATUS_PENDING, JOB_STATUS_RUNNING};
+use crate::errors::AppError;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::jobs::job_runner::JobRunner;
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
+
+const BASE_RETRY_DELAY_SECS: i64 = 10;
+const MAX_RETRY_DELAY_SECS: i64 = 60 * 60;
+
+/// A worker that dies mid-job leaves it `running`; after this long it is handed out again.
+const STALE_JOB_TIMEOUT_MINUTES: i64 = 15;
+const COMPLETED_JOB_RETENTION_DAYS: i64 = 7;
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Side effects that live outside the database. They are written to the `jobs` table in
+/// the same transaction as the change that causes them, so a crash or an outage of the
+/// remote service delays them instead of losing them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Job {
+    IndexProducts { product_ids: Vec<Uuid> },
+    RemoveProduct { product_id: Uuid },
+    /// Brings the listings' search documents in line with the database: live listings
+    /// are indexed, deleted or inactive ones removed.
+    IndexListings { listing_ids: Vec<Uuid> },
+    RemoveListings { listing_ids: Vec<Uuid> },
+    CreateStripeProduct { listing_id: Uuid },
+    SetStripeProductActive { stripe_product_id: String, active: bool },
+    DeleteStripeProduct { stripe_product_id: String },
+    DeleteProductImages { product_id: Uuid, game: String },
+    /// Pays each seller their share of a paid order.
+    TransferOrderFunds { order_id: Uuid, charge_id: Option<String> },
+    /// Pulls each seller's share of a refunded order back.
+    ReverseOrderTransfers { order_id: Uuid },
+    /// Returns a payment that landed after its order was cancelled.
+    RefundLatePayment { order_id: Uuid, payment_intent_id: String },
+}
+
+impl Job {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Job::IndexProducts { .. } => "index_products",
+            Job::RemoveProduct { .. } => "remove_product",
+            Job::IndexListings { .. } => "index_listings",
+            Job::RemoveListings { .. } => "remove_listings",
+            Job::CreateStripeProduct { .. } => "create_stripe_product",
+            Job::SetStripeProductActive { .. } => "set_stripe_product_active",
+            Job::DeleteStripeProduct { .. } => "delete_stripe_product",
+            Job::DeleteProductImages { .. } => "delete_product_images",
+            Job::TransferOrderFunds { .. } => "transfer_order_funds",
+            Job::ReverseOrderTransfers { .. } => "reverse_order_transfers",
+            Job::RefundLatePayment { .. } => "refund_late_payment",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobListResult {
+    pub jobs: Vec<jobs::Model>,
+    pub total: u64,
+}
+
+pub struct JobService {
+    state: AppState,
+}
+
+impl JobService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Takes the caller's connection so the job commits or rolls back with its cause.
+    pub async fn enqueue<C: ConnectionTrait>(
+        conn: &C,
+        job: Job,
+    ) -> Result<(), AppError> {
+        Self::enqueue_all(conn, vec![job]).await
+    }
+
+    pub async fn enqueue_all<C: ConnectionTrait>(
+        conn: &C,
+        jobs: Vec<Job>,
+    ) -> Result<(), AppError> {
+        if jobs.is_empty() {
+            return Ok(());
+        }
+
+        let max_attempts = Config::get().job_max_attempts;
+        let mut models = Vec::with_capacity(jobs.len());
+
+        for job in jobs {
+            let payload = serde_json::to_value(&job)
+                .map_err(|e| AppError::Internal(format!("Failed to serialize job: {}", e)))?;
+
+            models.push(jobs::ActiveModel {
+                kind: Set(job.kind().to_string()),
+                payload: Set(payload),
+                max_attempts: Set(max_attempts),
+                ..jobs::ActiveModel::new()
+            });
+        }
+
+        jobs::Entity::insert_many(models)
+            .exec(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to enqueue job: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Marks the oldest due job as running. `SKIP LOCKED` lets several workers, across
+    /// processes, poll the table without handing out the same job twice.
+    async fn claim(&self) -> Result<Option<jobs::Model>, AppError> {
+        let now = chrono::Utc::now();
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let job = jobs::Entity::find()
+            .filter(jobs::Column::Status.eq(JOB_STATUS_PENDING))
+            .filter(jobs::Column::RunAt.lte(now))
+            .order_by_asc(jobs::Column::RunAt)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch job: {}", e)))?;
+
+        let Some(job) = job else {
+            return Ok(None);
+        };
+
+        let attempts = job.attempts + 1;
+
+        let mut job: jobs::ActiveModel = job.into();
+        job.status = Set(JOB_STATUS_RUNNING.to_string());
+        job.attempts = Set(attempts);
+        job.locked_at = Set(Some(now));
+        job.updated_at = Set(now);
+
+        let job = job.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to claim job: {}", e)))?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit job claim: {}", e)))?;
+
+        Ok(Some(job))
+    }
+
+    async fn complete(
+        &self,
+        job_id: Uuid,
+    ) -> Result<(), AppError> {
+        let now = chrono::Utc::now();
+
+        jobs::Entity::update_many()
+            .col_expr(jobs::Column::Status, Expr::value(JOB_STATUS_COMPLETED))
+            .col_expr(jobs::Column::LockedAt, Expr::value(Option::<chrono::DateTime<chrono::Utc>>::None))
+            .col_expr(jobs::Column::CompletedAt, Expr::value(now))
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(jobs::Column::Id.eq(job_id))
+            .filter(jobs::Column::Status.eq(JOB_STATUS_RUNNING))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to complete job: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Schedules another attempt with exponential backoff, or parks the job as `dead`
+    /// once it has used up its attempts.
+    async fn fail(
+        &self,
+        job: &jobs::Model,
+        error: &str,
+    ) -> Result<(), AppError> {
+        let now = chrono::Utc::now();
+        let status = Self::status_after_failure(job.attempts, job.max_attempts);
+
+        jobs::Entity::update_many()
+            .col_expr(jobs::Column::Status, Expr::value(status))
+            .col_expr(jobs::Column::RunAt, Expr::value(now + Self::retry_delay(job.attempts)))
+            .col_expr(jobs::Column::LockedAt, Expr::value(Option::<chrono::DateTime<chrono::Utc>>::None))
+            .col_expr(jobs::Column::LastError, Expr::value(error))
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(jobs::Column::Id.eq(job.id))
+            .filter(jobs::Column::Status.eq(JOB_STATUS_RUNNING))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to record job failure: {}", e)))?;
+
+        if status == JOB_STATUS_DEAD {
+            MessageUtil::error(&format!("Job {} ({}) gave up after {} attempt(s)", job.id, job.kind, job.attempts));
+        }
+
+        Ok(())
+    }
+
+    fn status_after_failure(attempts: i32, max_attempts: i32) -> &'static str {
+        if attempts >= max_attempts {
+            JOB_STATUS_DEAD
+        } else {
+            JOB_STATUS_PENDING
+        }
+    }
+
+    fn retry_delay(attempts: i32) -> chrono::Duration {
+        let exponent = (attempts - 1).clamp(0, 16) as u32;
+        let delay = BASE_RETRY_DELAY_SECS.saturating_mul(2_i64.pow(exponent));
+
+        chrono::Duration::seconds(delay.min(MAX_RETRY_DELAY_SECS))
+    }
+
+    /// Runs the next due job, if any. Returns whether there was one.
+    async fn run_next(
+        &self,
+        job_runner: &JobRunner,
+    ) -> Result<bool, AppError> {
+        let Some(job) = self.claim().await? else {
+            return Ok(false);
+        };
+
+        let result = match serde_json::from_value::<Job>(job.payload.clone()) {
+            Ok(task) => job_runner.run(task).await,
+            Err(e) => Err(AppError::Internal(format!("Invalid job payload: {}", e))),
+        };
+
+        match result {
+            Ok(_) => self.complete(job.id).await?,
+            Err(e) => {
+                MessageUtil::error(&format!("Job {} ({}) failed on attempt {}: {}", job.id, job.kind, job.attempts, e));
+                self.fail(&job, &e.to_string()).await?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Hands stale jobs out again, or marks them dead when the attempt that stalled was
+    /// their last one. Returns the number of jobs requeued.
+    async fn reset_stale_jobs(&self) -> Result<u64, AppError> {
+        let now = chrono::Utc::now();
+        let cutoff = now - chrono::Duration::minutes(STALE_JOB_TIMEOUT_MINUTES);
+
+        let dead = jobs::Entity::update_many()
+            .col_expr(jobs::Column::Status, Expr::value(JOB_STATUS_DEAD))
+            .col_expr(jobs::Column::LockedAt, Expr::value(Option::<chrono::DateTime<chrono::Utc>>::None))
+            .col_expr(jobs::Column::LastError, Expr::value("Worker stopped while running the job"))
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(jobs::Column::Status.eq(JOB_STATUS_RUNNING))
+            .filter(jobs::Column::LockedAt.lt(cutoff))
+            .filter(Expr::col(jobs::Column::Attempts).gte(Expr::col(jobs::Column::MaxAttempts)))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to mark stale jobs dead: {}", e)))?;
+
+        if dead.rows_affected > 0 {
+            MessageUtil::error(&format!("{} stale job(s) gave up after their last attempt", dead.rows_affected));
+        }
+
+        let result = jobs::Entity::update_many()
+            .col_expr(jobs::Column::Status, Expr::value(JOB_STATUS_PENDING))
+            .col_expr(jobs::Column::LockedAt, Expr::value(Option::<chrono::DateTime<chrono::Utc>>::None))
+            .col_expr(jobs::Column::UpdatedAt, Expr::value(now))
+            .filter(jobs::Column::Status.eq(JOB_STATUS_RUNNING))
+            .filter(jobs::Column::LockedAt.lt(cutoff))
+            .filter(Expr::col(jobs::Column::Attempts).lt(Expr::col(jobs::Column::MaxAttempts)))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to reset stale jobs: {}", e)))?;
+
+        Ok(result.rows_affected)
+    }
+
+    async fn purge_completed_jobs(&self) -> Result<u64, AppError> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(COMPLETED_JOB_RETENTION_DAYS);
+
+        let result = jobs::Entity::delete_many()
+            .filter(jobs::Column::Status.eq(JOB_STATUS_COMPLETED))
+            .filter(jobs::Column::CompletedAt.lt(cutoff))
+            .exec(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to purge completed jobs: {}", e)))?;
+
+        Ok(result.rows_affected)
+    }
+
+    pub async fn list_jobs(
+        &self,
+        status: Option<&str>,
+        kind: Option<&str>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<JobListResult, AppError> {
+        let db = &self.state.db;
+        let mut select = jobs::Entity::find();
+
+        if let Some(status) = status {
+            select = select.filter(jobs::Column::Status.eq(status));
+        }
+
+        if let Some(kind) = kind {
+            select = select.filter(jobs::Column::Kind.eq(kind));
+        }
+
+        let total = select.clone()
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count jobs: {}", e)))?;
+
+        let jobs = select
+            .order_by_desc(jobs::Column::CreatedAt)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch jobs: {}", e)))?;
+
+        Ok(JobListResult { jobs, total })
+    }
+
+    pub async fn get_job(
+        &self,
+        job_id: Uuid,
+    ) -> Result<jobs::Model, AppError> {
+        jobs::Entity::find_by_id(job_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch job: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Job not found".to_string()))
+    }
+
+    /// Puts a dead or waiting job back at the front of the queue with a fresh set of attempts.
+    pub async fn retry_job(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        job_id: Uuid,
+    ) -> Result<jobs::Model, AppError> {
+        let job = self.get_job(job_id).await?;
+
+        if job.status != JOB_STATUS_DEAD && job.status != JOB_STATUS_PENDING {
+            return Err(AppError::Conflict("Only pending or dead jobs can be retried".to_string()));
+        }
+
+        let previous_status = job.status.clone();
+        let now = chrono::Utc::now();
+
+        let mut job: jobs::ActiveModel = job.into();
+        job.status = Set(JOB_STATUS_PENDING.to_string());
+        job.attempts = Set(0);
+        job.run_at = Set(now);
+        job.updated_at = Set(now);
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let job = job.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to retry job: {}", e)))?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "job.retried",
+            target_type: "job",
+            target_id: Some(job.id.to_string()),
+            old_values: Some(serde_json::json!({ "status": previous_status })),
+            new_values: Some(serde_json::json!({ "status": job.status })),
+            metadata: Some(serde_json::json!({ "kind": job.kind })),
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit job retry: {}", e)))?;
+
+        Ok(job)
+    }
+
+    pub fn start_workers(state: AppState) {
+        let config = Config::get();
+        let poll_interval = Duration::from_secs(config.job_poll_interval_secs);
+
+        for _ in 0..config.job_worker_count.max(1) {
+            let state = state.clone();
+
+            tokio::spawn(async move {
+                let job_service = JobService::new(state.clone());
+                let job_runner = JobRunner::new(state);
+
+                loop {
+                    match job_service.run_next(&job_runner).await {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(e) => MessageUtil::error(&format!("Job worker error: {}", e)),
+                    }
+
+                    tokio::time::sleep(poll_interval).await;
+                }
+            });
+        }
+
+        tokio::spawn(async move {
+            let job_service = JobService::new(state);
+            let mut ticker = tokio::time::interval(MAINTENANCE_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                match job_service.reset_stale_jobs().await {
+                    Ok(0) => {}
+                    Ok(count) => MessageUtil::info(&format!("Requeued {} stale job(s)", count)),
+                    Err(e) => MessageUtil::error(&e.to_string()),
+                }
+
+                match job_service.purge_completed_jobs().await {
+                    Ok(0) => {}
+                    Ok(count) => MessageUtil::info(&format!("Purged {} completed job(s)", count)),
+                    Err(e) => MessageUtil::error(&e.to_string()),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_back_off_exponentially() {
+        assert_eq!(JobService::retry_delay(1), chrono::Duration::seconds(BASE_RETRY_DELAY_SECS));
+        assert_eq!(JobService::retry_delay(2), chrono::Duration::seconds(BASE_RETRY_DELAY_SECS * 2));
+        assert_eq!(JobService::retry_delay(4), chrono::Duration::seconds(BASE_RETRY_DELAY_SECS * 8));
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(JobService::retry_delay(20), chrono::Duration::seconds(MAX_RETRY_DELAY_SECS));
+        assert_eq!(JobService::retry_delay(i32::MAX), chrono::Duration::seconds(MAX_RETRY_DELAY_SECS));
+        assert_eq!(JobService::retry_delay(0), chrono::Duration::seconds(BASE_RETRY_DELAY_SECS));
+    }
+
+    #[test]
+    fn jobs_are_dead_lettered_after_their_last_attempt() {
+        assert_eq!(JobService::status_after_failure(1, 5), JOB_STATUS_PENDING);
+        assert_eq!(JobService::status_after_failure(4, 5), JOB_STATUS_PENDING);
+        assert_eq!(JobService::status_after_failure(5, 5), JOB_STATUS_DEAD);
+    }
+
+    #[test]
+    fn payloads_are_tagged_with_their_kind() {
+        let job = Job::RefundLatePayment {
+            order_id: Uuid::nil(),
+            payment_intent_id: "pi_123".to_string(),
+        };
+
+        let payload = serde_json::to_value(&job).unwrap();
+        assert_eq!(payload["kind"], job.kind());
+
+        match serde_json::from_value::<Job>(payload).unwrap() {
+            Job::RefundLatePayment { payment_intent_id, .. } => assert_eq!(payment_intent_id, "pi_123"),
+            other => panic!("unexpected job {:?}", other),
+        }
+    }
+}
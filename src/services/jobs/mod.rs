@@ -0,0 +1,2 @@
+pub mod job_service;
+pub mod job_runner;
\ No newline at end of file
@@ -0,0 +1,710 @@
+use std::collections::HashMap;
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, Func, Query};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{games, product_variants, products, sets};
+use crate::entities::products::{string_to_product_category, ProductCategory};
+use crate::errors::AppError;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::integrations::redis_service::CacheService;
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::utils::request_util::ClientInfo;
+
+const DEFAULT_VARIANT: &str = "Normal";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ImportFormat::Csv),
+            "json" => Some(ImportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &str) -> Option<Self> {
+        path.rsplit('.').next().and_then(Self::from_name)
+    }
+}
+
+/// One card as it appears in a set list. In CSV files `variants` is a single column with
+/// names separated by `|`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CatalogueImportRow {
+    #[serde(default)]
+    pub game: Option<String>,
+    #[serde(default)]
+    pub set: Option<String>,
+    #[serde(default)]
+    pub set_number: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub rarity: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub variants: Vec<String>,
+    #[serde(default)]
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvImportRow {
+    game: Option<String>,
+    set: Option<String>,
+    set_number: Option<String>,
+    name: Option<String>,
+    rarity: Option<String>,
+    category: Option<String>,
+    variants: Option<String>,
+    image_url: Option<String>,
+}
+
+/// JSON input is either a flat list of rows or a set file, whose cards inherit the
+/// file's game and set.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum JsonImport {
+    SetFile {
+        game: String,
+        set: String,
+        cards: Vec<CatalogueImportRow>,
+    },
+    Rows(Vec<CatalogueImportRow>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Created,
+    Updated,
+    Unchanged,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowReport {
+    pub row: usize,
+    pub set_number: Option<String>,
+    pub name: Option<String>,
+    pub status: RowStatus,
+    pub product_id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub rows: Vec<RowReport>,
+}
+
+struct ValidRow {
+    game: String,
+    set: String,
+    set_number: String,
+    name: String,
+    rarity: Option<String>,
+    category: ProductCategory,
+    variants: Vec<String>,
+    image_url: Option<String>,
+}
+
+pub struct CatalogueImportService {
+    state: AppState,
+}
+
+impl CatalogueImportService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Parses a set list into rows. Rows that can't be read are kept as errors so they
+    /// still show up in the report under their own row number.
+    pub fn parse(
+        data: &[u8],
+        format: ImportFormat,
+    ) -> Result<Vec<Result<CatalogueImportRow, AppError>>, AppError> {
+        match format {
+            ImportFormat::Csv => {
+                let mut reader = csv::ReaderBuilder::new()
+                    .trim(csv::Trim::All)
+                    .from_reader(data);
+
+                Ok(reader.deserialize::<CsvImportRow>()
+                    .map(|row| row
+                        .map(|row| CatalogueImportRow {
+                            game: row.game,
+                            set: row.set,
+                            set_number: row.set_number,
+                            name: row.name,
+                            rarity: row.rarity,
+                            category: row.category,
+                            variants: row.variants
+                                .map(|variants| variants.split('|').map(|variant| variant.trim().to_string()).collect())
+                                .unwrap_or_default(),
+                            image_url: row.image_url,
+                        })
+                        .map_err(|e| AppError::validation(format!("Invalid CSV row: {}", e))))
+                    .collect())
+            }
+            ImportFormat::Json => {
+                let import: JsonImport = serde_json::from_slice(data)
+                    .map_err(|e| AppError::field("body", format!("Invalid JSON: {}", e)))?;
+
+                let rows = match import {
+                    JsonImport::SetFile { game, set, cards } => cards.into_iter()
+                        .map(|card| CatalogueImportRow {
+                            game: card.game.or_else(|| Some(game.clone())),
+                            set: card.set.or_else(|| Some(set.clone())),
+                            ..card
+                        })
+                        .collect(),
+                    JsonImport::Rows(rows) => rows,
+                };
+
+                Ok(rows.into_iter().map(Ok).collect())
+            }
+        }
+    }
+
+    /// Upserts every row keyed on (game, set, set number). With `dry_run` nothing is
+    /// written and the report shows what would have happened.
+    pub async fn import(
+        &self,
+        actor_id: Option<Uuid>,
+        client: &ClientInfo,
+        rows: Vec<Result<CatalogueImportRow, AppError>>,
+        dry_run: bool,
+    ) -> Result<ImportReport, AppError> {
+        let mut report = ImportReport {
+            dry_run,
+            total_rows: rows.len(),
+            ..ImportReport::default()
+        };
+
+        let mut seen: HashMap<(String, String, String), usize> = HashMap::new();
+        let mut known_games: HashMap<String, String> = HashMap::new();
+        let mut known_sets: HashMap<(String, String), String> = HashMap::new();
+        let mut changed_products = Vec::new();
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let row_number = index + 1;
+
+            let (set_number, name) = match &row {
+                Ok(row) => (row.set_number.clone(), row.name.clone()),
+                Err(_) => (None, None),
+            };
+
+            let result = match row.and_then(Self::validate_row) {
+                Ok(row) => {
+                    let key = (normalise(&row.game), normalise(&row.set), normalise(&row.set_number));
+
+                    match seen.get(&key) {
+                        Some(first) => Err(AppError::validation(format!("Duplicate of row {}", first))),
+                        None => {
+                            seen.insert(key, row_number);
+                            self.import_row(actor_id, client, &row, dry_run, &mut known_games, &mut known_sets).await
+                        }
+                    }
+                }
+                Err(e) => Err(e),
+            };
+
+            let (status, product_id, error) = match result {
+                Ok((status, product)) => {
+                    let product_id = product.as_ref().map(|product| product.id);
+
+                    if let Some(product) = product.filter(|_| status != RowStatus::Unchanged) {
+                        changed_products.push(product);
+                    }
+
+                    (status, product_id, None)
+                }
+                Err(e) => (RowStatus::Failed, None, Some(e.report())),
+            };
+
+            match status {
+                RowStatus::Created => report.created += 1,
+                RowStatus::Updated => report.updated += 1,
+                RowStatus::Unchanged => report.unchanged += 1,
+                RowStatus::Failed => report.failed += 1,
+            }
+
+            report.rows.push(RowReport {
+                row: row_number,
+                set_number,
+                name,
+                status,
+                product_id,
+                error,
+            });
+        }
+
+        if !dry_run {
+            self.invalidate_caches(&changed_products, known_games.values()).await;
+        }
+
+        Ok(report)
+    }
+
+    fn validate_row(row: CatalogueImportRow) -> Result<ValidRow, AppError> {
+        fn required(value: Option<String>, field: &str, max: usize) -> Result<String, AppError> {
+            let value = value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| AppError::field(field, format!("{} is required", field)))?;
+
+            if value.chars().count() > max {
+                return Err(AppError::field(field, format!("{} cannot exceed {} characters", field, max)));
+            }
+
+            Ok(value)
+        }
+
+        let category = match row.category.as_deref().map(str::trim).filter(|category| !category.is_empty()) {
+            Some(category) => string_to_product_category(category)
+                .ok_or_else(|| AppError::field("category", format!("Invalid product category '{}'", category)))?,
+            None => ProductCategory::Card,
+        };
+
+        let mut variants: Vec<String> = Vec::new();
+
+        for variant in row.variants.into_iter().map(|variant| variant.trim().to_string()) {
+            if variant.is_empty() {
+                continue;
+            }
+
+            if variant.chars().count() > 100 {
+                return Err(AppError::field("variants", "Variant names cannot exceed 100 characters"));
+            }
+
+            if !variants.iter().any(|known| known.eq_ignore_ascii_case(&variant)) {
+                variants.push(variant);
+            }
+        }
+
+        if variants.is_empty() {
+            variants.push(DEFAULT_VARIANT.to_string());
+        }
+
+        let image_url = row.image_url
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+
+        if let Some(url) = &image_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(AppError::field("image_url", format!("Invalid image URL '{}'", url)));
+            }
+        }
+
+        Ok(ValidRow {
+            game: required(row.game, "game", 100)?,
+            set: required(row.set, "set", 100)?,
+            set_number: required(row.set_number, "set_number", 50)?,
+            name: required(row.name, "name", 255)?,
+            rarity: row.rarity.map(|rarity| rarity.trim().to_string()).filter(|rarity| !rarity.is_empty()),
+            category,
+            variants,
+            image_url,
+        })
+    }
+
+    /// Writes one row and its audit event in a transaction of their own.
+    async fn import_row(
+        &self,
+        actor_id: Option<Uuid>,
+        client: &ClientInfo,
+        row: &ValidRow,
+        dry_run: bool,
+        known_games: &mut HashMap<String, String>,
+        known_sets: &mut HashMap<(String, String), String>,
+    ) -> Result<(RowStatus, Option<products::Model>), AppError> {
+        let db = &self.state.db;
+        let existing = self.find_product(row).await?;
+
+        let existing_variants = match &existing {
+            Some(product) => product_variants::Entity::find()
+                .filter(product_variants::Column::ProductId.eq(product.id))
+                .order_by_asc(product_variants::Column::SortOrder)
+                .all(db)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch product variants: {}", e)))?,
+            None => Vec::new(),
+        };
+
+        let (status, missing_variants, metadata) = Self::diff(existing.as_ref(), &existing_variants, row);
+
+        if dry_run || status == RowStatus::Unchanged {
+            return Ok((status, existing));
+        }
+
+        let txn = db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let (game, set) = self.ensure_game_and_set(row, &txn, known_games, known_sets).await?;
+
+        let now = chrono::Utc::now();
+        let previous = existing.clone();
+
+        let product = match existing {
+            Some(product) => {
+                let image_url = row.image_url.clone().or_else(|| product.image_url.clone());
+
+                let mut product: products::ActiveModel = product.into();
+                product.name = Set(row.name.clone());
+                product.category = Set(row.category.clone());
+                product.image_url = Set(image_url);
+                product.metadata = Set(Some(metadata));
+                product.updated_at = Set(now);
+
+                product.update(&txn)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to update product: {}", e)))?
+            }
+            None => products::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                name: Set(row.name.clone()),
+                description: Set(None),
+                image_url: Set(row.image_url.clone()),
+                game: Set(game.clone()),
+                set: Set(Some(set.clone())),
+                category: Set(row.category.clone()),
+                subcategory: Set(None),
+                metadata: Set(Some(metadata)),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+                .insert(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to create product: {}", e)))?,
+        };
+
+        let has_primary = existing_variants.iter().any(|variant| variant.is_primary);
+        let first_position = existing_variants.iter()
+            .map(|variant| variant.sort_order + 1)
+            .max()
+            .unwrap_or(0);
+
+        for (offset, variant) in missing_variants.into_iter().enumerate() {
+            product_variants::ActiveModel {
+                product_id: Set(product.id),
+                name: Set(variant.clone()),
+                set_number: Set(Some(row.set_number.clone())),
+                is_primary: Set(!has_primary && offset == 0),
+                sort_order: Set(first_position + offset as i32),
+                created_at: Set(now),
+                ..Default::default()
+            }
+                .insert(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to create product variant: {}", e)))?;
+        }
+
+        JobService::enqueue(&txn, Job::IndexProducts { product_ids: vec![product.id] }).await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id,
+            client,
+            action: "catalogue.imported",
+            target_type: "product",
+            target_id: Some(product.id.to_string()),
+            old_values: previous.and_then(|product| serde_json::to_value(product).ok()),
+            new_values: serde_json::to_value(&product).ok(),
+            metadata: None,
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit row: {}", e)))?;
+
+        // Only remembered once committed; a rolled back row may have been the one that
+        // created the game or set.
+        known_sets.insert((normalise(&game), normalise(&set)), set);
+        known_games.insert(normalise(&game), game);
+
+        Ok((status, Some(product)))
+    }
+
+    /// Compares a row with the product it matched, returning what the import would do,
+    /// the variants it would add and the merged metadata. An image URL left out of the row
+    /// keeps the stored one.
+    fn diff<'a>(
+        existing: Option<&products::Model>,
+        existing_variants: &[product_variants::Model],
+        row: &'a ValidRow,
+    ) -> (RowStatus, Vec<&'a String>, serde_json::Value) {
+        let missing_variants: Vec<&String> = row.variants.iter()
+            .filter(|variant| !existing_variants.iter().any(|known| known.name.eq_ignore_ascii_case(variant)))
+            .collect();
+
+        let metadata = Self::merge_metadata(existing.and_then(|product| product.metadata.clone()), row);
+
+        let status = match existing {
+            None => RowStatus::Created,
+            Some(product) if product.name == row.name
+                && product.category == row.category
+                && (row.image_url.is_none() || product.image_url == row.image_url)
+                && product.metadata.as_ref() == Some(&metadata)
+                && missing_variants.is_empty() => RowStatus::Unchanged,
+            Some(_) => RowStatus::Updated,
+        };
+
+        (status, missing_variants, metadata)
+    }
+
+    async fn find_product(
+        &self,
+        row: &ValidRow,
+    ) -> Result<Option<products::Model>, AppError> {
+        products::Entity::find()
+            .filter(Expr::expr(Func::lower(Expr::col(products::Column::Game))).eq(normalise(&row.game)))
+            .filter(Expr::expr(Func::lower(Expr::col(products::Column::Set))).eq(normalise(&row.set)))
+            .filter(
+                products::Column::Id.in_subquery(
+                    Query::select()
+                        .column(product_variants::Column::ProductId)
+                        .from(product_variants::Entity)
+                        .and_where(
+                            Expr::expr(Func::lower(Expr::col(product_variants::Column::SetNumber)))
+                                .eq(normalise(&row.set_number))
+                        )
+                        .to_owned()
+                )
+            )
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))
+    }
+
+    /// Keeps whatever else is stored in the product's metadata and overwrites only the
+    /// fields a set list provides.
+    fn merge_metadata(
+        existing: Option<serde_json::Value>,
+        row: &ValidRow,
+    ) -> serde_json::Value {
+        let mut metadata = match existing {
+            Some(serde_json::Value::Object(metadata)) => metadata,
+            _ => serde_json::Map::new(),
+        };
+
+        metadata.insert("set_number".to_string(), serde_json::Value::String(row.set_number.clone()));
+
+        if let Some(rarity) = &row.rarity {
+            metadata.insert("rarity".to_string(), serde_json::Value::String(rarity.clone()));
+        }
+
+        serde_json::Value::Object(metadata)
+    }
+
+    /// Finds or creates the row's game and set and returns their stored names, which win
+    /// over the row's spelling so one game never ends up under two capitalisations.
+    /// `known_games` and `known_sets` hold what earlier, committed rows already resolved.
+    async fn ensure_game_and_set<C: ConnectionTrait>(
+        &self,
+        row: &ValidRow,
+        conn: &C,
+        known_games: &HashMap<String, String>,
+        known_sets: &HashMap<(String, String), String>,
+    ) -> Result<(String, String), AppError> {
+        let game = match known_games.get(&normalise(&row.game)) {
+            Some(game) => game.clone(),
+            None => {
+                let existing = games::Entity::find()
+                    .filter(Expr::expr(Func::lower(Expr::col(games::Column::Name))).eq(normalise(&row.game)))
+                    .one(conn)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to fetch game: {}", e)))?;
+
+                match existing {
+                    Some(game) => game.name,
+                    None => games::ActiveModel {
+                        name: Set(row.game.clone()),
+                        ..games::ActiveModel::new()
+                    }
+                        .insert(conn)
+                        .await
+                        .map_err(|e| AppError::Internal(format!("Failed to create game: {}", e)))?
+                        .name,
+                }
+            }
+        };
+
+        let set = match known_sets.get(&(normalise(&game), normalise(&row.set))) {
+            Some(set) => set.clone(),
+            None => {
+                let existing = sets::Entity::find()
+                    .filter(sets::Column::GameName.eq(game.clone()))
+                    .filter(Expr::expr(Func::lower(Expr::col(sets::Column::Name))).eq(normalise(&row.set)))
+                    .one(conn)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to fetch set: {}", e)))?;
+
+                match existing {
+                    Some(set) => set.name,
+                    None => sets::ActiveModel {
+                        game_name: Set(game.clone()),
+                        name: Set(row.set.clone()),
+                        ..sets::ActiveModel::new()
+                    }
+                        .insert(conn)
+                        .await
+                        .map_err(|e| AppError::Internal(format!("Failed to create set: {}", e)))?
+                        .name,
+                }
+            }
+        };
+
+        Ok((game, set))
+    }
+
+    async fn invalidate_caches(
+        &self,
+        products: &[products::Model],
+        games: impl Iterator<Item = &String>,
+    ) {
+        let mut keys = vec![CacheService::games_key(), CacheService::trending_products_key()];
+        keys.extend(games.map(|game| CacheService::game_sets_key(game)));
+        keys.extend(products.iter().map(|product| CacheService::product_key(&product.id)));
+
+        CacheService::new(self.state.clone()).invalidate(&keys).await;
+    }
+}
+
+/// Games, sets and set numbers match regardless of case, both against the catalogue and
+/// between rows of the same import.
+fn normalise(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, variants: &[&str]) -> CatalogueImportRow {
+        CatalogueImportRow {
+            game: Some("Pokemon".to_string()),
+            set: Some("Base Set".to_string()),
+            set_number: Some("4/102".to_string()),
+            name: Some(name.to_string()),
+            rarity: Some("Rare Holo".to_string()),
+            variants: variants.iter().map(|variant| variant.to_string()).collect(),
+            ..CatalogueImportRow::default()
+        }
+    }
+
+    fn product(name: &str) -> products::Model {
+        products::Model {
+            id: Uuid::nil(),
+            name: name.to_string(),
+            description: None,
+            image_url: Some("https://example.com/charizard.png".to_string()),
+            game: "Pokemon".to_string(),
+            set: Some("Base Set".to_string()),
+            category: ProductCategory::Card,
+            subcategory: None,
+            metadata: Some(serde_json::json!({ "set_number": "4/102", "rarity": "Rare Holo", "artist": "Mitsuhiro Arita" })),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn variant(name: &str) -> product_variants::Model {
+        product_variants::Model {
+            id: 1,
+            product_id: Uuid::nil(),
+            name: name.to_string(),
+            set_number: Some("4/102".to_string()),
+            is_primary: true,
+            sort_order: 0,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn valid(row: CatalogueImportRow) -> ValidRow {
+        CatalogueImportService::validate_row(row).unwrap()
+    }
+
+    #[test]
+    fn reads_set_files_and_flat_csv() {
+        let json = br#"{"game": "Pokemon", "set": "Base Set", "cards": [{"set_number": "4/102", "name": "Charizard"}]}"#;
+        let rows = CatalogueImportService::parse(json, ImportFormat::Json).unwrap();
+        let card = rows[0].as_ref().unwrap();
+        assert_eq!(card.game.as_deref(), Some("Pokemon"));
+        assert_eq!(card.set.as_deref(), Some("Base Set"));
+
+        let csv = b"game,set,set_number,name,variants\nPokemon,Base Set,4/102,Charizard,Normal | Holo\n";
+        let rows = CatalogueImportService::parse(csv, ImportFormat::Csv).unwrap();
+        assert_eq!(rows[0].as_ref().unwrap().variants, vec!["Normal", "Holo"]);
+    }
+
+    #[test]
+    fn rows_need_their_key_fields() {
+        let mut missing_name = row("Charizard", &[]);
+        missing_name.name = Some("  ".to_string());
+
+        assert!(matches!(CatalogueImportService::validate_row(missing_name), Err(AppError::Validation { .. })));
+        assert_eq!(valid(row("Charizard", &[])).variants, vec![DEFAULT_VARIANT]);
+        assert_eq!(valid(row("Charizard", &["Holo", "holo"])).variants, vec!["Holo"]);
+    }
+
+    #[test]
+    fn new_cards_are_created() {
+        let row = valid(row("Charizard", &["Holo"]));
+        let (status, missing, _) = CatalogueImportService::diff(None, &[], &row);
+
+        assert_eq!(status, RowStatus::Created);
+        assert_eq!(missing, vec!["Holo"]);
+    }
+
+    #[test]
+    fn matching_cards_are_left_unchanged() {
+        let row = valid(row("Charizard", &["normal"]));
+        let existing = product("Charizard");
+        let (status, missing, _) = CatalogueImportService::diff(Some(&existing), &[variant("Normal")], &row);
+
+        assert_eq!(status, RowStatus::Unchanged);
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn renames_and_new_variants_are_updates() {
+        let existing = product("Charizard");
+
+        let renamed = valid(row("Charizard (Shadowless)", &["Normal"]));
+        let (status, _, _) = CatalogueImportService::diff(Some(&existing), &[variant("Normal")], &renamed);
+        assert_eq!(status, RowStatus::Updated);
+
+        let with_holo = valid(row("Charizard", &["Normal", "Holo"]));
+        let (status, missing, _) = CatalogueImportService::diff(Some(&existing), &[variant("Normal")], &with_holo);
+        assert_eq!(status, RowStatus::Updated);
+        assert_eq!(missing, vec!["Holo"]);
+    }
+
+    #[test]
+    fn metadata_merge_keeps_unrelated_fields() {
+        let mut changed = row("Charizard", &["Normal"]);
+        changed.rarity = Some("Rare".to_string());
+        let changed = valid(changed);
+
+        let existing = product("Charizard");
+        let (status, _, metadata) = CatalogueImportService::diff(Some(&existing), &[variant("Normal")], &changed);
+
+        assert_eq!(status, RowStatus::Updated);
+        assert_eq!(metadata["rarity"], "Rare");
+        assert_eq!(metadata["artist"], "Mitsuhiro Arita");
+    }
+}
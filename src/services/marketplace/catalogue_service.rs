@@ -0,0 +1,643 @@
+use std::collections::HashSet;
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, IntoCondition};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{games, listings, order_items, product_variants, products, sets};
+use crate::errors::AppError;
+use crate::handlers::marketplace::catalogue_handler::{GameRequest, ReorderVariantsRequest, SetRequest, UpdateVariantRequest};
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::integrations::redis_service::CacheService;
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::services::marketplace::product_service::ProductService;
+use crate::utils::request_util::ClientInfo;
+
+pub struct CatalogueService {
+    state: AppState,
+}
+
+impl CatalogueService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn create_game(
+        &self,
+        request: GameRequest,
+    ) -> Result<games::Model, AppError> {
+        let game = games::ActiveModel {
+            name: Set(request.name),
+            description: Set(request.description),
+            publisher: Set(request.publisher),
+            release_date: Set(request.release_date),
+            image_url: Set(request.image_url),
+            ..games::ActiveModel::new()
+        }
+            .insert(&self.state.db)
+            .await
+            .map_err(|e| name_taken_or_internal(e, "A game with this name already exists", "Failed to create game"))?;
+
+        self.invalidate_games(&[&game.name]).await;
+
+        Ok(game)
+    }
+
+    /// Renaming a game carries its sets (through the foreign key) and its products along,
+    /// and re-indexes those products under the new name.
+    pub async fn update_game(
+        &self,
+        id: Uuid,
+        request: GameRequest,
+    ) -> Result<games::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let existing = games::Entity::find_by_id(id)
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch game: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Game not found".to_string()))?;
+
+        let old_name = existing.name.clone();
+
+        let mut game: games::ActiveModel = existing.into();
+        game.name = Set(request.name);
+        game.description = Set(request.description);
+        game.publisher = Set(request.publisher);
+        game.release_date = Set(request.release_date);
+        game.image_url = Set(request.image_url);
+        game.updated_at = Set(chrono::Utc::now().into());
+
+        let game = game.update(&txn)
+            .await
+            .map_err(|e| name_taken_or_internal(e, "A game with this name already exists", "Failed to update game"))?;
+
+        let renamed = old_name != game.name;
+
+        if renamed {
+            products::Entity::update_many()
+                .col_expr(products::Column::Game, Expr::value(game.name.clone()))
+                .col_expr(products::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+                .filter(products::Column::Game.eq(old_name.clone()))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to move products to renamed game: {}", e)))?;
+        }
+
+        let moved_products = if renamed {
+            Self::reindex_products(products::Column::Game.eq(game.name.clone()), &txn).await?
+        } else {
+            Vec::new()
+        };
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit game: {}", e)))?;
+
+        self.invalidate_games(&[&old_name, &game.name]).await;
+        self.invalidate_products(&moved_products).await;
+
+        Ok(game)
+    }
+
+    /// Games that still have products are kept; their products have to be moved or
+    /// removed first.
+    pub async fn delete_game(
+        &self,
+        id: Uuid,
+    ) -> Result<(), AppError> {
+        let db = &self.state.db;
+
+        let game = games::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch game: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Game not found".to_string()))?;
+
+        let product_count = products::Entity::find()
+            .filter(products::Column::Game.eq(game.name.clone()))
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count products: {}", e)))?;
+
+        if product_count > 0 {
+            return Err(AppError::Conflict(format!("Game '{}' still has {} products", game.name, product_count)));
+        }
+
+        games::Entity::delete_by_id(id)
+            .exec(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to delete game: {}", e)))?;
+
+        self.invalidate_games(&[&game.name]).await;
+
+        Ok(())
+    }
+
+    pub async fn create_set(
+        &self,
+        request: SetRequest,
+    ) -> Result<sets::Model, AppError> {
+        self.require_game(&request.game_name).await?;
+
+        let set = sets::ActiveModel {
+            game_name: Set(request.game_name),
+            name: Set(request.name),
+            description: Set(request.description),
+            release_date: Set(request.release_date),
+            image_url: Set(request.image_url),
+            ..sets::ActiveModel::new()
+        }
+            .insert(&self.state.db)
+            .await
+            .map_err(|e| name_taken_or_internal(e, "This game already has a set with this name", "Failed to create set"))?;
+
+        self.invalidate_games(&[&set.game_name]).await;
+
+        Ok(set)
+    }
+
+    pub async fn update_set(
+        &self,
+        id: Uuid,
+        request: SetRequest,
+    ) -> Result<sets::Model, AppError> {
+        self.require_game(&request.game_name).await?;
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let existing = sets::Entity::find_by_id(id)
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch set: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Set not found".to_string()))?;
+
+        let (old_game, old_name) = (existing.game_name.clone(), existing.name.clone());
+
+        let mut set: sets::ActiveModel = existing.into();
+        set.game_name = Set(request.game_name);
+        set.name = Set(request.name);
+        set.description = Set(request.description);
+        set.release_date = Set(request.release_date);
+        set.image_url = Set(request.image_url);
+        set.updated_at = Set(chrono::Utc::now().into());
+
+        let set = set.update(&txn)
+            .await
+            .map_err(|e| name_taken_or_internal(e, "This game already has a set with this name", "Failed to update set"))?;
+
+        let moved = old_game != set.game_name || old_name != set.name;
+
+        if moved {
+            products::Entity::update_many()
+                .col_expr(products::Column::Game, Expr::value(set.game_name.clone()))
+                .col_expr(products::Column::Set, Expr::value(set.name.clone()))
+                .col_expr(products::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+                .filter(products::Column::Game.eq(old_game.clone()))
+                .filter(products::Column::Set.eq(old_name))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to move products to renamed set: {}", e)))?;
+        }
+
+        let moved_products = if moved {
+            Self::reindex_products(
+                Condition::all()
+                    .add(products::Column::Game.eq(set.game_name.clone()))
+                    .add(products::Column::Set.eq(set.name.clone())),
+                &txn,
+            ).await?
+        } else {
+            Vec::new()
+        };
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit set: {}", e)))?;
+
+        self.invalidate_games(&[&old_game, &set.game_name]).await;
+        self.invalidate_products(&moved_products).await;
+
+        Ok(set)
+    }
+
+    pub async fn delete_set(
+        &self,
+        id: Uuid,
+    ) -> Result<(), AppError> {
+        let db = &self.state.db;
+
+        let set = sets::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch set: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Set not found".to_string()))?;
+
+        let product_count = products::Entity::find()
+            .filter(products::Column::Game.eq(set.game_name.clone()))
+            .filter(products::Column::Set.eq(set.name.clone()))
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count products: {}", e)))?;
+
+        if product_count > 0 {
+            return Err(AppError::Conflict(format!("Set '{}' still has {} products", set.name, product_count)));
+        }
+
+        sets::Entity::delete_by_id(id)
+            .exec(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to delete set: {}", e)))?;
+
+        self.invalidate_games(&[&set.game_name]).await;
+
+        Ok(())
+    }
+
+    pub async fn update_variant(
+        &self,
+        product_id: Uuid,
+        variant_id: i32,
+        request: UpdateVariantRequest,
+    ) -> Result<product_variants::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let existing = product_variants::Entity::find_by_id(variant_id)
+            .filter(product_variants::Column::ProductId.eq(product_id))
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product variant: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Variant not found".to_string()))?;
+
+        let mut variant: product_variants::ActiveModel = existing.into();
+
+        if let Some(name) = request.name {
+            variant.name = Set(name);
+        }
+
+        if let Some(set_number) = request.set_number {
+            variant.set_number = Set(Some(set_number));
+        }
+
+        if request.is_primary == Some(true) {
+            Self::clear_primary(product_id, &txn).await?;
+            variant.is_primary = Set(true);
+        } else if request.is_primary == Some(false) {
+            variant.is_primary = Set(false);
+        }
+
+        let variant = variant.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update product variant: {}", e)))?;
+
+        Self::touch_product(product_id, &txn).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit product variant: {}", e)))?;
+
+        self.invalidate_products(&[product_id]).await;
+
+        Ok(variant)
+    }
+
+    /// `variant_ids` must list every variant of the product exactly once, in the new order.
+    pub async fn reorder_variants(
+        &self,
+        product_id: Uuid,
+        request: ReorderVariantsRequest,
+    ) -> Result<Vec<product_variants::Model>, AppError> {
+        let product_service = ProductService::new(self.state.clone());
+        let variants = product_service.get_product_variants(&product_id).await?;
+
+        Self::check_variant_order(variants.iter().map(|variant| variant.id).collect(), &request)?;
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        for (position, variant_id) in request.variant_ids.iter().enumerate() {
+            product_variants::Entity::update_many()
+                .col_expr(product_variants::Column::SortOrder, Expr::value(position as i32))
+                .filter(product_variants::Column::Id.eq(*variant_id))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to reorder product variants: {}", e)))?;
+        }
+
+        if let Some(primary_id) = request.primary_variant_id {
+            Self::clear_primary(product_id, &txn).await?;
+
+            product_variants::Entity::update_many()
+                .col_expr(product_variants::Column::IsPrimary, Expr::value(true))
+                .filter(product_variants::Column::Id.eq(primary_id))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to set primary variant: {}", e)))?;
+        }
+
+        Self::touch_product(product_id, &txn).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit variant order: {}", e)))?;
+
+        self.invalidate_products(&[product_id]).await;
+
+        product_service.get_product_variants(&product_id).await
+    }
+
+    /// Folds `duplicate_id` into `product_id`: listings and order history are re-pointed,
+    /// variants the target doesn't have yet are moved over, and the duplicate is deleted.
+    pub async fn merge_products(
+        &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        product_id: Uuid,
+        duplicate_id: Uuid,
+    ) -> Result<products::Model, AppError> {
+        if product_id == duplicate_id {
+            return Err(AppError::validation("A product cannot be merged into itself"));
+        }
+
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let product = products::Entity::find_by_id(product_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+        let duplicate = products::Entity::find_by_id(duplicate_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Duplicate product not found".to_string()))?;
+
+        let moved_listings: Vec<Uuid> = listings::Entity::find()
+            .select_only()
+            .column(listings::Column::Id)
+            .filter(listings::Column::ProductId.eq(duplicate_id))
+            .filter(listings::Column::DeletedAt.is_null())
+            .into_tuple()
+            .all(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listings: {}", e)))?;
+
+        listings::Entity::update_many()
+            .col_expr(listings::Column::ProductId, Expr::value(product_id))
+            .col_expr(listings::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(listings::Column::ProductId.eq(duplicate_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to move listings: {}", e)))?;
+
+        order_items::Entity::update_many()
+            .col_expr(order_items::Column::ProductId, Expr::value(product_id))
+            .filter(order_items::Column::ProductId.eq(duplicate_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to move order items: {}", e)))?;
+
+        let target_variants = product_variants::Entity::find()
+            .filter(product_variants::Column::ProductId.eq(product_id))
+            .all(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product variants: {}", e)))?;
+
+        let known_names: HashSet<String> = target_variants.iter()
+            .map(|variant| variant.name.to_lowercase())
+            .collect();
+        let mut next_position = target_variants.iter()
+            .map(|variant| variant.sort_order + 1)
+            .max()
+            .unwrap_or(0);
+
+        let duplicate_variants = product_variants::Entity::find()
+            .filter(product_variants::Column::ProductId.eq(duplicate_id))
+            .order_by_asc(product_variants::Column::SortOrder)
+            .all(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product variants: {}", e)))?;
+
+        for variant in duplicate_variants {
+            if known_names.contains(&variant.name.to_lowercase()) {
+                continue;
+            }
+
+            let mut variant: product_variants::ActiveModel = variant.into();
+            variant.product_id = Set(product_id);
+            variant.is_primary = Set(false);
+            variant.sort_order = Set(next_position);
+            next_position += 1;
+
+            variant.update(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to move product variant: {}", e)))?;
+        }
+
+        products::Entity::delete_by_id(duplicate_id)
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to delete duplicate product: {}", e)))?;
+
+        let moved_listing_count = moved_listings.len();
+
+        JobService::enqueue_all(&txn, vec![
+            Job::RemoveProduct { product_id: duplicate_id },
+            Job::IndexProducts { product_ids: vec![product_id] },
+            Job::IndexListings { listing_ids: moved_listings },
+        ]).await?;
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action: "product.merged",
+            target_type: "product",
+            target_id: Some(product_id.to_string()),
+            old_values: serde_json::to_value(&duplicate).ok(),
+            new_values: None,
+            metadata: Some(serde_json::json!({
+                "duplicate_id": duplicate_id,
+                "moved_listings": moved_listing_count,
+            })),
+        }).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit product merge: {}", e)))?;
+
+        self.invalidate_products(&[product_id, duplicate_id]).await;
+
+        Ok(product)
+    }
+
+    fn check_variant_order(
+        current: HashSet<i32>,
+        request: &ReorderVariantsRequest,
+    ) -> Result<(), AppError> {
+        let requested: HashSet<i32> = request.variant_ids.iter().copied().collect();
+
+        if requested.len() != request.variant_ids.len() || requested != current {
+            return Err(AppError::validation("Variant order must list every variant of the product exactly once"));
+        }
+
+        if let Some(primary_id) = request.primary_variant_id {
+            if !current.contains(&primary_id) {
+                return Err(AppError::validation("Primary variant does not belong to this product"));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn clear_primary<C: ConnectionTrait>(
+        product_id: Uuid,
+        conn: &C,
+    ) -> Result<(), AppError> {
+        product_variants::Entity::update_many()
+            .col_expr(product_variants::Column::IsPrimary, Expr::value(false))
+            .filter(product_variants::Column::ProductId.eq(product_id))
+            .exec(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to clear primary variant: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Variant changes show up on the product, so it is bumped and queued for re-indexing.
+    async fn touch_product<C: ConnectionTrait>(
+        product_id: Uuid,
+        conn: &C,
+    ) -> Result<(), AppError> {
+        let result = products::Entity::update_many()
+            .col_expr(products::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(products::Column::Id.eq(product_id))
+            .exec(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update product: {}", e)))?;
+
+        if result.rows_affected == 0 {
+            return Err(AppError::NotFound("Product not found".to_string()));
+        }
+
+        Ok(JobService::enqueue(conn, Job::IndexProducts { product_ids: vec![product_id] }).await?)
+    }
+
+    /// Queues the matching products for re-indexing and returns their ids.
+    async fn reindex_products<F: IntoCondition, C: ConnectionTrait>(
+        filter: F,
+        conn: &C,
+    ) -> Result<Vec<Uuid>, AppError> {
+        let product_ids: Vec<Uuid> = products::Entity::find()
+            .select_only()
+            .column(products::Column::Id)
+            .filter(filter)
+            .into_tuple()
+            .all(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch products: {}", e)))?;
+
+        if !product_ids.is_empty() {
+            JobService::enqueue(conn, Job::IndexProducts { product_ids: product_ids.clone() }).await?;
+        }
+
+        Ok(product_ids)
+    }
+
+    async fn invalidate_products(
+        &self,
+        product_ids: &[Uuid],
+    ) {
+        let product_service = ProductService::new(self.state.clone());
+
+        for product_id in product_ids {
+            product_service.invalidate_product(product_id).await;
+        }
+    }
+
+    async fn require_game(
+        &self,
+        game_name: &str,
+    ) -> Result<(), AppError> {
+        let exists = games::Entity::find()
+            .filter(games::Column::Name.eq(game_name))
+            .count(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch game: {}", e)))? > 0;
+
+        if !exists {
+            return Err(AppError::NotFound(format!("Game '{}' not found", game_name)));
+        }
+
+        Ok(())
+    }
+
+    async fn invalidate_games(
+        &self,
+        game_names: &[&str],
+    ) {
+        let mut keys = vec![CacheService::games_key()];
+        keys.extend(game_names.iter().map(|name| CacheService::game_sets_key(name)));
+
+        CacheService::new(self.state.clone()).invalidate(&keys).await;
+    }
+}
+
+/// Game names and (game, set) pairs are unique, so a clash is the caller's to fix.
+fn name_taken_or_internal(e: DbErr, conflict: &str, context: &str) -> AppError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => AppError::Conflict(conflict.to_string()),
+        _ => AppError::Internal(format!("{}: {}", context, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reorder(variant_ids: &[i32], primary_variant_id: Option<i32>) -> ReorderVariantsRequest {
+        ReorderVariantsRequest {
+            variant_ids: variant_ids.to_vec(),
+            primary_variant_id,
+        }
+    }
+
+    fn check(request: ReorderVariantsRequest) -> Result<(), AppError> {
+        CatalogueService::check_variant_order([1, 2, 3].into_iter().collect(), &request)
+    }
+
+    #[test]
+    fn accepts_a_full_reordering() {
+        assert!(check(reorder(&[3, 1, 2], None)).is_ok());
+        assert!(check(reorder(&[3, 1, 2], Some(1))).is_ok());
+    }
+
+    #[test]
+    fn rejects_missing_repeated_or_foreign_variants() {
+        assert!(check(reorder(&[3, 1], None)).is_err());
+        assert!(check(reorder(&[3, 1, 1, 2], None)).is_err());
+        assert!(check(reorder(&[3, 1, 4], None)).is_err());
+    }
+
+    #[test]
+    fn primary_must_be_one_of_the_product_variants() {
+        assert!(matches!(check(reorder(&[1, 2, 3], Some(9))), Err(AppError::Validation { .. })));
+    }
+
+    #[test]
+    fn only_unique_violations_are_conflicts() {
+        let error = name_taken_or_internal(DbErr::Custom("connection reset".to_string()), "Game already exists", "Failed to create game");
+
+        assert!(matches!(error, AppError::Internal(_)));
+    }
+}
@@ -1,8 +1,14 @@
+use std::time::Duration;
 use sea_orm::QueryFilter;
 use sea_orm::ColumnTrait;
 use sea_orm::EntityTrait;
 use crate::app_state::AppState;
 use crate::entities::{games, sets};
+use crate::errors::AppError;
+use crate::services::integrations::redis_service::CacheService;
+
+/// Games and sets only change through catalogue edits, so they can be cached for a while.
+const CATALOGUE_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
 
 pub struct GameService {
     pub state: AppState,
@@ -13,47 +19,73 @@ impl GameService {
         Self { state }
     }
 
-    pub async fn get_games(&self) -> Result<Vec<String>, String> {
-        games::Entity::find()
+    pub async fn get_games(&self) -> Result<Vec<String>, AppError> {
+        let cache_service = CacheService::new(self.state.clone());
+        let cache_key = CacheService::games_key();
+
+        if let Some(games) = cache_service.get::<Vec<String>>(&cache_key).await {
+            return Ok(games);
+        }
+
+        let games: Vec<String> = games::Entity::find()
             .all(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch games: {}", e))
-            .map(|games| games.into_iter().map(|game| game.name).collect())
+            .map_err(|e| AppError::Internal(format!("Failed to fetch games: {}", e)))?
+            .into_iter()
+            .map(|game| game.name)
+            .collect();
+
+        cache_service.set(&cache_key, &games, CATALOGUE_CACHE_TTL).await;
+
+        Ok(games)
     }
 
     pub async fn get_game_by_name(
         &self,
         game_name: &str,
-    ) -> Result<Option<games::Model>, String> {
+    ) -> Result<Option<games::Model>, AppError> {
         if game_name.is_empty() {
-            return Err("Game name cannot be empty".to_string());
+            return Err(AppError::validation("Game name cannot be empty"));
         }
 
         games::Entity::find()
             .filter(games::Column::Name.eq(game_name))
             .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch game '{}': {}", game_name, e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch game '{}': {}", game_name, e)))
     }
 
     pub async fn get_sets_by_game(
         &self,
         game_name: &str,
-    ) -> Result<Vec<String>, String> {
+    ) -> Result<Vec<String>, AppError> {
         if game_name.is_empty() {
-            return Err("Game name cannot be empty".to_string());
+            return Err(AppError::validation("Game name cannot be empty"));
         }
 
         let games = self.get_games().await?;
         if !games.iter().any(|g| g == game_name) {
-            return Err(format!("Game '{}' not found", game_name));
+            return Err(AppError::NotFound(format!("Game '{}' not found", game_name)));
+        }
+
+        let cache_service = CacheService::new(self.state.clone());
+        let cache_key = CacheService::game_sets_key(game_name);
+
+        if let Some(sets) = cache_service.get::<Vec<String>>(&cache_key).await {
+            return Ok(sets);
         }
 
-        sets::Entity::find()
+        let sets: Vec<String> = sets::Entity::find()
             .filter(sets::Column::GameName.eq(game_name))
             .all(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch sets for game '{}': {}", game_name, e))
-            .map(|sets| sets.into_iter().map(|set| set.name).collect())
+            .map_err(|e| AppError::Internal(format!("Failed to fetch sets for game '{}': {}", game_name, e)))?
+            .into_iter()
+            .map(|set| set.name)
+            .collect();
+
+        cache_service.set(&cache_key, &sets, CATALOGUE_CACHE_TTL).await;
+
+        Ok(sets)
     }
 }
\ No newline at end of file
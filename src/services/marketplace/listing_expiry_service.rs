@@ -0,0 +1,128 @@
+use std::time::Duration;
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::listings;
+use crate::entities::listings::ListingStatus;
+use crate::errors::AppError;
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::services::marketplace::listing_service::ListingService;
+use crate::utils::message_util::MessageUtil;
+
+pub struct ListingExpiryService {
+    pub state: AppState,
+}
+
+impl ListingExpiryService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Expires active listings past their `expires_at`. Listings with stock held by a
+    /// pending checkout are left alone until the hold is committed or released.
+    pub async fn expire_stale_listings(&self) -> Result<usize, AppError> {
+        let db = &self.state.db;
+        let now = chrono::Utc::now();
+
+        let stale_listings = listings::Entity::find()
+            .filter(listings::Column::Status.eq(ListingStatus::Active))
+            .filter(listings::Column::DeletedAt.is_null())
+            .filter(listings::Column::ExpiresAt.lt(now))
+            .filter(listings::Column::ReservedQuantity.eq(0))
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch stale listings: {}", e)))?;
+
+        let mut expired = 0;
+
+        for listing in stale_listings {
+            let txn = db.begin()
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+            let result = Self::expire_statement(listing.id, now)
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to expire listing: {}", e)))?;
+
+            if result.rows_affected == 0 {
+                continue;
+            }
+
+            JobService::enqueue(&txn, Job::RemoveListings { listing_ids: vec![listing.id] }).await?;
+
+            if let Some(stripe_product_id) = listing.stripe_product_id.clone() {
+                JobService::enqueue(&txn, Job::SetStripeProductActive {
+                    stripe_product_id,
+                    active: false,
+                }).await?;
+            }
+
+            txn.commit()
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to commit listing expiry: {}", e)))?;
+
+            expired += 1;
+
+            ListingService::new(self.state.clone())
+                .invalidate_product_listings(&listing.product_id)
+                .await;
+        }
+
+        Ok(expired)
+    }
+
+    /// Re-checks the conditions in the UPDATE so a renewal or checkout that raced the
+    /// query for stale listings wins.
+    fn expire_statement(
+        listing_id: Uuid,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> UpdateMany<listings::Entity> {
+        listings::Entity::update_many()
+            .col_expr(listings::Column::Status, Expr::value(ListingStatus::Expired))
+            .col_expr(listings::Column::UpdatedAt, Expr::value(now))
+            .filter(listings::Column::Id.eq(listing_id))
+            .filter(listings::Column::Status.eq(ListingStatus::Active))
+            .filter(listings::Column::ExpiresAt.lt(now))
+            .filter(listings::Column::ReservedQuantity.eq(0))
+    }
+
+    pub fn start_listing_expiry_job(state: AppState) {
+        let interval = Duration::from_secs(Config::get().listing_expiry_interval_secs);
+
+        tokio::spawn(async move {
+            let expiry_service = ListingExpiryService::new(state);
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                match expiry_service.expire_stale_listings().await {
+                    Ok(0) => {}
+                    Ok(count) => MessageUtil::info(&format!("Expired {} stale listing(s)", count)),
+                    Err(e) => MessageUtil::error(&format!("Failed to expire stale listings: {}", e)),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, QueryTrait};
+    use super::*;
+
+    #[test]
+    fn expiry_skips_listings_that_were_renewed_or_reserved() {
+        let sql = ListingExpiryService::expire_statement(Uuid::nil(), chrono::Utc::now())
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains("'expired'"), "{}", sql);
+        assert!(sql.contains("'active'"), "{}", sql);
+        assert!(sql.contains(r#""expires_at" <"#), "{}", sql);
+        assert!(sql.contains(r#""reserved_quantity" = 0"#), "{}", sql);
+    }
+}
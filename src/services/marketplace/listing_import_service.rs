@@ -0,0 +1,647 @@
+use std::collections::{HashMap, HashSet};
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, Func, Query};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{listings, product_variants, products};
+use crate::entities::listings::{string_to_condition, Condition, ListingStatus};
+use crate::errors::AppError;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::services::marketplace::listing_service::ListingService;
+use crate::utils::request_util::ClientInfo;
+
+const BATCH_SIZE: usize = 100;
+const MAX_IMPORT_ROWS: usize = 5_000;
+
+/// One line of a seller's inventory file. A row with a `listing_id` changes that listing;
+/// one without creates a new listing for the product given by `product_id` or by
+/// `game`, `set` and `set_number`. `action = delete` removes the listing.
+#[derive(Debug, Deserialize)]
+struct ListingImportRow {
+    listing_id: Option<String>,
+    product_id: Option<String>,
+    game: Option<String>,
+    set: Option<String>,
+    set_number: Option<String>,
+    condition: Option<String>,
+    quantity: Option<i64>,
+    price: Option<i64>,
+    description: Option<String>,
+    action: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListingExportRow<'a> {
+    listing_id: Uuid,
+    product_id: Uuid,
+    game: &'a str,
+    set: &'a str,
+    set_number: &'a str,
+    product_name: &'a str,
+    condition: &'a str,
+    quantity: i64,
+    price: i64,
+    description: &'a str,
+    status: &'a str,
+    expires_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingRowStatus {
+    Created,
+    Updated,
+    Deleted,
+    Unchanged,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListingRowReport {
+    pub row: usize,
+    pub listing_id: Option<Uuid>,
+    pub product_id: Option<Uuid>,
+    pub status: ListingRowStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ListingImportReport {
+    pub dry_run: bool,
+    pub total_rows: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub rows: Vec<ListingRowReport>,
+}
+
+enum PlannedChange {
+    Create(listings::ActiveModel),
+    Update(listings::ActiveModel),
+    Delete(listings::Model),
+    Unchanged(listings::Model),
+}
+
+struct PlannedRow {
+    row: usize,
+    listing_id: Uuid,
+    product: products::Model,
+    change: PlannedChange,
+}
+
+pub struct ListingImportService {
+    state: AppState,
+}
+
+impl ListingImportService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Checks every row first, then writes the valid ones in batches. A batch that fails
+    /// to commit marks all of its rows as failed and leaves the others untouched. Stripe
+    /// products are created and deleted afterwards by the background sync queue.
+    pub async fn import(
+        &self,
+        seller_id: Uuid,
+        client: &ClientInfo,
+        data: &[u8],
+        dry_run: bool,
+    ) -> Result<ListingImportReport, AppError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(data);
+
+        let rows: Vec<Result<ListingImportRow, AppError>> = reader.deserialize::<ListingImportRow>()
+            .map(|row| row.map_err(|e| AppError::validation(format!("Invalid CSV row: {}", e))))
+            .collect();
+
+        if rows.len() > MAX_IMPORT_ROWS {
+            return Err(AppError::validation(format!("Imports are limited to {} rows", MAX_IMPORT_ROWS)));
+        }
+
+        let mut report = ListingImportReport {
+            dry_run,
+            total_rows: rows.len(),
+            ..ListingImportReport::default()
+        };
+
+        let mut products_by_reference: HashMap<String, products::Model> = HashMap::new();
+        let mut touched_listings: HashSet<Uuid> = HashSet::new();
+        let mut planned = Vec::new();
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let row_number = index + 1;
+
+            let result = match row {
+                Ok(row) => self.plan_row(seller_id, row_number, row, &mut products_by_reference, &mut touched_listings).await,
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(plan) => planned.push(plan),
+                Err(e) => report.rows.push(ListingRowReport {
+                    row: row_number,
+                    listing_id: None,
+                    product_id: None,
+                    status: ListingRowStatus::Failed,
+                    error: Some(e.report()),
+                }),
+            }
+        }
+
+        let mut batch_plans = Vec::new();
+
+        while !planned.is_empty() {
+            let batch: Vec<PlannedRow> = planned.drain(..planned.len().min(BATCH_SIZE)).collect();
+
+            if dry_run {
+                batch_plans.extend(batch.into_iter().map(|plan| (plan, None)));
+                continue;
+            }
+
+            match self.write_batch(seller_id, client, &batch).await {
+                Ok(()) => {
+                    self.invalidate_batch(&batch).await;
+                    batch_plans.extend(batch.into_iter().map(|plan| (plan, None)));
+                }
+                Err(e) => {
+                    let error = e.report();
+                    batch_plans.extend(batch.into_iter().map(|plan| (plan, Some(error.clone()))));
+                }
+            }
+        }
+
+        for (plan, error) in batch_plans {
+            let status = match (&error, &plan.change) {
+                (Some(_), _) => ListingRowStatus::Failed,
+                (None, PlannedChange::Create(_)) => ListingRowStatus::Created,
+                (None, PlannedChange::Update(_)) => ListingRowStatus::Updated,
+                (None, PlannedChange::Delete(_)) => ListingRowStatus::Deleted,
+                (None, PlannedChange::Unchanged(_)) => ListingRowStatus::Unchanged,
+            };
+
+            report.rows.push(ListingRowReport {
+                row: plan.row,
+                listing_id: Some(plan.listing_id),
+                product_id: Some(plan.product.id),
+                status,
+                error,
+            });
+        }
+
+        report.rows.sort_by_key(|row| row.row);
+
+        for row in &report.rows {
+            match row.status {
+                ListingRowStatus::Created => report.created += 1,
+                ListingRowStatus::Updated => report.updated += 1,
+                ListingRowStatus::Deleted => report.deleted += 1,
+                ListingRowStatus::Unchanged => report.unchanged += 1,
+                ListingRowStatus::Failed => report.failed += 1,
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Writes the seller's live listings in the same layout the import reads, so an
+    /// exported file can be edited and uploaded again.
+    pub async fn export(
+        &self,
+        seller_id: Uuid,
+    ) -> Result<Vec<u8>, AppError> {
+        let listings = listings::Entity::find()
+            .filter(listings::Column::SellerId.eq(seller_id))
+            .filter(listings::Column::DeletedAt.is_null())
+            .order_by_asc(listings::Column::CreatedAt)
+            .find_also_related(products::Entity)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listings: {}", e)))?;
+
+        // Set numbers come from the variants, which is where the import looks them up.
+        let product_ids: HashSet<Uuid> = listings.iter().map(|(listing, _)| listing.product_id).collect();
+        let variants = product_variants::Entity::find()
+            .filter(product_variants::Column::ProductId.is_in(product_ids))
+            .filter(product_variants::Column::SetNumber.is_not_null())
+            .order_by_desc(product_variants::Column::IsPrimary)
+            .order_by_asc(product_variants::Column::SortOrder)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product variants: {}", e)))?;
+
+        let mut set_numbers: HashMap<Uuid, String> = HashMap::new();
+        for variant in variants {
+            if let Some(set_number) = variant.set_number {
+                set_numbers.entry(variant.product_id).or_insert(set_number);
+            }
+        }
+
+        let mut writer = csv::Writer::from_writer(Vec::new());
+
+        for (listing, product) in &listings {
+            let set_number = set_numbers.get(&listing.product_id).map_or("", |set_number| set_number.as_str());
+
+            writer.serialize(ListingExportRow {
+                listing_id: listing.id,
+                product_id: listing.product_id,
+                game: product.as_ref().map_or("", |product| product.game.as_str()),
+                set: product.as_ref().and_then(|product| product.set.as_deref()).unwrap_or(""),
+                set_number,
+                product_name: product.as_ref().map_or("", |product| product.name.as_str()),
+                condition: listing.condition.as_str(),
+                quantity: listing.quantity,
+                price: listing.price,
+                description: listing.description.as_deref().unwrap_or(""),
+                status: listing.status.as_str(),
+                expires_at: listing.expires_at.to_rfc3339(),
+            }).map_err(|e| AppError::Internal(format!("Failed to write CSV row: {}", e)))?;
+        }
+
+        writer.into_inner()
+            .map_err(|e| AppError::Internal(format!("Failed to write CSV: {}", e)))
+    }
+
+    async fn plan_row(
+        &self,
+        seller_id: Uuid,
+        row_number: usize,
+        row: ListingImportRow,
+        products_by_reference: &mut HashMap<String, products::Model>,
+        touched_listings: &mut HashSet<Uuid>,
+    ) -> Result<PlannedRow, AppError> {
+        let action = row.action.as_deref().map(str::to_lowercase).filter(|action| !action.is_empty());
+        let listing_id = Self::non_empty(row.listing_id.clone())
+            .map(|id| Uuid::parse_str(&id).map_err(|_| AppError::validation(format!("Invalid listing_id '{}'", id))))
+            .transpose()?;
+
+        let condition = Self::non_empty(row.condition.clone())
+            .map(|condition| string_to_condition(&condition).ok_or_else(|| AppError::validation(format!("Invalid condition '{}'", condition))))
+            .transpose()?;
+
+        if let Some(price) = row.price {
+            if price <= 0 {
+                return Err(AppError::validation("price must be a positive amount in cents"));
+            }
+        }
+
+        let description = Self::non_empty(row.description.clone());
+
+        if description.as_ref().map_or(false, |description| description.chars().count() > 2000) {
+            return Err(AppError::validation("description cannot exceed 2000 characters"));
+        }
+
+        let Some(listing_id) = listing_id else {
+            if action.as_deref().map_or(false, |action| action != "create") {
+                return Err(AppError::validation("listing_id is required to update or delete a listing"));
+            }
+
+            let product = self.find_product(&row, products_by_reference).await?;
+            let condition = condition.ok_or_else(|| AppError::validation("condition is required"))?;
+            let quantity = row.quantity.ok_or_else(|| AppError::validation("quantity is required"))?;
+            let price = row.price.ok_or_else(|| AppError::validation("price is required"))?;
+
+            if quantity <= 0 {
+                return Err(AppError::validation("quantity must be at least 1"));
+            }
+
+            Self::check_condition(&condition, &product)?;
+
+            let id = Uuid::new_v4();
+            let now = chrono::Utc::now();
+
+            let product_id = product.id;
+
+            return Ok(PlannedRow {
+                row: row_number,
+                listing_id: id,
+                product,
+                change: PlannedChange::Create(listings::ActiveModel {
+                    id: Set(id),
+                    product_id: Set(product_id),
+                    seller_id: Set(seller_id),
+                    price: Set(price),
+                    condition: Set(condition),
+                    quantity: Set(quantity),
+                    reserved_quantity: Set(0),
+                    status: Set(ListingStatus::Active),
+                    stripe_product_id: Set(None),
+                    previous_stripe_product_id: Set(None),
+                    image_url: Set(None),
+                    description: Set(description),
+                    expires_at: Set(ListingService::listing_expiry()),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    deleted_at: Set(None),
+                }),
+            });
+        };
+
+        if !touched_listings.insert(listing_id) {
+            return Err(AppError::validation("The same listing appears more than once"));
+        }
+
+        let listing = listings::Entity::find_by_id(listing_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .filter(|listing| listing.seller_id == seller_id && listing.deleted_at.is_none())
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        let product = products::Entity::find_by_id(listing.product_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+        match action.as_deref() {
+            Some("delete") => {
+                if listing.reserved_quantity > 0 {
+                    return Err(AppError::Conflict("Listing has stock held by a pending checkout".to_string()));
+                }
+
+                return Ok(PlannedRow {
+                    row: row_number,
+                    listing_id,
+                    product,
+                    change: PlannedChange::Delete(listing),
+                });
+            }
+            Some("update") | None => {}
+            Some(action) => return Err(AppError::validation(format!("Invalid action '{}'", action))),
+        }
+
+        if let Some(quantity) = row.quantity {
+            if quantity <= 0 {
+                return Err(AppError::validation("quantity must be at least 1, use action=delete to remove a listing"));
+            }
+
+            if quantity < listing.reserved_quantity {
+                return Err(AppError::Conflict(format!("quantity cannot be below the {} units held by pending checkouts", listing.reserved_quantity)));
+            }
+        }
+
+        if let Some(condition) = &condition {
+            Self::check_condition(condition, &product)?;
+        }
+
+        let unchanged = row.price.map_or(true, |price| price == listing.price)
+            && condition.as_ref().map_or(true, |condition| *condition == listing.condition)
+            && row.quantity.map_or(true, |quantity| quantity == listing.quantity)
+            && description.as_ref().map_or(true, |description| Some(description) == listing.description.as_ref());
+
+        if unchanged {
+            return Ok(PlannedRow {
+                row: row_number,
+                listing_id,
+                product,
+                change: PlannedChange::Unchanged(listing),
+            });
+        }
+
+        let mut update: listings::ActiveModel = listing.into();
+
+        if let Some(price) = row.price {
+            update.price = Set(price);
+        }
+
+        if let Some(condition) = condition {
+            update.condition = Set(condition);
+        }
+
+        if let Some(quantity) = row.quantity {
+            update.quantity = Set(quantity);
+        }
+
+        if let Some(description) = description {
+            update.description = Set(Some(description));
+        }
+
+        update.updated_at = Set(chrono::Utc::now());
+
+        Ok(PlannedRow {
+            row: row_number,
+            listing_id,
+            product,
+            change: PlannedChange::Update(update),
+        })
+    }
+
+    /// Writes a batch together with the search and Stripe jobs it causes and the audit
+    /// event recording it.
+    async fn write_batch(
+        &self,
+        seller_id: Uuid,
+        client: &ClientInfo,
+        batch: &[PlannedRow],
+    ) -> Result<(), AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let mut jobs = Vec::new();
+        let mut indexed = Vec::new();
+        let mut removed = Vec::new();
+        let mut created = Vec::new();
+
+        for plan in batch {
+            match &plan.change {
+                PlannedChange::Create(listing) => {
+                    listing.clone()
+                        .insert(&txn)
+                        .await
+                        .map_err(|e| AppError::Internal(format!("Failed to create listing: {}", e)))?;
+
+                    jobs.push(Job::CreateStripeProduct { listing_id: plan.listing_id });
+                    indexed.push(plan.listing_id);
+                    created.push(plan.listing_id);
+                }
+                PlannedChange::Update(listing) => {
+                    listing.clone()
+                        .update(&txn)
+                        .await
+                        .map_err(|e| AppError::Internal(format!("Failed to update listing: {}", e)))?;
+
+                    indexed.push(plan.listing_id);
+                }
+                PlannedChange::Delete(listing) => {
+                    if let Some(stripe_product_id) = listing.stripe_product_id.clone() {
+                        jobs.push(Job::DeleteStripeProduct { stripe_product_id });
+                    }
+
+                    removed.push(listing.id);
+
+                    let mut listing: listings::ActiveModel = listing.clone().into();
+                    listing.deleted_at = Set(Some(chrono::Utc::now()));
+
+                    listing.update(&txn)
+                        .await
+                        .map_err(|e| AppError::Internal(format!("Failed to delete listing: {}", e)))?;
+                }
+                PlannedChange::Unchanged(_) => {}
+            }
+        }
+
+        let updated: Vec<Uuid> = indexed.iter()
+            .filter(|listing_id| !created.contains(listing_id))
+            .copied()
+            .collect();
+
+        AuditService::record_on(&txn, AuditEvent {
+            actor_id: Some(seller_id),
+            client,
+            action: "listing.imported",
+            target_type: "listing",
+            target_id: None,
+            old_values: None,
+            new_values: None,
+            metadata: Some(serde_json::json!({
+                "rows": batch.iter().map(|plan| plan.row).collect::<Vec<_>>(),
+                "created": created,
+                "updated": updated,
+                "deleted": removed,
+            })),
+        }).await?;
+
+        if !indexed.is_empty() {
+            jobs.push(Job::IndexListings { listing_ids: indexed });
+        }
+
+        if !removed.is_empty() {
+            jobs.push(Job::RemoveListings { listing_ids: removed });
+        }
+
+        JobService::enqueue_all(&txn, jobs).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit batch: {}", e)))
+    }
+
+    async fn invalidate_batch(
+        &self,
+        batch: &[PlannedRow],
+    ) {
+        let product_ids: HashSet<Uuid> = batch.iter()
+            .filter(|plan| !matches!(plan.change, PlannedChange::Unchanged(_)))
+            .map(|plan| plan.product.id)
+            .collect();
+
+        let listing_service = ListingService::new(self.state.clone());
+
+        for product_id in product_ids {
+            listing_service.invalidate_product_listings(&product_id).await;
+        }
+    }
+
+    async fn find_product(
+        &self,
+        row: &ListingImportRow,
+        products_by_reference: &mut HashMap<String, products::Model>,
+    ) -> Result<products::Model, AppError> {
+        if let Some(product_id) = Self::non_empty(row.product_id.clone()) {
+            let product_id = Uuid::parse_str(&product_id)
+                .map_err(|_| AppError::validation(format!("Invalid product_id '{}'", product_id)))?;
+
+            if let Some(product) = products_by_reference.get(&product_id.to_string()) {
+                return Ok(product.clone());
+            }
+
+            let product = products::Entity::find_by_id(product_id)
+                .one(&self.state.db)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+                .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+            products_by_reference.insert(product_id.to_string(), product.clone());
+            return Ok(product);
+        }
+
+        let (game, set, set_number) = match (
+            Self::non_empty(row.game.clone()),
+            Self::non_empty(row.set.clone()),
+            Self::non_empty(row.set_number.clone()),
+        ) {
+            (Some(game), Some(set), Some(set_number)) => (game, set, set_number),
+            _ => return Err(AppError::validation("Either product_id or game, set and set_number are required")),
+        };
+
+        let (game_key, set_key, set_number_key) = (game.to_lowercase(), set.to_lowercase(), set_number.to_lowercase());
+        let reference = format!("{}\u{1f}{}\u{1f}{}", game_key, set_key, set_number_key);
+
+        if let Some(product) = products_by_reference.get(&reference) {
+            return Ok(product.clone());
+        }
+
+        let product = Self::product_by_reference(&game_key, &set_key, &set_number_key)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound(format!("No product {} in {} / {}", set_number, game, set)))?;
+
+        products_by_reference.insert(reference, product.clone());
+        Ok(product)
+    }
+
+    /// Matches game, set and set number ignoring case, the same way the cache key does,
+    /// so the lookup doesn't depend on how the first row happened to spell them. Expects
+    /// lowercased input.
+    fn product_by_reference(
+        game: &str,
+        set: &str,
+        set_number: &str,
+    ) -> Select<products::Entity> {
+        products::Entity::find()
+            .filter(Expr::expr(Func::lower(Expr::col(products::Column::Game))).eq(game))
+            .filter(Expr::expr(Func::lower(Expr::col(products::Column::Set))).eq(set))
+            .filter(
+                products::Column::Id.in_subquery(
+                    Query::select()
+                        .column(product_variants::Column::ProductId)
+                        .from(product_variants::Entity)
+                        .and_where(
+                            Expr::expr(Func::lower(Expr::col(product_variants::Column::SetNumber)))
+                                .eq(set_number)
+                        )
+                        .to_owned()
+                )
+            )
+    }
+
+    fn check_condition(
+        condition: &Condition,
+        product: &products::Model,
+    ) -> Result<(), AppError> {
+        if !condition.valid_for_category(&product.category) {
+            return Err(AppError::validation(format!("Condition '{}' does not apply to this product", condition.as_str())));
+        }
+
+        Ok(())
+    }
+
+    fn non_empty(value: Option<String>) -> Option<String> {
+        value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, QueryTrait};
+    use super::*;
+
+    #[test]
+    fn product_lookup_ignores_case() {
+        let sql = ListingImportService::product_by_reference("pokemon", "base set", "4/102")
+            .build(DbBackend::Postgres)
+            .to_string();
+
+        assert!(sql.contains(r#"LOWER("game") = 'pokemon'"#), "{}", sql);
+        assert!(sql.contains(r#"LOWER("set") = 'base set'"#), "{}", sql);
+        assert!(sql.contains(r#"LOWER("set_number") = '4/102'"#), "{}", sql);
+    }
+}
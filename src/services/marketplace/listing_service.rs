@@ -1,12 +1,72 @@
-use sea_orm::{ActiveModelTrait, EntityTrait, Set, QueryFilter, ColumnTrait};
+use std::time::Duration;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sea_orm::{ActiveModelTrait, EntityTrait, Set, QueryFilter, ColumnTrait, TransactionTrait, JoinType, Order, QueryOrder, QuerySelect, RelationTrait, ConnectionTrait, UpdateMany};
+use sea_orm::sea_query::Expr;
+use serde::Serialize;
 use uuid::Uuid;
 use crate::app_state::AppState;
-use crate::entities::listings;
-use crate::entities::listings::{string_to_condition, ListingStatus};
-use crate::handlers::marketplace::listing_handler::{CreateListingRequest, UpdateListingRequest};
+use crate::config::config::Config;
+use crate::entities::{listings, products};
+use crate::entities::listings::{string_to_condition, string_to_status, ListingStatus};
+use crate::errors::AppError;
+use crate::handlers::marketplace::listing_handler::{CreateListingRequest, ListingBrowseQuery, UpdateListingRequest};
 use crate::services::account::user_service::UserService;
-use crate::services::integrations::stripe_service::StripeService;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::integrations::redis_service::CacheService;
+use crate::services::jobs::job_service::{Job, JobService};
 use crate::services::marketplace::product_service::ProductService;
+use crate::utils::request_util::ClientInfo;
+
+/// Kept short because checkouts move stock without going through this service.
+const PRODUCT_LISTINGS_CACHE_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingSort {
+    Newest,
+    Oldest,
+    PriceAsc,
+    PriceDesc,
+}
+
+impl ListingSort {
+    pub fn parse(sort: &str) -> Option<Self> {
+        match sort.to_lowercase().as_str() {
+            "newest" => Some(ListingSort::Newest),
+            "oldest" => Some(ListingSort::Oldest),
+            "price_asc" => Some(ListingSort::PriceAsc),
+            "price_desc" => Some(ListingSort::PriceDesc),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingSort::Newest => "newest",
+            ListingSort::Oldest => "oldest",
+            ListingSort::PriceAsc => "price_asc",
+            ListingSort::PriceDesc => "price_desc",
+        }
+    }
+
+    fn ascending(&self) -> bool {
+        matches!(self, ListingSort::Oldest | ListingSort::PriceAsc)
+    }
+
+    /// The sort column's value for a listing, as stored in a cursor.
+    fn key(&self, listing: &listings::Model) -> i64 {
+        match self {
+            ListingSort::Newest | ListingSort::Oldest => listing.created_at.timestamp_micros(),
+            ListingSort::PriceAsc | ListingSort::PriceDesc => listing.price,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListingPage {
+    pub listings: Vec<listings::Model>,
+    pub next_cursor: Option<String>,
+}
 
 pub struct ListingService {
     pub state: AppState,
@@ -20,32 +80,27 @@ impl ListingService {
     pub async fn create_listing(
         &self,
         user_id: Uuid,
+        client: &ClientInfo,
         request: CreateListingRequest,
-    ) -> Result<listings::Model, String> {
+    ) -> Result<listings::Model, AppError> {
         let condition = string_to_condition(
             &request.condition
-        ).ok_or_else(|| "condition not defined".to_string())?;
+        ).ok_or_else(|| AppError::validation("condition not defined"))?;
+
+        Self::check_price(request.price)?;
+        Self::check_quantity(request.quantity)?;
 
         let user_service = UserService::new(self.state.clone());
 
         user_service.get_user_by_id(&user_id)
-            .await
-            .map_err(|e| format!("Failed to fetch user: {}", e))?
-            .ok_or_else(|| "User not found".to_string())?;
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
         let product_service = ProductService::new(self.state.clone());
         
-        let product = product_service.get_product_by_id(&request.product_id)
-            .await
-            .map_err(|e| format!("Failed to fetch product: {}", e))?
-            .ok_or_else(|| "Product not found".to_string())?;
-        
-        let stripe_product = StripeService::create_stripe_product(
-            &product.name,
-            request.description.as_deref(),
-            request.price
-        ).await
-        .map_err(|e| format!("Failed to create Stripe product: {}", e))?;
+        product_service.get_product_by_id(&request.product_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
         
         let listing = listings::Model {
             id: Uuid::new_v4(),
@@ -56,21 +111,98 @@ impl ListingService {
             quantity: request.quantity,
             reserved_quantity: 0,
             status: ListingStatus::Active,
-            stripe_product_id: stripe_product.id,
+            stripe_product_id: None,
             previous_stripe_product_id: None,
             image_url: request.image_url,
             description: request.description,
+            expires_at: Self::listing_expiry(),
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
             deleted_at: None,
         };
 
-        let db = &self.state.db;
-        
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
         listings::ActiveModel::from(listing.clone())
-            .insert(db)
+            .insert(&txn)
             .await
-            .map_err(|e| format!("Failed to create listing: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to create listing: {}", e)))?;
+
+        JobService::enqueue_all(&txn, vec![
+            Job::CreateStripeProduct { listing_id: listing.id },
+            Job::IndexListings { listing_ids: vec![listing.id] },
+        ]).await?;
+
+        self.audit(&txn, client, "listing.created", None, Some(&listing)).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit listing: {}", e)))?;
+
+        self.invalidate_product_listings(&listing.product_id).await;
+
+        Ok(listing)
+    }
+
+    /// Pushes a listing's expiry out by another lifetime. Expired listings go back on
+    /// sale, which means reactivating their Stripe product and re-indexing them.
+    pub async fn renew_listing(
+        &self,
+        user_id: Uuid,
+        client: &ClientInfo,
+        id: Uuid,
+    ) -> Result<listings::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let listing = listings::Entity::find_by_id(id)
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        if listing.seller_id != user_id || listing.deleted_at.is_some() {
+            return Err(AppError::NotFound("Listing not found".to_string()));
+        }
+
+        if !listing.is_active() && !listing.is_expired() {
+            return Err(AppError::validation("Only active or expired listings can be renewed"));
+        }
+
+        let was_expired = listing.is_expired();
+        let stripe_product_id = listing.stripe_product_id.clone();
+        let previous = listing.clone();
+
+        let mut listing: listings::ActiveModel = listing.into();
+        listing.status = Set(ListingStatus::Active);
+        listing.expires_at = Set(Self::listing_expiry());
+        listing.updated_at = Set(chrono::Utc::now());
+
+        let listing = listing.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to renew listing: {}", e)))?;
+
+        if was_expired {
+            if let Some(stripe_product_id) = stripe_product_id {
+                JobService::enqueue(&txn, Job::SetStripeProductActive {
+                    stripe_product_id,
+                    active: true,
+                }).await?;
+            }
+
+            JobService::enqueue(&txn, Job::IndexListings { listing_ids: vec![listing.id] }).await?;
+        }
+
+        self.audit(&txn, client, "listing.renewed", Some(&previous), Some(&listing)).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit listing renewal: {}", e)))?;
+
+        self.invalidate_product_listings(&listing.product_id).await;
 
         Ok(listing)
     }
@@ -78,55 +210,81 @@ impl ListingService {
     pub async fn update_listing(
         &self,
         user_id: Uuid,
+        client: &ClientInfo,
         request: UpdateListingRequest,
-    ) -> Result<listings::Model, String> {
-        let db = &self.state.db;
+    ) -> Result<listings::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
 
         let listing = listings::Entity::find_by_id(request.id)
-            .one(db)
+            .one(&txn)
             .await
-            .map_err(|e| format!("Failed to fetch listing: {}", e))?
-            .ok_or("Listing not found".to_string())?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
 
         if listing.seller_id != user_id {
-            return Err("You are not the seller of this listing".to_string());
+            return Err(AppError::Forbidden("You are not the seller of this listing".to_string()));
         }
 
-        let mut listing: listings::ActiveModel = listing.into();
+        if listing.deleted_at.is_some() {
+            return Err(AppError::NotFound("Listing not found".to_string()));
+        }
 
         if let Some(price) = request.price {
-            listing.price = Set(price);
+            Self::check_price(price)?;
         }
 
-        if let Some(condition) = request.condition {
-            let card_condition = string_to_condition(&condition)
-                .ok_or_else(|| "Invalid condition".to_string())?;
-            listing.condition = Set(card_condition);
-        }
+        let condition = request.condition.as_deref()
+            .map(|condition| string_to_condition(condition).ok_or_else(|| AppError::validation("Invalid condition")))
+            .transpose()?;
 
         if let Some(quantity) = request.quantity {
-            listing.quantity = Set(quantity);
-        }
+            Self::check_quantity(quantity)?;
 
-        if let Some(image_url) = request.image_url {
-            listing.image_url = Set(Some(image_url));
+            if quantity < listing.reserved_quantity {
+                return Err(AppError::Conflict(format!(
+                    "quantity cannot be below the {} units held by pending checkouts",
+                    listing.reserved_quantity
+                )));
+            }
         }
 
-        if let Some(description) = request.description {
-            listing.description = Set(Some(description));
+        let previous = listing;
+
+        let result = Self::update_statement(&request, condition, chrono::Utc::now())
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update listing: {}", e)))?;
+
+        // A checkout or a delete got in between the read above and the update.
+        if result.rows_affected == 0 {
+            return Err(AppError::Conflict("The listing changed while it was being updated, please try again".to_string()));
         }
 
-        listing.updated_at = Set(chrono::Utc::now());
+        let listing = listings::Entity::find_by_id(previous.id)
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        JobService::enqueue(&txn, Job::IndexListings { listing_ids: vec![listing.id] }).await?;
 
-        listing.update(db)
+        self.audit(&txn, client, "listing.updated", Some(&previous), Some(&listing)).await?;
+
+        txn.commit()
             .await
-            .map_err(|e| format!("Failed to update listing: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to commit listing update: {}", e)))?;
+
+        self.invalidate_product_listings(&listing.product_id).await;
+
+        Ok(listing)
     }
 
     pub async fn get_listing(
         &self,
         id: Uuid
-    ) -> Result<listings::Model, String> {
+    ) -> Result<listings::Model, AppError> {
         println!("Fetching listing with ID: {}", id);
         
         let db = &self.state.db;
@@ -134,43 +292,55 @@ impl ListingService {
         listings::Entity::find_by_id(id)
             .one(db)
             .await
-            .map_err(|e| format!("Failed to fetch listing: {}", e))?
-            .ok_or("Listing not found".to_string())
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))
     }
 
     pub async fn delete_listing(
         &self,
         user_id: Uuid,
+        client: &ClientInfo,
         id: Uuid
-    ) -> Result<bool, String> {
-        let db = &self.state.db;
+    ) -> Result<bool, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
 
         let listing = listings::Entity::find_by_id(id)
-            .one(db)
+            .one(&txn)
             .await
-            .map_err(|e| format!("Failed to fetch listing: {}", e))?
-            .ok_or("Listing not found".to_string())?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
         
         if listing.seller_id != user_id {
-            return Err("You are not the seller of this listing".to_string());
+            return Err(AppError::Forbidden("You are not the seller of this listing".to_string()));
         }
         
         if listing.deleted_at.is_some() {
             return Ok(false);
         }
 
-        let stripe_service = StripeService::new(self.state.clone());
+        if let Some(stripe_product_id) = listing.stripe_product_id.clone() {
+            JobService::enqueue(&txn, Job::DeleteStripeProduct { stripe_product_id }).await?;
+        }
 
-        stripe_service.delete_stripe_product(&listing.stripe_product_id)
-            .await
-            .map_err(|e| format!("Failed to delete StripeProduct: {}", e))?;
+        JobService::enqueue(&txn, Job::RemoveListings { listing_ids: vec![listing.id] }).await?;
 
+        let previous = listing.clone();
         let mut listing: listings::ActiveModel = listing.into();
         listing.deleted_at = Set(Some(chrono::Utc::now()));
 
-        let deleted = listing.update(db)
+        let deleted = listing.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to delete listing: {}", e)))?;
+
+        self.audit(&txn, client, "listing.deleted", Some(&previous), Some(&deleted)).await?;
+
+        txn.commit()
             .await
-            .map_err(|e| format!("Failed to delete listing: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to commit listing deletion: {}", e)))?;
+
+        self.invalidate_product_listings(&deleted.product_id).await;
 
         Ok(deleted.deleted_at.is_some())
     }
@@ -178,13 +348,404 @@ impl ListingService {
     pub async fn get_listings_by_product_id(
         &self,
         product_id: Uuid
-    ) -> Result<Vec<listings::Model>, String> {
+    ) -> Result<Vec<listings::Model>, AppError> {
         let db = &self.state.db;
+        let cache_service = CacheService::new(self.state.clone());
+        let cache_key = CacheService::product_listings_key(&product_id);
 
-        listings::Entity::find()
+        if let Some(listings) = cache_service.get::<Vec<listings::Model>>(&cache_key).await {
+            return Ok(listings);
+        }
+
+        let listings = listings::Entity::find()
             .filter(listings::Column::ProductId.eq(product_id))
+            .filter(listings::Column::Status.eq(ListingStatus::Active))
+            .filter(listings::Column::DeletedAt.is_null())
             .all(db)
             .await
-            .map_err(|e| format!("Failed to fetch listings: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listings: {}", e)))?;
+
+        cache_service.set(&cache_key, &listings, PRODUCT_LISTINGS_CACHE_TTL).await;
+
+        Ok(listings)
+    }
+
+    /// Public listing search. Only active listings are returned unless `status` asks for
+    /// others, and deleted listings never are. Pages are keyed on the sort column plus
+    /// the listing id, so new listings don't shift later pages the way an offset would.
+    pub async fn browse_listings(
+        &self,
+        query: &ListingBrowseQuery,
+        limit: u64,
+    ) -> Result<ListingPage, AppError> {
+        let sort = match query.sort.as_deref().filter(|sort| !sort.is_empty()) {
+            Some(sort) => ListingSort::parse(sort).ok_or_else(|| {
+                AppError::field("sort", "sort must be one of newest, oldest, price_asc or price_desc")
+            })?,
+            None => ListingSort::Newest,
+        };
+
+        let statuses = match query.status.as_deref().filter(|status| !status.is_empty()) {
+            Some(status) => status.split(',')
+                .map(|status| string_to_status(status.trim())
+                    .ok_or_else(|| AppError::field("status", format!("Invalid status '{}'", status.trim()))))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![ListingStatus::Active],
+        };
+
+        let mut select = listings::Entity::find()
+            .filter(listings::Column::DeletedAt.is_null())
+            .filter(listings::Column::Status.is_in(statuses));
+
+        if let Some(condition) = query.condition.as_deref().filter(|condition| !condition.is_empty()) {
+            let conditions = condition.split(',')
+                .map(|condition| string_to_condition(condition.trim())
+                    .ok_or_else(|| AppError::field("condition", format!("Invalid condition '{}'", condition.trim()))))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            select = select.filter(listings::Column::Condition.is_in(conditions));
+        }
+
+        if let (Some(min_price), Some(max_price)) = (query.min_price, query.max_price) {
+            if min_price > max_price {
+                return Err(AppError::field("min_price", "min_price cannot be greater than max_price"));
+            }
+        }
+
+        if let Some(min_price) = query.min_price {
+            select = select.filter(listings::Column::Price.gte(min_price));
+        }
+
+        if let Some(max_price) = query.max_price {
+            select = select.filter(listings::Column::Price.lte(max_price));
+        }
+
+        if let Some(seller_id) = query.seller_id {
+            select = select.filter(listings::Column::SellerId.eq(seller_id));
+        }
+
+        if let Some(product_id) = query.product_id {
+            select = select.filter(listings::Column::ProductId.eq(product_id));
+        }
+
+        let game = query.game.as_deref().filter(|game| !game.is_empty());
+        let set = query.set.as_deref().filter(|set| !set.is_empty());
+
+        if game.is_some() || set.is_some() {
+            select = select.join(JoinType::InnerJoin, listings::Relation::Product.def());
+
+            if let Some(game) = game {
+                select = select.filter(products::Column::Game.eq(game));
+            }
+
+            if let Some(set) = set {
+                select = select.filter(products::Column::Set.eq(set));
+            }
+        }
+
+        if let Some(cursor) = query.cursor.as_deref().filter(|cursor| !cursor.is_empty()) {
+            select = select.filter(Self::after_cursor(sort, cursor)?);
+        }
+
+        let column = match sort {
+            ListingSort::Newest | ListingSort::Oldest => listings::Column::CreatedAt,
+            ListingSort::PriceAsc | ListingSort::PriceDesc => listings::Column::Price,
+        };
+        let order = if sort.ascending() { Order::Asc } else { Order::Desc };
+
+        // One extra row tells us whether there is another page.
+        let mut listings = select
+            .order_by(column, order.clone())
+            .order_by(listings::Column::Id, order)
+            .limit(limit + 1)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listings: {}", e)))?;
+
+        let next_cursor = if listings.len() as u64 > limit {
+            listings.truncate(limit as usize);
+            listings.last().map(|listing| Self::encode_cursor(sort, listing))
+        } else {
+            None
+        };
+
+        Ok(ListingPage { listings, next_cursor })
+    }
+
+    fn encode_cursor(
+        sort: ListingSort,
+        listing: &listings::Model,
+    ) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}:{}", sort.as_str(), sort.key(listing), listing.id))
+    }
+
+    /// Rows strictly after the cursor in `sort` order. A cursor only makes sense for the
+    /// sort it was issued with, so one from a different sort is rejected.
+    fn after_cursor(
+        sort: ListingSort,
+        cursor: &str,
+    ) -> Result<sea_orm::Condition, AppError> {
+        let invalid = || AppError::field("cursor", "Invalid cursor");
+
+        let decoded = URL_SAFE_NO_PAD.decode(cursor)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+
+        let mut parts = decoded.splitn(3, ':');
+        let (Some(cursor_sort), Some(key), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+
+        if ListingSort::parse(cursor_sort) != Some(sort) {
+            return Err(AppError::field("cursor", "Cursor was issued for a different sort"));
+        }
+
+        let key: i64 = key.parse().map_err(|_| invalid())?;
+        let id: Uuid = id.parse().map_err(|_| invalid())?;
+
+        let (column, value): (listings::Column, sea_orm::Value) = match sort {
+            ListingSort::Newest | ListingSort::Oldest => {
+                let created_at = chrono::DateTime::from_timestamp_micros(key).ok_or_else(invalid)?;
+                (listings::Column::CreatedAt, created_at.into())
+            }
+            ListingSort::PriceAsc | ListingSort::PriceDesc => (listings::Column::Price, key.into()),
+        };
+
+        let (past_key, past_id) = if sort.ascending() {
+            (column.gt(value.clone()), listings::Column::Id.gt(id))
+        } else {
+            (column.lt(value.clone()), listings::Column::Id.lt(id))
+        };
+
+        Ok(sea_orm::Condition::any()
+            .add(past_key)
+            .add(sea_orm::Condition::all().add(column.eq(value)).add(past_id)))
+    }
+
+    pub async fn invalidate_product_listings(
+        &self,
+        product_id: &Uuid,
+    ) {
+        CacheService::new(self.state.clone())
+            .invalidate(&[CacheService::product_listings_key(product_id)])
+            .await;
+    }
+
+    /// Listing changes are recorded against the seller, who is the only one who can make them.
+    async fn audit<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        client: &ClientInfo,
+        action: &str,
+        old: Option<&listings::Model>,
+        new: Option<&listings::Model>,
+    ) -> Result<(), AppError> {
+        let Some(listing) = new.or(old) else {
+            return Ok(());
+        };
+
+        AuditService::record_on(conn, AuditEvent {
+            actor_id: Some(listing.seller_id),
+            client,
+            action,
+            target_type: "listing",
+            target_id: Some(listing.id.to_string()),
+            old_values: old.and_then(|listing| serde_json::to_value(listing).ok()),
+            new_values: new.and_then(|listing| serde_json::to_value(listing).ok()),
+            metadata: None,
+        }).await?;
+
+        Ok(())
+    }
+
+    pub fn listing_expiry() -> chrono::DateTime<chrono::Utc> {
+        chrono::Utc::now() + chrono::Duration::days(Config::get().listing_lifetime_days)
+    }
+
+    /// The update is conditional so it can't race a checkout: it only applies to a live
+    /// listing and, when the quantity changes, only while the new quantity still covers
+    /// the units held by pending checkouts.
+    fn update_statement(
+        request: &UpdateListingRequest,
+        condition: Option<listings::Condition>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> UpdateMany<listings::Entity> {
+        let mut update = listings::Entity::update_many()
+            .col_expr(listings::Column::UpdatedAt, Expr::value(now))
+            .filter(listings::Column::Id.eq(request.id))
+            .filter(listings::Column::DeletedAt.is_null());
+
+        if let Some(price) = request.price {
+            update = update.col_expr(listings::Column::Price, Expr::value(price));
+        }
+
+        if let Some(condition) = condition {
+            update = update.col_expr(listings::Column::Condition, Expr::value(condition));
+        }
+
+        if let Some(quantity) = request.quantity {
+            update = update
+                .col_expr(listings::Column::Quantity, Expr::value(quantity))
+                .filter(listings::Column::ReservedQuantity.lte(quantity));
+        }
+
+        if let Some(image_url) = &request.image_url {
+            update = update.col_expr(listings::Column::ImageUrl, Expr::value(image_url.clone()));
+        }
+
+        if let Some(description) = &request.description {
+            update = update.col_expr(listings::Column::Description, Expr::value(description.clone()));
+        }
+
+        update
     }
-}
\ No newline at end of file
+
+    /// Prices are in cents. The listing import applies the same rule to each row.
+    fn check_price(price: i64) -> Result<(), AppError> {
+        if price <= 0 {
+            return Err(AppError::field("price", "price must be a positive amount in cents"));
+        }
+
+        Ok(())
+    }
+
+    fn check_quantity(quantity: i64) -> Result<(), AppError> {
+        if quantity <= 0 {
+            return Err(AppError::field("quantity", "quantity must be at least 1"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, QueryTrait};
+    use super::*;
+
+    #[test]
+    fn rejects_prices_that_are_not_positive() {
+        assert!(ListingService::check_price(1).is_ok());
+        assert!(matches!(ListingService::check_price(0), Err(AppError::Validation { .. })));
+        assert!(matches!(ListingService::check_price(-500), Err(AppError::Validation { .. })));
+    }
+
+    fn update_request(quantity: Option<i64>) -> UpdateListingRequest {
+        UpdateListingRequest {
+            id: Uuid::nil(),
+            price: Some(1_250),
+            condition: None,
+            quantity,
+            image_url: None,
+            description: None,
+        }
+    }
+
+    fn update_sql(request: &UpdateListingRequest) -> String {
+        ListingService::update_statement(request, None, chrono::Utc::now())
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn quantity_update_cannot_drop_below_held_stock() {
+        let sql = update_sql(&update_request(Some(3)));
+
+        assert!(sql.contains(r#""quantity" = 3"#), "{}", sql);
+        assert!(sql.contains(r#""reserved_quantity" <= 3"#), "{}", sql);
+        assert!(sql.contains(r#""deleted_at" IS NULL"#), "{}", sql);
+    }
+
+    #[test]
+    fn update_without_quantity_leaves_stock_alone() {
+        let sql = update_sql(&update_request(None));
+
+        assert!(sql.contains(r#""price" = 1250"#), "{}", sql);
+        assert!(!sql.contains("reserved_quantity"), "{}", sql);
+        assert!(sql.contains(r#""deleted_at" IS NULL"#), "{}", sql);
+    }
+
+    #[test]
+    fn rejects_quantities_below_one() {
+        assert!(ListingService::check_quantity(1).is_ok());
+        assert!(matches!(ListingService::check_quantity(0), Err(AppError::Validation { .. })));
+        assert!(matches!(ListingService::check_quantity(-1), Err(AppError::Validation { .. })));
+    }
+
+    fn listing(price: i64) -> listings::Model {
+        let now = chrono::Utc::now();
+
+        listings::Model {
+            id: Uuid::new_v4(),
+            product_id: Uuid::nil(),
+            seller_id: Uuid::nil(),
+            price,
+            condition: listings::Condition::NearMint,
+            quantity: 1,
+            reserved_quantity: 0,
+            status: ListingStatus::Active,
+            stripe_product_id: None,
+            previous_stripe_product_id: None,
+            image_url: None,
+            description: None,
+            expires_at: now,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        }
+    }
+
+    fn after_sql(sort: ListingSort, cursor: &str) -> String {
+        listings::Entity::find()
+            .filter(ListingService::after_cursor(sort, cursor).unwrap())
+            .build(DbBackend::Postgres)
+            .to_string()
+    }
+
+    #[test]
+    fn sorts_round_trip_through_their_names() {
+        for sort in [ListingSort::Newest, ListingSort::Oldest, ListingSort::PriceAsc, ListingSort::PriceDesc] {
+            assert_eq!(ListingSort::parse(sort.as_str()), Some(sort));
+        }
+        assert_eq!(ListingSort::parse("PRICE_ASC"), Some(ListingSort::PriceAsc));
+        assert_eq!(ListingSort::parse("cheapest"), None);
+    }
+
+    #[test]
+    fn cursor_continues_after_the_last_listing() {
+        let last = listing(1_250);
+        let cursor = ListingService::encode_cursor(ListingSort::PriceAsc, &last);
+        let sql = after_sql(ListingSort::PriceAsc, &cursor);
+
+        assert!(sql.contains(r#""price" > 1250"#), "{}", sql);
+        assert!(sql.contains(r#""price" = 1250"#), "{}", sql);
+        assert!(sql.contains(&format!(r#""id" > '{}'"#, last.id)), "{}", sql);
+    }
+
+    #[test]
+    fn descending_cursors_walk_backwards() {
+        let last = listing(1_250);
+        let cursor = ListingService::encode_cursor(ListingSort::Newest, &last);
+        let sql = after_sql(ListingSort::Newest, &cursor);
+
+        assert!(sql.contains(r#""created_at" <"#), "{}", sql);
+        assert!(sql.contains(&format!(r#""id" < '{}'"#, last.id)), "{}", sql);
+    }
+
+    #[test]
+    fn cursors_only_work_for_their_own_sort() {
+        let cursor = ListingService::encode_cursor(ListingSort::PriceAsc, &listing(1_250));
+
+        assert!(matches!(ListingService::after_cursor(ListingSort::PriceDesc, &cursor), Err(AppError::Validation { .. })));
+    }
+
+    #[test]
+    fn rejects_tampered_cursors() {
+        let tampered = URL_SAFE_NO_PAD.encode("price_asc:cheap:nope");
+
+        for cursor in ["not base64!", "bmV3ZXN0", tampered.as_str()] {
+            assert!(matches!(ListingService::after_cursor(ListingSort::PriceAsc, cursor), Err(AppError::Validation { .. })), "{}", cursor);
+        }
+    }
+}
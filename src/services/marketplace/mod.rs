@@ -1,3 +1,8 @@
 pub mod product_service;
 pub mod listing_service;
-pub mod game_service;
\ No newline at end of file
+pub mod game_service;
+pub mod seller_service;
+pub mod listing_expiry_service;
+pub mod catalogue_service;
+pub mod catalogue_import_service;
+pub mod listing_import_service;
\ No newline at end of file
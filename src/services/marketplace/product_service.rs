@@ -1,14 +1,23 @@
 use std::collections::HashMap;
-use sea_orm::{ActiveModelTrait, EntityTrait, QuerySelect, Set, PaginatorTrait, JsonValue, QueryFilter, ColumnTrait};
+use std::time::Duration;
+use sea_orm::{ActiveModelTrait, EntityTrait, QuerySelect, Set, PaginatorTrait, JsonValue, QueryFilter, ColumnTrait, QueryOrder, TransactionTrait, ConnectionTrait};
 use serde::{Deserialize, Serialize};
+use crate::entities::listings;
 use crate::entities::products;
 use crate::entities::product_variants;
-use crate::handlers::marketplace::product_handler::{CreateProductRequest, CreateVariantRequest, ProductImageUploadRequest, ProductResponse};
+use crate::errors::AppError;
+use crate::handlers::marketplace::product_handler::{CreateProductRequest, CreateVariantRequest, ProductImageUploadRequest, ProductResponse, UpdateProductRequest};
 use uuid::Uuid;
 use crate::app_state::AppState;
 use crate::entities::products::string_to_product_category;
-use crate::services::integrations::meilisearch_service::MeilisearchService;
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
 use crate::services::integrations::r2_service::R2Service;
+use crate::services::integrations::redis_service::CacheService;
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::services::marketplace::game_service::GameService;
+use crate::utils::request_util::ClientInfo;
+
+const PRODUCT_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
 
 pub struct ProductService {
     pub state: AppState,
@@ -46,12 +55,12 @@ impl ProductService {
     
     pub async fn create_product(
         &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
         request: CreateProductRequest,
-    ) -> Result<ProductResponse, String> {
-        let db = &self.state.db;
-
+    ) -> Result<ProductResponse, AppError> {
         let category = string_to_product_category(&request.category)
-            .ok_or("Invalid product category")?;
+            .ok_or_else(|| AppError::validation("Invalid product category"))?;
 
         let product = products::ActiveModel {
             id: Set(Uuid::new_v4()),
@@ -67,21 +76,30 @@ impl ProductService {
             updated_at: Set(chrono::Utc::now()),
         };
 
-        let product: products::Model = product.insert(db).await
-            .map_err(|e| format!("Failed to create product: {}", e))
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let product: products::Model = product.insert(&txn).await
+            .map_err(|e| AppError::Internal(format!("Failed to create product: {}", e)))
             .map(|model| model.into())?;
 
+        JobService::enqueue(&txn, Job::IndexProducts { product_ids: vec![product.id] }).await?;
+
+        self.audit(&txn, actor_id, client, "product.created", None, Some(&product)).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit product: {}", e)))?;
+
         let variants = self.create_product_variants(
             product.id,
             request.variants
-        ).await.map_err(|e| format!("Failed to create product variants: {}", e))?;
+        ).await?;
 
-
-        let search_service = MeilisearchService::new(self.state.clone());
-        
-        if let Err(e) = search_service.index_product(&product).await {
-            return Err(format!("Failed to index product: {}", e));
-        }
+        CacheService::new(self.state.clone())
+            .invalidate(&[CacheService::trending_products_key()])
+            .await;
 
         Ok(ProductResponse {
             product,
@@ -93,23 +111,30 @@ impl ProductService {
         &self,
         product_id: Uuid,
         requests: Vec<CreateVariantRequest>,
-    ) -> Result<Vec<product_variants::Model>, String> {
+    ) -> Result<Vec<product_variants::Model>, AppError> {
         let db = &self.state.db;
 
         let product = products::Entity::find_by_id(product_id)
             .one(db)
             .await
-            .map_err(|e| format!("Failed to fetch product: {}", e))?
-            .ok_or("Product not found")?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+        let existing = product_variants::Entity::find()
+            .filter(product_variants::Column::ProductId.eq(product.id))
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count product variants: {}", e)))?;
 
         let mut variants = Vec::new();
 
-        for request in requests {
+        for (position, request) in requests.into_iter().enumerate() {
             let variant = self.create_product_variant(
                 product_id,
                 request.name,
                 request.set_number,
                 request.is_primary,
+                (existing as usize + position) as i32,
             ).await?;
 
             variants.push(variant);
@@ -124,7 +149,8 @@ impl ProductService {
         name: String,
         set_number: Option<String>,
         is_primary: bool,
-    ) -> Result<product_variants::Model, String> {
+        sort_order: i32,
+    ) -> Result<product_variants::Model, AppError> {
         let db = &self.state.db;
 
         let variant = product_variants::ActiveModel {
@@ -132,12 +158,13 @@ impl ProductService {
             name: Set(name),
             set_number: Set(set_number),
             is_primary: Set(is_primary),
+            sort_order: Set(sort_order),
             created_at: Set(chrono::Utc::now()),
             ..Default::default()
         };
 
         let variant = variant.insert(db).await
-            .map_err(|e| format!("Failed to create product variant: {}", e))?;
+            .map_err(|e| AppError::Internal(format!("Failed to create product variant: {}", e)))?;
 
         Ok(variant)
     }
@@ -145,81 +172,231 @@ impl ProductService {
     pub async fn get_product_variants(
         &self,
         product_id: &Uuid,
-    ) -> Result<Vec<product_variants::Model>, String> {
+    ) -> Result<Vec<product_variants::Model>, AppError> {
         let db = &self.state.db;
 
         product_variants::Entity::find()
             .filter(product_variants::Column::ProductId.eq(*product_id))
+            .order_by_asc(product_variants::Column::SortOrder)
+            .order_by_asc(product_variants::Column::Id)
             .all(db)
             .await
-            .map_err(|e| format!("Failed to fetch product variants: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product variants: {}", e)))
     }
 
-    /*
     pub async fn update_product(
         &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        id: Uuid,
         request: UpdateProductRequest,
-    ) -> Result<products::Model, String> {
-        let db = &self.state.db;
-
-        let mut product: products::ActiveModel = products::Entity::find_by_id(request.id)
-            .one(db)
+    ) -> Result<products::Model, AppError> {
+        let existing = products::Entity::find_by_id(id)
+            .one(&self.state.db)
             .await
-            .map_err(|e| format!("Failed to fetch product: {}", e))?
-            .ok_or("Product not found")?
-            .into();
-        
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+        let previous = existing.clone();
+        let mut product: products::ActiveModel = existing.into();
+
         if let Some(name) = request.name {
             product.name = Set(name);
         }
 
+        if let Some(description) = request.description {
+            product.description = Set(Some(description));
+        }
+
+        if let Some(image_url) = request.image_url {
+            product.image_url = Set(Some(image_url));
+        }
+
         if let Some(game) = request.game {
+            self.require_game(&game).await?;
             product.game = Set(game);
         }
 
+        if let Some(set) = request.set {
+            product.set = Set(Some(set));
+        }
+
+        if let Some(category) = request.category {
+            let category = string_to_product_category(&category)
+                .ok_or_else(|| AppError::validation("Invalid product category"))?;
+            product.category = Set(category);
+        }
+
+        if let Some(subcategory) = request.subcategory {
+            product.subcategory = Set(Some(subcategory));
+        }
+
         if let Some(metadata) = request.metadata {
-            product.metadata = Set(metadata);
+            product.metadata = Set(Some(metadata));
         }
-        
-        product.image_url = Set(request.image_url);
-        product.set = Set(request.set);
+
         product.updated_at = Set(chrono::Utc::now());
 
-        product.update(db).await
-            .map_err(|e| format!("Failed to update product: {}", e))
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let product = product.update(&txn).await
+            .map_err(|e| AppError::Internal(format!("Failed to update product: {}", e)))?;
+
+        JobService::enqueue(&txn, Job::IndexProducts { product_ids: vec![product.id] }).await?;
+
+        self.audit(&txn, actor_id, client, "product.updated", Some(&previous), Some(&product)).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit product: {}", e)))?;
+
+        self.invalidate_product(&product.id).await;
+
+        Ok(product)
+    }
+
+    /// Drops every cached copy of a changed product. Search catches up through the
+    /// job queue.
+    pub async fn invalidate_product(
+        &self,
+        product_id: &Uuid,
+    ) {
+        CacheService::new(self.state.clone())
+            .invalidate(&[
+                CacheService::product_key(product_id),
+                CacheService::product_listings_key(product_id),
+                CacheService::trending_products_key(),
+            ])
+            .await;
+    }
+
+    async fn audit<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        actor_id: Uuid,
+        client: &ClientInfo,
+        action: &str,
+        old: Option<&products::Model>,
+        new: Option<&products::Model>,
+    ) -> Result<(), AppError> {
+        let Some(product) = new.or(old) else {
+            return Ok(());
+        };
+
+        AuditService::record_on(conn, AuditEvent {
+            actor_id: Some(actor_id),
+            client,
+            action,
+            target_type: "product",
+            target_id: Some(product.id.to_string()),
+            old_values: old.and_then(|product| serde_json::to_value(product).ok()),
+            new_values: new.and_then(|product| serde_json::to_value(product).ok()),
+            metadata: None,
+        }).await?;
+
+        Ok(())
+    }
+
+    async fn require_game(
+        &self,
+        game: &str,
+    ) -> Result<(), AppError> {
+        GameService::new(self.state.clone())
+            .get_game_by_name(game)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Game '{}' not found", game)))?;
+
+        Ok(())
     }
-     */
 
     pub async fn delete_product(
         &self,
+        actor_id: Uuid,
+        client: &ClientInfo,
         id: Uuid
-    ) -> Result<bool, String> {
-        let db = &self.state.db;
+    ) -> Result<bool, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        // Locking the row makes a listing being created for it concurrently wait for the
+        // count below, instead of slipping in before the delete.
+        let Some(product) = products::Entity::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+        else {
+            return Ok(false);
+        };
+
+        // Listings keep their product even once deleted, since orders point at them.
+        let listing_count = listings::Entity::find()
+            .filter(listings::Column::ProductId.eq(id))
+            .count(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count listings: {}", e)))?;
+
+        if listing_count > 0 {
+            return Err(AppError::Conflict(format!(
+                "Product '{}' has {} listings; merge it into another product instead",
+                product.name,
+                listing_count,
+            )));
+        }
 
         products::Entity::delete_by_id(id)
-            .exec(db)
+            .exec(&txn)
             .await
-            .map_err(|e| format!("Failed to delete product: {}", e))
-            .map(|_| true)
+            .map_err(|e| AppError::Internal(format!("Failed to delete product: {}", e)))?;
+
+        JobService::enqueue_all(&txn, vec![
+            Job::RemoveProduct { product_id: id },
+            Job::DeleteProductImages { product_id: id, game: product.game.clone() },
+        ]).await?;
+
+        self.audit(&txn, actor_id, client, "product.deleted", Some(&product), None).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit product deletion: {}", e)))?;
+
+        self.invalidate_product(&id).await;
+
+        Ok(true)
     }
 
     pub async fn get_product_by_id(
         &self,
         id: &Uuid,
-    ) -> Result<Option<products::Model>, String> {
+    ) -> Result<Option<products::Model>, AppError> {
         let db = &self.state.db;
+        let cache_service = CacheService::new(self.state.clone());
+        let cache_key = CacheService::product_key(id);
+
+        if let Some(product) = cache_service.get::<products::Model>(&cache_key).await {
+            return Ok(Some(product));
+        }
 
-        products::Entity::find_by_id(*id)
+        let product = products::Entity::find_by_id(*id)
             .one(db)
             .await
-            .map_err(|e| format!("Failed to fetch product: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?;
+
+        if let Some(product) = &product {
+            cache_service.set(&cache_key, product, PRODUCT_CACHE_TTL).await;
+        }
+
+        Ok(product)
     }
 
     pub async fn get_products(
         &self,
         offset: u64,
         limit: u64,
-    ) -> Result<Vec<products::Model>, String> {
+    ) -> Result<Vec<products::Model>, AppError> {
         let db = &self.state.db;
 
         products::Entity::find()
@@ -227,18 +404,18 @@ impl ProductService {
             .limit(limit)
             .all(db)
             .await
-            .map_err(|e| format!("Failed to fetch products: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to fetch products: {}", e)))
     }
 
     pub async fn get_number_of_products(
         &self,
-    ) -> Result<u64, String> {
+    ) -> Result<u64, AppError> {
         let db = &self.state.db;
 
         products::Entity::find()
             .count(db)
             .await
-            .map_err(|e| format!("Failed to count products: {}", e))
+            .map_err(|e| AppError::Internal(format!("Failed to count products: {}", e)))
     }
 
     /*
@@ -246,7 +423,7 @@ impl ProductService {
         &self,
         product_id: Uuid,
         variant_id: String,
-    ) -> Result<products::Model, String> {
+    ) -> Result<products::Model, AppError> {
 
     }
 
@@ -257,14 +434,14 @@ impl ProductService {
         user_id: &Uuid,
         product_id: &Uuid,
         request: ProductImageUploadRequest,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let db = &self.state.db;
 
         let product = products::Entity::find_by_id(*product_id)
             .one(db)
             .await
-            .map_err(|e| format!("Failed to fetch product: {}", e))?
-            .ok_or("Product not found")?;
+            .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
 
 
         let r2_service = R2Service::new(self.state.clone());
@@ -280,7 +457,7 @@ impl ProductService {
                 &upload.variant_name,
                 front_data.as_deref(),
                 back_data.as_deref(),
-            ).await.map_err(|e| format!("Failed to upload front image: {}", e))?;
+            ).await?;
         }
 
         Ok(())
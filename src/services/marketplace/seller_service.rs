@@ -0,0 +1,536 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use sea_orm::{ActiveModelTrait, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use serde::Serialize;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::{listings, order_items, orders, products, users};
+use crate::entities::listings::{string_to_status, ListingStatus};
+use crate::entities::orders::OrderStatus;
+use crate::entities::users::ACCOUNT_STATUS_ACTIVE;
+use crate::errors::AppError;
+use crate::handlers::marketplace::listing_handler::ListingBrowseQuery;
+use crate::handlers::marketplace::seller_handler::SellerOnboardingStatus;
+use crate::services::account::user_service::UserService;
+use crate::services::integrations::stripe_service::{StripeAccount, StripeAccountLink, StripeService};
+use crate::services::marketplace::listing_service::{ListingPage, ListingService};
+
+/// Orders that have been paid for and not refunded, i.e. money the seller keeps.
+const COMPLETED_SALE_STATUSES: [OrderStatus; 3] = [
+    OrderStatus::Paid,
+    OrderStatus::Shipped,
+    OrderStatus::Delivered,
+];
+
+/// Refunded orders still show up in a seller's sales history, but not in their totals.
+const SALE_HISTORY_STATUSES: [OrderStatus; 4] = [
+    OrderStatus::Paid,
+    OrderStatus::Shipped,
+    OrderStatus::Delivered,
+    OrderStatus::Refunded,
+];
+
+#[derive(Debug, Serialize)]
+pub struct SellerListing {
+    #[serde(flatten)]
+    pub listing: listings::Model,
+    pub product_name: Option<String>,
+    pub game: Option<String>,
+    pub sold_quantity: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct InventoryBucket {
+    pub listings: u64,
+    pub quantity: i64,
+    pub value: i64,
+}
+
+/// Stock on active listings, valued at list price. Reserved units are still counted,
+/// since they only leave the seller's inventory once checkout completes.
+#[derive(Debug, Default, Serialize)]
+pub struct InventorySummary {
+    pub active_listings: u64,
+    pub total_quantity: i64,
+    pub reserved_quantity: i64,
+    pub total_value: i64,
+    pub by_condition: BTreeMap<&'static str, InventoryBucket>,
+    pub by_game: BTreeMap<String, InventoryBucket>,
+}
+
+impl InventorySummary {
+    /// Counts the listing if it is active; anything else isn't for sale.
+    fn add(&mut self, listing: &listings::Model, game: &str) {
+        if !listing.is_active() {
+            return;
+        }
+
+        let value = listing.price * listing.quantity;
+
+        self.active_listings += 1;
+        self.total_quantity += listing.quantity;
+        self.reserved_quantity += listing.reserved_quantity;
+        self.total_value += value;
+
+        for bucket in [
+            self.by_condition.entry(listing.condition.as_str()).or_default(),
+            self.by_game.entry(game.to_string()).or_default(),
+        ] {
+            bucket.listings += 1;
+            bucket.quantity += listing.quantity;
+            bucket.value += value;
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SellerListings {
+    pub listings: Vec<SellerListing>,
+    pub status_counts: BTreeMap<&'static str, u64>,
+    pub reserved_quantity: i64,
+    pub sold_quantity: i64,
+    pub inventory: InventorySummary,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SellerSale {
+    #[serde(flatten)]
+    pub item: order_items::Model,
+    pub order_status: OrderStatus,
+    pub paid_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub shipped_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Totals cover every completed sale, not just the current page.
+#[derive(Debug, Default, Serialize)]
+pub struct SalesTotals {
+    pub orders: u64,
+    pub items_sold: i64,
+    pub gross_amount: i64,
+    pub fees: i64,
+    pub net_amount: i64,
+}
+
+impl SalesTotals {
+    /// Sums `(order_id, unit_price, quantity, application_fee)` rows of completed sales.
+    fn from_items(items: Vec<(Uuid, i64, i64, i64)>) -> Self {
+        let mut totals = SalesTotals::default();
+        let mut order_ids = HashSet::new();
+
+        for (order_id, unit_price, quantity, fee) in items {
+            order_ids.insert(order_id);
+            totals.items_sold += quantity;
+            totals.gross_amount += unit_price * quantity;
+            totals.fees += fee;
+        }
+
+        totals.orders = order_ids.len() as u64;
+        totals.net_amount = totals.gross_amount - totals.fees;
+
+        totals
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SellerSales {
+    pub sales: Vec<SellerSale>,
+    pub totals: SalesTotals,
+    pub total: u64,
+}
+
+/// What anyone can see about a seller. There are no reviews yet, so `rating` is
+/// always empty.
+#[derive(Debug, Serialize)]
+pub struct SellerStorefront {
+    pub username: String,
+    pub avatar_url: Option<String>,
+    pub verified_seller: bool,
+    pub member_since: chrono::DateTime<chrono::Utc>,
+    pub rating: Option<f64>,
+    pub rating_count: u64,
+    pub listings: ListingPage,
+}
+
+pub struct SellerService {
+    pub state: AppState,
+}
+
+impl SellerService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn start_onboarding(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<StripeAccountLink, AppError> {
+        let user_service = UserService::new(self.state.clone());
+        let stripe_service = StripeService::new(self.state.clone());
+
+        let user = user_service.get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let account_id = match user.stripe_account_id {
+            Some(account_id) => account_id,
+            None => {
+                let account = stripe_service.create_connected_account(&user.email, &user.id).await?;
+                let account_id = account.id.clone();
+
+                user_service.update_user_field(
+                    &user.id,
+                    |user| {
+                        user.stripe_account_id = Set(Some(account_id));
+                    }
+                ).await?;
+
+                account.id
+            }
+        };
+
+        let config = Config::get();
+
+        stripe_service.create_account_link(
+            &account_id,
+            &config.stripe_connect_refresh_url,
+            &config.stripe_connect_return_url,
+        ).await
+    }
+
+    pub async fn get_onboarding_status(
+        &self,
+        user_id: &Uuid,
+    ) -> Result<SellerOnboardingStatus, AppError> {
+        let user_service = UserService::new(self.state.clone());
+
+        let user = user_service.get_user_by_id(user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let account_id = match user.stripe_account_id {
+            Some(account_id) => account_id,
+            None => return Ok(SellerOnboardingStatus {
+                stripe_account_id: None,
+                verified_seller: user.verified_seller,
+                charges_enabled: false,
+                payouts_enabled: false,
+                details_submitted: false,
+            }),
+        };
+
+        let stripe_service = StripeService::new(self.state.clone());
+        let account = stripe_service.get_connected_account(&account_id).await?;
+        let verified_seller = self.sync_connected_account(&self.state.db, &account).await?;
+
+        Ok(SellerOnboardingStatus {
+            stripe_account_id: Some(account.id),
+            verified_seller,
+            charges_enabled: account.charges_enabled,
+            payouts_enabled: account.payouts_enabled,
+            details_submitted: account.details_submitted,
+        })
+    }
+
+    /// Mirrors a connected account's capabilities onto `users.verified_seller`.
+    pub async fn sync_connected_account<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        account: &StripeAccount,
+    ) -> Result<bool, AppError> {
+        let user = users::Entity::find()
+            .filter(users::Column::StripeAccountId.eq(account.id.as_str()))
+            .one(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch user by Stripe account: {}", e)))?;
+
+        let user = match user {
+            Some(user) => user,
+            None => return Ok(false),
+        };
+
+        let verified_seller = account.can_receive_payouts();
+
+        if user.verified_seller != verified_seller {
+            let mut user_update: users::ActiveModel = user.into();
+            user_update.verified_seller = Set(verified_seller);
+            user_update.updated_at = Set(chrono::Utc::now());
+
+            user_update.update(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to update user: {}", e)))?;
+        }
+
+        Ok(verified_seller)
+    }
+
+    /// Every listing the seller hasn't deleted, newest first, optionally narrowed to the
+    /// statuses in `status`. Counts and the inventory summary always cover all of them.
+    pub async fn get_my_listings(
+        &self,
+        seller_id: Uuid,
+        status: Option<&str>,
+    ) -> Result<SellerListings, AppError> {
+        let statuses = match status.filter(|status| !status.is_empty()) {
+            Some(status) => Some(status.split(',')
+                .map(|status| string_to_status(status.trim())
+                    .ok_or_else(|| AppError::field("status", format!("Invalid status '{}'", status.trim()))))
+                .collect::<Result<Vec<_>, _>>()?),
+            None => None,
+        };
+
+        let rows = listings::Entity::find()
+            .filter(listings::Column::SellerId.eq(seller_id))
+            .filter(listings::Column::DeletedAt.is_null())
+            .order_by_desc(listings::Column::CreatedAt)
+            .find_also_related(products::Entity)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listings: {}", e)))?;
+
+        let sold = self.sold_quantities(seller_id).await?;
+
+        let mut status_counts: BTreeMap<&'static str, u64> = [
+            ListingStatus::Active,
+            ListingStatus::Sold,
+            ListingStatus::Cancelled,
+            ListingStatus::Expired,
+        ]
+            .iter()
+            .map(|status| (status.as_str(), 0))
+            .collect();
+
+        let mut inventory = InventorySummary::default();
+        let mut reserved_quantity = 0;
+        let mut listings = Vec::new();
+
+        for (listing, product) in rows {
+            *status_counts.entry(listing.status.as_str()).or_default() += 1;
+            reserved_quantity += listing.reserved_quantity;
+
+            inventory.add(&listing, product.as_ref().map_or("Unknown", |product| product.game.as_str()));
+
+            if statuses.as_ref().is_some_and(|statuses| !statuses.contains(&listing.status)) {
+                continue;
+            }
+
+            listings.push(SellerListing {
+                sold_quantity: sold.get(&listing.id).copied().unwrap_or(0),
+                product_name: product.as_ref().map(|product| product.name.clone()),
+                game: product.map(|product| product.game),
+                listing,
+            });
+        }
+
+        Ok(SellerListings {
+            listings,
+            status_counts,
+            reserved_quantity,
+            sold_quantity: sold.values().sum(),
+            inventory,
+        })
+    }
+
+    pub async fn get_my_sales(
+        &self,
+        seller_id: Uuid,
+        offset: u64,
+        limit: u64,
+    ) -> Result<SellerSales, AppError> {
+        let db = &self.state.db;
+
+        let total = order_items::Entity::find()
+            .inner_join(orders::Entity)
+            .filter(order_items::Column::SellerId.eq(seller_id))
+            .filter(orders::Column::Status.is_in(SALE_HISTORY_STATUSES))
+            .count(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count sales: {}", e)))?;
+
+        let sales = order_items::Entity::find()
+            .find_also_related(orders::Entity)
+            .filter(order_items::Column::SellerId.eq(seller_id))
+            .filter(orders::Column::Status.is_in(SALE_HISTORY_STATUSES))
+            .order_by_desc(order_items::Column::CreatedAt)
+            .offset(offset)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch sales: {}", e)))?
+            .into_iter()
+            .filter_map(|(item, order)| order.map(|order| SellerSale {
+                item,
+                order_status: order.status,
+                paid_at: order.paid_at,
+                shipped_at: order.shipped_at,
+                delivered_at: order.delivered_at,
+            }))
+            .collect();
+
+        let completed: Vec<(Uuid, i64, i64, i64)> = order_items::Entity::find()
+            .select_only()
+            .column(order_items::Column::OrderId)
+            .column(order_items::Column::UnitPrice)
+            .column(order_items::Column::Quantity)
+            .column(order_items::Column::ApplicationFeeAmount)
+            .inner_join(orders::Entity)
+            .filter(order_items::Column::SellerId.eq(seller_id))
+            .filter(orders::Column::Status.is_in(COMPLETED_SALE_STATUSES))
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch sales totals: {}", e)))?;
+
+        Ok(SellerSales { sales, totals: SalesTotals::from_items(completed), total })
+    }
+
+    /// A seller's public page. Suspended and banned accounts are hidden, as are users
+    /// who have never listed anything, so usernames of buyers aren't confirmed.
+    pub async fn get_storefront(
+        &self,
+        username: &str,
+        mut query: ListingBrowseQuery,
+        limit: u64,
+    ) -> Result<SellerStorefront, AppError> {
+        let not_found = || AppError::NotFound("Seller not found".to_string());
+
+        let user = UserService::new(self.state.clone())
+            .get_user_by_username(username)
+            .await?
+            .filter(|user| user.account_status == ACCOUNT_STATUS_ACTIVE)
+            .ok_or_else(not_found)?;
+
+        let has_listings = listings::Entity::find()
+            .filter(listings::Column::SellerId.eq(user.id))
+            .filter(listings::Column::DeletedAt.is_null())
+            .count(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to count listings: {}", e)))?
+            > 0;
+
+        if !user.verified_seller && !has_listings {
+            return Err(not_found());
+        }
+
+        // The storefront only ever shows what can be bought right now.
+        query.seller_id = Some(user.id);
+        query.status = None;
+
+        let listings = ListingService::new(self.state.clone())
+            .browse_listings(&query, limit)
+            .await?;
+
+        Ok(SellerStorefront {
+            username: user.username.unwrap_or_default(),
+            avatar_url: user.avatar_url,
+            verified_seller: user.verified_seller,
+            member_since: user.created_at,
+            rating: None,
+            rating_count: 0,
+            listings,
+        })
+    }
+
+    /// Units sold per listing across the seller's completed orders.
+    async fn sold_quantities(
+        &self,
+        seller_id: Uuid,
+    ) -> Result<HashMap<Uuid, i64>, AppError> {
+        let items: Vec<(Uuid, i64)> = order_items::Entity::find()
+            .select_only()
+            .column(order_items::Column::ListingId)
+            .column(order_items::Column::Quantity)
+            .inner_join(orders::Entity)
+            .filter(order_items::Column::SellerId.eq(seller_id))
+            .filter(orders::Column::Status.is_in(COMPLETED_SALE_STATUSES))
+            .into_tuple()
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch sold quantities: {}", e)))?;
+
+        let mut sold = HashMap::new();
+
+        for (listing_id, quantity) in items {
+            *sold.entry(listing_id).or_insert(0) += quantity;
+        }
+
+        Ok(sold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::listings::Condition;
+
+    fn listing(status: ListingStatus, condition: Condition, price: i64, quantity: i64, reserved_quantity: i64) -> listings::Model {
+        let now = chrono::Utc::now();
+
+        listings::Model {
+            id: Uuid::new_v4(),
+            product_id: Uuid::nil(),
+            seller_id: Uuid::nil(),
+            price,
+            condition,
+            quantity,
+            reserved_quantity,
+            status,
+            stripe_product_id: None,
+            previous_stripe_product_id: None,
+            image_url: None,
+            description: None,
+            expires_at: now,
+            created_at: now,
+            updated_at: now,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn inventory_values_active_stock_at_list_price() {
+        let mut inventory = InventorySummary::default();
+
+        inventory.add(&listing(ListingStatus::Active, Condition::NearMint, 500, 3, 1), "Pokemon");
+        inventory.add(&listing(ListingStatus::Active, Condition::NearMint, 1_000, 1, 0), "Yu-Gi-Oh!");
+
+        assert_eq!(inventory.active_listings, 2);
+        assert_eq!(inventory.total_quantity, 4);
+        assert_eq!(inventory.reserved_quantity, 1);
+        assert_eq!(inventory.total_value, 2_500);
+
+        let near_mint = &inventory.by_condition[Condition::NearMint.as_str()];
+        assert_eq!((near_mint.listings, near_mint.quantity, near_mint.value), (2, 4, 2_500));
+        assert_eq!(inventory.by_game["Pokemon"].value, 1_500);
+        assert_eq!(inventory.by_game["Yu-Gi-Oh!"].value, 1_000);
+    }
+
+    #[test]
+    fn inventory_skips_listings_that_are_not_for_sale() {
+        let mut inventory = InventorySummary::default();
+
+        inventory.add(&listing(ListingStatus::Sold, Condition::Mint, 500, 1, 0), "Pokemon");
+        inventory.add(&listing(ListingStatus::Expired, Condition::Mint, 500, 1, 0), "Pokemon");
+
+        assert_eq!(inventory.active_listings, 0);
+        assert_eq!(inventory.total_value, 0);
+        assert!(inventory.by_game.is_empty());
+    }
+
+    #[test]
+    fn sales_totals_count_orders_once_and_net_out_fees() {
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+
+        let totals = SalesTotals::from_items(vec![
+            (first, 1_000, 2, 100),
+            (first, 500, 1, 25),
+            (second, 250, 4, 50),
+        ]);
+
+        assert_eq!(totals.orders, 2);
+        assert_eq!(totals.items_sold, 7);
+        assert_eq!(totals.gross_amount, 3_500);
+        assert_eq!(totals.fees, 175);
+        assert_eq!(totals.net_amount, 3_325);
+    }
+}
@@ -2,4 +2,5 @@ pub mod transactions;
 pub mod marketplace;
 pub mod account;
 pub mod integrations;
-pub mod admin;
\ No newline at end of file
+pub mod admin;
+pub mod jobs;
\ No newline at end of file
@@ -0,0 +1,199 @@
+use sea_orm::*;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{cart_items, listings};
+use crate::errors::AppError;
+use crate::handlers::transactions::cart_handler::{AddCartItemRequest, CartItemResponse, CartResponse};
+use crate::services::transactions::order_service::DEFAULT_CURRENCY;
+
+pub struct CartService {
+    pub state: AppState,
+}
+
+impl CartService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn get_cart(
+        &self,
+        user_id: Uuid,
+    ) -> Result<CartResponse, AppError> {
+        let db = &self.state.db;
+
+        let rows = cart_items::Entity::find()
+            .filter(cart_items::Column::UserId.eq(user_id))
+            .find_also_related(listings::Entity)
+            .order_by_asc(cart_items::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch cart: {}", e)))?;
+
+        let mut items = Vec::new();
+        let mut total_amount = 0;
+
+        for (item, listing) in rows {
+            let listing = match listing {
+                Some(listing) => listing,
+                None => continue,
+            };
+
+            let line_total = listing.price * item.quantity;
+            total_amount += line_total;
+
+            items.push(CartItemResponse {
+                available: listing.is_active()
+                    && listing.deleted_at.is_none()
+                    && listing.available_quantity() >= item.quantity,
+                item,
+                listing,
+                line_total,
+            });
+        }
+
+        Ok(CartResponse {
+            items,
+            total_amount,
+            currency: DEFAULT_CURRENCY.to_string(),
+        })
+    }
+
+    pub async fn add_item(
+        &self,
+        user_id: Uuid,
+        request: AddCartItemRequest,
+    ) -> Result<CartResponse, AppError> {
+        if request.quantity <= 0 {
+            return Err(AppError::field("quantity", "Quantity must be greater than 0"));
+        }
+
+        let db = &self.state.db;
+
+        let listing = listings::Entity::find_by_id(request.listing_id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        if !listing.is_active() || listing.deleted_at.is_some() {
+            return Err(AppError::Conflict("Listing is no longer available".to_string()));
+        }
+
+        if listing.seller_id == user_id {
+            return Err(AppError::validation("You cannot add your own listing to your cart"));
+        }
+
+        let existing = cart_items::Entity::find()
+            .filter(cart_items::Column::UserId.eq(user_id))
+            .filter(cart_items::Column::ListingId.eq(request.listing_id))
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch cart item: {}", e)))?;
+
+        let quantity = existing.as_ref().map(|item| item.quantity).unwrap_or(0) + request.quantity;
+
+        if quantity > listing.available_quantity() {
+            return Err(AppError::Conflict(format!("Only {} item(s) available for this listing", listing.available_quantity())));
+        }
+
+        match existing {
+            Some(item) => {
+                let mut item: cart_items::ActiveModel = item.into();
+                item.quantity = Set(quantity);
+                item.updated_at = Set(chrono::Utc::now());
+
+                item.update(db)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to update cart item: {}", e)))?;
+            }
+            None => {
+                let item = cart_items::ActiveModel {
+                    user_id: Set(user_id),
+                    listing_id: Set(request.listing_id),
+                    quantity: Set(quantity),
+                    ..cart_items::ActiveModel::new()
+                };
+
+                item.insert(db)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to add cart item: {}", e)))?;
+            }
+        }
+
+        self.get_cart(user_id).await
+    }
+
+    pub async fn remove_item(
+        &self,
+        user_id: Uuid,
+        listing_id: Uuid,
+        quantity: Option<i64>,
+    ) -> Result<CartResponse, AppError> {
+        let db = &self.state.db;
+
+        let item = cart_items::Entity::find()
+            .filter(cart_items::Column::UserId.eq(user_id))
+            .filter(cart_items::Column::ListingId.eq(listing_id))
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch cart item: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing is not in your cart".to_string()))?;
+
+        let remaining = match quantity {
+            Some(quantity) if quantity <= 0 => {
+                return Err(AppError::field("quantity", "Quantity must be greater than 0"));
+            }
+            Some(quantity) => item.quantity - quantity,
+            None => 0,
+        };
+
+        if remaining > 0 {
+            let mut item: cart_items::ActiveModel = item.into();
+            item.quantity = Set(remaining);
+            item.updated_at = Set(chrono::Utc::now());
+
+            item.update(db)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to update cart item: {}", e)))?;
+        } else {
+            cart_items::Entity::delete_by_id(item.id)
+                .exec(db)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to remove cart item: {}", e)))?;
+        }
+
+        self.get_cart(user_id).await
+    }
+
+    pub async fn clear_cart(
+        &self,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        Self::delete_cart_items(&self.state.db, user_id).await
+    }
+
+    pub async fn get_cart_items<C: ConnectionTrait>(
+        conn: &C,
+        user_id: Uuid,
+    ) -> Result<Vec<cart_items::Model>, AppError> {
+        cart_items::Entity::find()
+            .filter(cart_items::Column::UserId.eq(user_id))
+            .order_by_asc(cart_items::Column::CreatedAt)
+            .all(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch cart: {}", e)))
+    }
+
+    pub async fn delete_cart_items<C: ConnectionTrait>(
+        conn: &C,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        cart_items::Entity::delete_many()
+            .filter(cart_items::Column::UserId.eq(user_id))
+            .exec(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to clear cart: {}", e)))?;
+
+        Ok(())
+    }
+}
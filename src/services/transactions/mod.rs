@@ -0,0 +1,4 @@
+pub mod cart_service;
+pub mod order_service;
+pub mod payout_service;
+pub mod reservation_service;
\ No newline at end of file
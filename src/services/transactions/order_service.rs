@@ -0,0 +1,866 @@
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::{order_items, orders, products, users};
+use crate::entities::orders::OrderStatus;
+use crate::errors::{AppError, UpstreamService};
+use crate::handlers::transactions::order_handler::{CheckoutResponse, OrderResponse};
+use crate::services::admin::audit_service::{AuditEvent, AuditService};
+use crate::services::integrations::stripe_service::{StripePaymentIntent, StripeService};
+use crate::services::jobs::job_service::{Job, JobService};
+use crate::services::transactions::cart_service::CartService;
+use crate::services::transactions::reservation_service::ReservationService;
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
+
+pub const DEFAULT_CURRENCY: &str = "eur";
+const STRIPE_METADATA_VALUE_LIMIT: usize = 500;
+
+/// What became of a payment Stripe reported as succeeded.
+pub enum PaymentOutcome {
+    Paid(orders::Model),
+    /// The order was cancelled before the payment landed, so the money has to go back.
+    OrderClosed(orders::Model),
+}
+
+pub struct OrderService {
+    pub state: AppState,
+}
+
+impl OrderService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub async fn checkout(
+        &self,
+        buyer_id: Uuid,
+        client: &ClientInfo,
+    ) -> Result<CheckoutResponse, AppError> {
+        let (OrderResponse { order, items }, hold_expires_at) = self.create_pending_order(buyer_id, client).await?;
+
+        let payment_intent = match self.create_payment_intent(&order, &items).await {
+            Ok(payment_intent) => payment_intent,
+            Err(e) => {
+                self.cancel_order(buyer_id, client, order.id).await?;
+                return Err(e);
+            }
+        };
+
+        let order_id = order.id;
+
+        // Stored before anything else can fail, so a webhook for this intent can always
+        // be matched to the order.
+        let mut order: orders::ActiveModel = order.into();
+        order.stripe_payment_intent_id = Set(Some(payment_intent.id.clone()));
+        order.updated_at = Set(chrono::Utc::now());
+
+        let order = match order.update(&self.state.db).await {
+            Ok(order) => order,
+            Err(e) => {
+                self.abandon_checkout(buyer_id, client, order_id, &payment_intent.id).await;
+                return Err(AppError::Internal(format!("Failed to update order: {}", e)));
+            }
+        };
+
+        let client_secret = match payment_intent.client_secret.clone() {
+            Some(client_secret) => client_secret,
+            None => {
+                // The intent is on the order now, so cancelling the order cancels it too.
+                self.cancel_order(buyer_id, client, order.id).await?;
+                return Err(AppError::Upstream(UpstreamService::Stripe, "Stripe did not return a client secret".to_string()));
+            }
+        };
+
+        Ok(CheckoutResponse {
+            order,
+            items,
+            client_secret,
+            hold_expires_at,
+        })
+    }
+
+    pub async fn get_payment_client_secret(
+        &self,
+        buyer_id: Uuid,
+        order_id: Uuid,
+    ) -> Result<String, AppError> {
+        let order = orders::Entity::find_by_id(order_id)
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch order: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+        if order.buyer_id != buyer_id {
+            return Err(AppError::NotFound("Order not found".to_string()));
+        }
+
+        if order.status != OrderStatus::Pending {
+            return Err(AppError::Conflict("Order is not awaiting payment".to_string()));
+        }
+
+        let payment_intent_id = order.stripe_payment_intent_id
+            .ok_or_else(|| AppError::Conflict("Order has no payment attached".to_string()))?;
+
+        let stripe_service = StripeService::new(self.state.clone());
+
+        stripe_service.get_payment_intent(&payment_intent_id)
+            .await?
+            .client_secret
+            .ok_or_else(|| AppError::Upstream(UpstreamService::Stripe, "Stripe did not return a client secret".to_string()))
+    }
+
+    async fn create_pending_order(
+        &self,
+        buyer_id: Uuid,
+        client: &ClientInfo,
+    ) -> Result<(OrderResponse, DateTime<Utc>), AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let cart_items = CartService::get_cart_items(&txn, buyer_id).await?;
+
+        if cart_items.is_empty() {
+            return Err(AppError::validation("Cart is empty"));
+        }
+
+        let order = orders::ActiveModel {
+            buyer_id: Set(buyer_id),
+            total_amount: Set(0),
+            currency: Set(DEFAULT_CURRENCY.to_string()),
+            ..orders::ActiveModel::new()
+        }
+            .insert(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create order: {}", e)))?;
+
+        let mut total_amount = 0;
+        let mut hold_expires_at = None;
+        let mut created_items = Vec::new();
+
+        for cart_item in cart_items {
+            let (listing, hold) = ReservationService::reserve(
+                &txn,
+                order.id,
+                buyer_id,
+                cart_item.listing_id,
+                cart_item.quantity,
+            ).await?;
+
+            if listing.seller_id == buyer_id {
+                return Err(AppError::validation("You cannot buy your own listing"));
+            }
+
+            let seller = users::Entity::find_by_id(listing.seller_id)
+                .one(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch seller: {}", e)))?
+                .ok_or_else(|| AppError::NotFound("Seller not found".to_string()))?;
+
+            if !seller.verified_seller || seller.stripe_account_id.is_none() {
+                return Err(AppError::Conflict(format!(
+                    "Seller {} cannot accept payments yet",
+                    seller.username.unwrap_or_default()
+                )));
+            }
+
+            let product = products::Entity::find_by_id(listing.product_id)
+                .one(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch product: {}", e)))?
+                .ok_or_else(|| AppError::NotFound("Product not found".to_string()))?;
+
+            let line_total = Self::line_total(listing.price, cart_item.quantity)?;
+            total_amount += line_total;
+            hold_expires_at.get_or_insert(hold.expires_at);
+
+            let item = order_items::ActiveModel {
+                order_id: Set(order.id),
+                listing_id: Set(listing.id),
+                seller_id: Set(listing.seller_id),
+                product_id: Set(product.id),
+                product_name: Set(product.name),
+                condition: Set(listing.condition),
+                unit_price: Set(listing.price),
+                quantity: Set(cart_item.quantity),
+                stripe_product_id: Set(listing.stripe_product_id),
+                application_fee_amount: Set(Self::application_fee(line_total, Config::get().stripe_application_fee_bps)),
+                stripe_transfer_id: Set(None),
+                ..order_items::ActiveModel::new()
+            }
+                .insert(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to create order item: {}", e)))?;
+
+            created_items.push(item);
+        }
+
+        let mut order: orders::ActiveModel = order.into();
+        order.total_amount = Set(total_amount);
+
+        let order = order.update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update order total: {}", e)))?;
+
+        CartService::delete_cart_items(&txn, buyer_id).await?;
+
+        self.audit(&txn, Some(buyer_id), client, "order.created", None, &order).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit checkout: {}", e)))?;
+
+        Ok((
+            OrderResponse {
+                order,
+                items: created_items,
+            },
+            hold_expires_at.unwrap_or_else(|| chrono::Utc::now()),
+        ))
+    }
+
+    pub async fn get_orders(
+        &self,
+        buyer_id: Uuid,
+    ) -> Result<Vec<orders::Model>, AppError> {
+        orders::Entity::find()
+            .filter(orders::Column::BuyerId.eq(buyer_id))
+            .order_by_desc(orders::Column::CreatedAt)
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch orders: {}", e)))
+    }
+
+    pub async fn get_order(
+        &self,
+        user_id: Uuid,
+        order_id: Uuid,
+    ) -> Result<OrderResponse, AppError> {
+        let db = &self.state.db;
+
+        let order = orders::Entity::find_by_id(order_id)
+            .one(db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch order: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Order not found".to_string()))?;
+
+        let items = Self::get_order_items(db, order.id).await?;
+
+        let is_seller = items.iter().any(|item| item.seller_id == user_id);
+
+        if order.buyer_id != user_id && !is_seller {
+            return Err(AppError::NotFound("Order not found".to_string()));
+        }
+
+        Ok(OrderResponse {
+            order,
+            items,
+        })
+    }
+
+    pub async fn cancel_order(
+        &self,
+        buyer_id: Uuid,
+        client: &ClientInfo,
+        order_id: Uuid,
+    ) -> Result<orders::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let order = Self::lock_order(&txn, order_id).await?;
+
+        if order.buyer_id != buyer_id {
+            return Err(AppError::NotFound("Order not found".to_string()));
+        }
+
+        let old_status = order.status.clone();
+        let order = Self::cancel_locked_order(&txn, order).await?;
+
+        self.audit(&txn, Some(buyer_id), client, "order.cancelled", Some(&old_status), &order).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit order cancellation: {}", e)))?;
+
+        self.cancel_payment_intent(&order).await;
+
+        Ok(order)
+    }
+
+    /// Cancels a pending order whose stock holds ran out, so its payment intent can no
+    /// longer be confirmed against stock that has gone back on sale.
+    pub async fn expire_order(
+        &self,
+        order_id: Uuid,
+    ) -> Result<orders::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let order = self.cancel_unpaid_order(&txn, order_id).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit order cancellation: {}", e)))?;
+
+        if order.status == OrderStatus::Cancelled {
+            self.cancel_payment_intent(&order).await;
+        }
+
+        Ok(order)
+    }
+
+    /// Applies a succeeded payment on the caller's transaction, so the Stripe webhook can
+    /// record the event in the same commit. An order that was cancelled before the payment
+    /// landed is left alone and handed back as `PaymentOutcome::OrderClosed`.
+    pub async fn mark_order_paid<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<PaymentOutcome, AppError> {
+        let order = Self::lock_order(conn, order_id).await?;
+
+        match order.status {
+            OrderStatus::Pending => {}
+            OrderStatus::Cancelled => return Ok(PaymentOutcome::OrderClosed(order)),
+            _ => return Ok(PaymentOutcome::Paid(order)),
+        }
+
+        ReservationService::commit_order_holds(conn, order.id).await?;
+
+        let old_status = order.status.clone();
+        let order = Self::transition(order, OrderStatus::Paid)?
+            .update(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update order: {}", e)))?;
+
+        Self::audit_system(conn, "order.paid", &old_status, &order).await?;
+
+        Ok(PaymentOutcome::Paid(order))
+    }
+
+    /// Records how much of an order's charge Stripe has refunded so far. A full refund
+    /// moves the order to Refunded; a partial one can't be matched to particular items,
+    /// so the sellers' transfers are left for someone to settle by hand.
+    pub async fn record_refund<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        order_id: Uuid,
+        amount_refunded: i64,
+        fully_refunded: bool,
+    ) -> Result<orders::Model, AppError> {
+        let order = Self::lock_order(conn, order_id).await?;
+
+        // Stripe doesn't deliver events in order, so an older total never wins.
+        let order = if amount_refunded > order.amount_refunded {
+            let mut order: orders::ActiveModel = order.into();
+            order.amount_refunded = Set(amount_refunded);
+            order.updated_at = Set(chrono::Utc::now());
+
+            order.update(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to record refund: {}", e)))?
+        } else {
+            order
+        };
+
+        if fully_refunded {
+            return self.mark_order_refunded(conn, order.id).await;
+        }
+
+        MessageUtil::error(&format!(
+            "Order {} was partially refunded ({} of {} {}), seller transfers need manual review",
+            order.id, order.amount_refunded, order.total_amount, order.currency
+        ));
+
+        Self::audit_system(conn, "order.partially_refunded", &order.status, &order).await?;
+
+        Ok(order)
+    }
+
+    /// Marks a fully refunded order and queues pulling each seller's share back from
+    /// their connected account, on the caller's transaction.
+    pub async fn mark_order_refunded<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<orders::Model, AppError> {
+        let order = Self::lock_order(conn, order_id).await?;
+
+        // A cancelled order was never paid out; its refund is the one the webhook issues
+        // for a payment that landed too late, so there's nothing to unwind.
+        if !order.status.can_transition_to(&OrderStatus::Refunded) {
+            return Ok(order);
+        }
+
+        JobService::enqueue(conn, Job::ReverseOrderTransfers { order_id: order.id }).await?;
+
+        let old_status = order.status.clone();
+        let order = Self::transition(order, OrderStatus::Refunded)?
+            .update(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update order: {}", e)))?;
+
+        Self::audit_system(conn, "order.refunded", &old_status, &order).await?;
+
+        Ok(order)
+    }
+
+    pub async fn cancel_unpaid_order<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<orders::Model, AppError> {
+        let order = Self::lock_order(conn, order_id).await?;
+
+        if order.status != OrderStatus::Pending {
+            return Ok(order);
+        }
+
+        let old_status = order.status.clone();
+        let order = Self::cancel_locked_order(conn, order).await?;
+
+        Self::audit_system(conn, "order.cancelled", &old_status, &order).await?;
+
+        Ok(order)
+    }
+
+    pub async fn get_order_by_payment_intent(
+        &self,
+        payment_intent_id: &str,
+    ) -> Result<Option<orders::Model>, AppError> {
+        orders::Entity::find()
+            .filter(orders::Column::StripePaymentIntentId.eq(payment_intent_id))
+            .one(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch order: {}", e)))
+    }
+
+    /// Marks the seller's own items in a paid order as shipped. An order with several
+    /// sellers only moves to Shipped once the last of them has shipped.
+    pub async fn mark_order_shipped(
+        &self,
+        seller_id: Uuid,
+        client: &ClientInfo,
+        order_id: Uuid,
+    ) -> Result<OrderResponse, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let order = Self::lock_order(&txn, order_id).await?;
+        let items = Self::get_order_items(&txn, order.id).await?;
+
+        if !items.iter().any(|item| item.seller_id == seller_id) {
+            return Err(AppError::NotFound("Order not found".to_string()));
+        }
+
+        if order.status != OrderStatus::Paid {
+            return Err(AppError::Conflict(format!("Cannot ship an order that is {:?}", order.status)));
+        }
+
+        if items.iter().all(|item| item.seller_id != seller_id || item.shipped_at.is_some()) {
+            return Err(AppError::Conflict("Your items in this order have already been shipped".to_string()));
+        }
+
+        order_items::Entity::update_many()
+            .col_expr(order_items::Column::ShippedAt, Expr::value(chrono::Utc::now()))
+            .filter(order_items::Column::OrderId.eq(order.id))
+            .filter(order_items::Column::SellerId.eq(seller_id))
+            .filter(order_items::Column::ShippedAt.is_null())
+            .exec(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update order items: {}", e)))?;
+
+        let fully_shipped = items.iter()
+            .all(|item| item.seller_id == seller_id || item.shipped_at.is_some());
+
+        let old_status = order.status.clone();
+        let order = if fully_shipped {
+            Self::transition(order, OrderStatus::Shipped)?
+                .update(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to update order: {}", e)))?
+        } else {
+            order
+        };
+
+        let items = Self::get_order_items(&txn, order.id).await?;
+
+        let action = if fully_shipped { "order.shipped" } else { "order.partially_shipped" };
+        self.audit(&txn, Some(seller_id), client, action, Some(&old_status), &order).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit order shipment: {}", e)))?;
+
+        Ok(OrderResponse {
+            order,
+            items,
+        })
+    }
+
+    pub async fn mark_order_delivered(
+        &self,
+        buyer_id: Uuid,
+        client: &ClientInfo,
+        order_id: Uuid,
+    ) -> Result<orders::Model, AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let order = Self::lock_order(&txn, order_id).await?;
+
+        if order.buyer_id != buyer_id {
+            return Err(AppError::NotFound("Order not found".to_string()));
+        }
+
+        let old_status = order.status.clone();
+        let order = Self::transition(order, OrderStatus::Delivered)?
+            .update(&txn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update order: {}", e)))?;
+
+        self.audit(&txn, Some(buyer_id), client, "order.delivered", Some(&old_status), &order).await?;
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit order delivery: {}", e)))?;
+
+        Ok(order)
+    }
+
+    pub async fn get_order_items<C: ConnectionTrait>(
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<Vec<order_items::Model>, AppError> {
+        order_items::Entity::find()
+            .filter(order_items::Column::OrderId.eq(order_id))
+            .all(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch order items: {}", e)))
+    }
+
+    pub(crate) async fn lock_order<C: ConnectionTrait>(
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<orders::Model, AppError> {
+        orders::Entity::find_by_id(order_id)
+            .lock_exclusive()
+            .one(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch order: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Order not found".to_string()))
+    }
+
+    async fn cancel_locked_order<C: ConnectionTrait>(
+        conn: &C,
+        order: orders::Model,
+    ) -> Result<orders::Model, AppError> {
+        ReservationService::release_order_holds(conn, order.id).await?;
+
+        let order = Self::transition(order, OrderStatus::Cancelled)?;
+
+        order.update(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to update order: {}", e)))
+    }
+
+    /// Undoes a checkout that failed after its PaymentIntent was created. The intent is
+    /// cancelled so it can't be confirmed, and the order so its stock goes back on sale.
+    /// If cancelling the order fails too, the hold sweeper expires it later.
+    async fn abandon_checkout(
+        &self,
+        buyer_id: Uuid,
+        client: &ClientInfo,
+        order_id: Uuid,
+        payment_intent_id: &str,
+    ) {
+        let stripe_service = StripeService::new(self.state.clone());
+
+        if let Err(e) = stripe_service.cancel_payment_intent(payment_intent_id).await {
+            MessageUtil::error(&format!("Failed to cancel payment intent {}: {}", payment_intent_id, e));
+        }
+
+        if let Err(e) = self.cancel_order(buyer_id, client, order_id).await {
+            MessageUtil::error(&format!("Failed to cancel order {} after checkout failed: {}", order_id, e));
+        }
+    }
+
+    /// Sends back a payment that landed after its order was cancelled. Runs as a job, and
+    /// the refund's idempotency key makes a repeated run return the same refund.
+    pub async fn refund_late_payment(
+        &self,
+        order_id: Uuid,
+        payment_intent_id: &str,
+    ) -> Result<(), AppError> {
+        let refund = StripeService::new(self.state.clone())
+            .create_refund(payment_intent_id, &format!("refund-{}", payment_intent_id))
+            .await?;
+
+        MessageUtil::info(&format!(
+            "Refunded payment intent {} for cancelled order {}",
+            payment_intent_id, order_id
+        ));
+
+        AuditService::new(self.state.clone())
+            .record(AuditEvent {
+                actor_id: None,
+                client: &ClientInfo::default(),
+                action: "order.late_payment_refunded",
+                target_type: "order",
+                target_id: Some(order_id.to_string()),
+                old_values: None,
+                new_values: None,
+                metadata: Some(serde_json::json!({
+                    "payment_intent_id": payment_intent_id,
+                    "refund_id": refund.id,
+                })),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    async fn cancel_payment_intent(
+        &self,
+        order: &orders::Model,
+    ) {
+        if let Some(payment_intent_id) = &order.stripe_payment_intent_id {
+            let stripe_service = StripeService::new(self.state.clone());
+
+            if let Err(e) = stripe_service.cancel_payment_intent(payment_intent_id).await {
+                MessageUtil::error(&format!("Failed to cancel payment intent {}: {}", payment_intent_id, e));
+            }
+        }
+    }
+
+    async fn create_payment_intent(
+        &self,
+        order: &orders::Model,
+        items: &[order_items::Model],
+    ) -> Result<StripePaymentIntent, AppError> {
+        let stripe_service = StripeService::new(self.state.clone());
+
+        let metadata = vec![
+            ("order_id".to_string(), order.id.to_string()),
+            ("buyer_id".to_string(), order.buyer_id.to_string()),
+            ("stripe_products".to_string(), Self::stripe_products(items)),
+        ];
+
+        stripe_service.create_payment_intent(
+            order.total_amount,
+            &order.currency,
+            &order.id.to_string(),
+            &metadata,
+            &format!("order-{}", order.id),
+        ).await
+    }
+
+    /// Records a transition on the transaction that made it, so the change and its audit
+    /// trail commit together.
+    async fn audit<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        actor_id: Option<Uuid>,
+        client: &ClientInfo,
+        action: &str,
+        old_status: Option<&OrderStatus>,
+        order: &orders::Model,
+    ) -> Result<(), AppError> {
+        AuditService::record_on(conn, Self::audit_event(actor_id, client, action, old_status, order)).await?;
+
+        Ok(())
+    }
+
+    /// Like `audit`, for transitions driven by Stripe or the hold sweeper.
+    async fn audit_system<C: ConnectionTrait>(
+        conn: &C,
+        action: &str,
+        old_status: &OrderStatus,
+        order: &orders::Model,
+    ) -> Result<(), AppError> {
+        let client = ClientInfo::default();
+
+        AuditService::record_on(conn, Self::audit_event(None, &client, action, Some(old_status), order)).await?;
+
+        Ok(())
+    }
+
+    fn audit_event<'a>(
+        actor_id: Option<Uuid>,
+        client: &'a ClientInfo,
+        action: &'a str,
+        old_status: Option<&OrderStatus>,
+        order: &orders::Model,
+    ) -> AuditEvent<'a> {
+        AuditEvent {
+            actor_id,
+            client,
+            action,
+            target_type: "order",
+            target_id: Some(order.id.to_string()),
+            old_values: old_status.map(|status| serde_json::json!({ "status": status })),
+            new_values: Some(serde_json::json!({
+                "status": order.status,
+                "total_amount": order.total_amount,
+                "amount_refunded": order.amount_refunded,
+                "currency": order.currency,
+            })),
+            metadata: order.stripe_payment_intent_id.as_ref()
+                .map(|payment_intent_id| serde_json::json!({ "payment_intent_id": payment_intent_id })),
+        }
+    }
+
+    /// A line that isn't for a positive amount would lower the charge while the other
+    /// sellers' transfers and fees are still worked out from their full lines.
+    fn line_total(unit_price: i64, quantity: i64) -> Result<i64, AppError> {
+        if unit_price <= 0 {
+            return Err(AppError::validation("A listing in your cart has no valid price"));
+        }
+
+        if quantity <= 0 {
+            return Err(AppError::validation("Cart quantities must be at least 1"));
+        }
+
+        unit_price.checked_mul(quantity)
+            .ok_or_else(|| AppError::validation("Order total is too large"))
+    }
+
+    /// `product:quantity` pairs for the payment intent metadata, cut to Stripe's value limit.
+    /// Lines without a Stripe product fall back to their listing id.
+    fn stripe_products(items: &[order_items::Model]) -> String {
+        let mut stripe_products = items.iter()
+            .map(|item| match &item.stripe_product_id {
+                Some(stripe_product_id) => format!("{}:{}", stripe_product_id, item.quantity),
+                None => format!("{}:{}", item.listing_id, item.quantity),
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        stripe_products.truncate(STRIPE_METADATA_VALUE_LIMIT);
+
+        stripe_products
+    }
+
+    /// Rounds down, so the odd cent goes to the seller.
+    fn application_fee(amount: i64, fee_bps: i64) -> i64 {
+        amount * fee_bps / 10_000
+    }
+
+    fn transition(
+        order: orders::Model,
+        next: OrderStatus,
+    ) -> Result<orders::ActiveModel, AppError> {
+        if !order.status.can_transition_to(&next) {
+            return Err(AppError::Conflict(format!("Cannot move order from {:?} to {:?}", order.status, next)));
+        }
+
+        let now = chrono::Utc::now();
+        let mut order: orders::ActiveModel = order.into();
+
+        match next {
+            OrderStatus::Paid => order.paid_at = Set(Some(now)),
+            OrderStatus::Shipped => order.shipped_at = Set(Some(now)),
+            OrderStatus::Delivered => order.delivered_at = Set(Some(now)),
+            OrderStatus::Cancelled => order.cancelled_at = Set(Some(now)),
+            _ => {}
+        }
+
+        order.status = Set(next);
+        order.updated_at = Set(now);
+
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::listings::Condition;
+
+    fn item(stripe_product_id: Option<&str>, quantity: i64) -> order_items::Model {
+        order_items::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::nil(),
+            listing_id: Uuid::nil(),
+            seller_id: Uuid::new_v4(),
+            product_id: Uuid::new_v4(),
+            product_name: "Test card".to_string(),
+            condition: Condition::NearMint,
+            unit_price: 1000,
+            quantity,
+            stripe_product_id: stripe_product_id.map(str::to_string),
+            application_fee_amount: 50,
+            stripe_transfer_id: None,
+            shipped_at: None,
+            stripe_transfer_reversal_id: None,
+            application_fee_refunded: 0,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn line_total_multiplies_price_by_quantity() {
+        assert_eq!(OrderService::line_total(250, 4).unwrap(), 1_000);
+    }
+
+    #[test]
+    fn line_total_rejects_lines_that_would_lower_the_charge() {
+        assert!(OrderService::line_total(0, 1).is_err());
+        assert!(OrderService::line_total(-250, 2).is_err());
+        assert!(OrderService::line_total(250, 0).is_err());
+        assert!(OrderService::line_total(250, -1).is_err());
+    }
+
+    #[test]
+    fn line_total_rejects_overflow() {
+        assert!(OrderService::line_total(i64::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn application_fee_takes_basis_points_of_the_line() {
+        assert_eq!(OrderService::application_fee(10_000, 500), 500);
+        assert_eq!(OrderService::application_fee(10_000, 0), 0);
+    }
+
+    #[test]
+    fn application_fee_leaves_the_remainder_to_the_seller() {
+        let line_total = OrderService::line_total(333, 3).unwrap();
+        let fee = OrderService::application_fee(line_total, 250);
+
+        assert_eq!(fee, 24);
+        assert_eq!(line_total - fee, 975);
+    }
+
+    #[test]
+    fn orders_only_move_forward() {
+        assert!(OrderStatus::Pending.can_transition_to(&OrderStatus::Paid));
+        assert!(OrderStatus::Paid.can_transition_to(&OrderStatus::Refunded));
+        assert!(!OrderStatus::Cancelled.can_transition_to(&OrderStatus::Paid));
+        assert!(!OrderStatus::Pending.can_transition_to(&OrderStatus::Shipped));
+        assert!(!OrderStatus::Refunded.can_transition_to(&OrderStatus::Paid));
+    }
+
+    #[test]
+    fn stripe_products_lists_each_line_with_its_quantity() {
+        let items = vec![item(Some("prod_a"), 2), item(None, 1)];
+
+        assert_eq!(
+            OrderService::stripe_products(&items),
+            format!("prod_a:2,{}:1", Uuid::nil())
+        );
+    }
+
+    #[test]
+    fn stripe_products_fits_the_metadata_limit() {
+        let items = vec![item(Some("prod_abcdefghijklmnop"), 1); 100];
+
+        assert_eq!(OrderService::stripe_products(&items).len(), STRIPE_METADATA_VALUE_LIMIT);
+    }
+}
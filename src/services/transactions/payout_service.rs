@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, SimpleExpr};
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::entities::{order_items, users};
+use crate::entities::orders::OrderStatus;
+use crate::errors::AppError;
+use crate::services::integrations::stripe_service::StripeService;
+use crate::services::transactions::order_service::OrderService;
+
+pub struct PayoutService {
+    pub state: AppState,
+}
+
+impl PayoutService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Transfers each seller's share of a paid order, minus the platform fee, to their
+    /// connected account. Runs as a job; items that already carry a transfer ID are
+    /// skipped, and each transfer has a stable idempotency key, so a rolled back attempt
+    /// can safely run again. The order stays locked throughout, so a refund's reversal
+    /// can't run until the transfers it has to undo are on record.
+    pub async fn transfer_order_funds(
+        &self,
+        order_id: Uuid,
+        charge_id: Option<&str>,
+    ) -> Result<(), AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        let order = OrderService::lock_order(&txn, order_id).await?;
+
+        // Refunded before the transfer job got to it; there's nothing left to pay out.
+        if !matches!(order.status, OrderStatus::Paid | OrderStatus::Shipped | OrderStatus::Delivered) {
+            return Ok(());
+        }
+
+        let items = OrderService::get_order_items(&txn, order.id).await?;
+
+        let mut items_by_seller: BTreeMap<Uuid, Vec<order_items::Model>> = BTreeMap::new();
+        for item in items.into_iter().filter(|item| item.stripe_transfer_id.is_none()) {
+            items_by_seller.entry(item.seller_id).or_default().push(item);
+        }
+
+        let stripe_service = StripeService::new(self.state.clone());
+
+        for (seller_id, items) in items_by_seller {
+            let seller = users::Entity::find_by_id(seller_id)
+                .one(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to fetch seller: {}", e)))?
+                .ok_or_else(|| AppError::NotFound("Seller not found".to_string()))?;
+
+            let account_id = seller.stripe_account_id
+                .ok_or_else(|| AppError::Conflict(format!("Seller {} has no connected Stripe account", seller_id)))?;
+
+            let amount: i64 = items.iter()
+                .map(|item| item.line_total() - item.application_fee_amount)
+                .sum();
+
+            if amount <= 0 {
+                continue;
+            }
+
+            let transfer = stripe_service.create_transfer(
+                amount,
+                &order.currency,
+                &account_id,
+                charge_id,
+                &order.id.to_string(),
+                &format!("transfer-{}-{}", order.id, seller_id),
+            ).await?;
+
+            order_items::Entity::update_many()
+                .col_expr(order_items::Column::StripeTransferId, Expr::value(transfer.id))
+                .filter(order_items::Column::Id.is_in(items.iter().map(|item| item.id)))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to record transfer: {}", e)))?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit transfers: {}", e)))
+    }
+
+    /// Claws back each seller's transfer for a refunded order. The platform fee went back
+    /// to the buyer as part of the charge refund, so it's recorded as refunded per item.
+    /// Runs as a job; reversals use stable idempotency keys, so a rolled back attempt can
+    /// safely run again.
+    pub async fn reverse_order_transfers(
+        &self,
+        order_id: Uuid,
+    ) -> Result<(), AppError> {
+        let txn = self.state.db.begin()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to start transaction: {}", e)))?;
+
+        OrderService::lock_order(&txn, order_id).await?;
+
+        let items = OrderService::get_order_items(&txn, order_id).await?;
+
+        let stripe_service = StripeService::new(self.state.clone());
+
+        for reversal in pending_reversals(items) {
+            let reversal_id = match &reversal.transfer_id {
+                Some(transfer_id) => Some(
+                    stripe_service.create_transfer_reversal(
+                        transfer_id,
+                        &format!("reversal-{}-{}", order_id, reversal.seller_id),
+                    ).await?.id
+                ),
+                None => None,
+            };
+
+            order_items::Entity::update_many()
+                .col_expr(order_items::Column::StripeTransferReversalId, Expr::value(reversal_id))
+                .col_expr(
+                    order_items::Column::ApplicationFeeRefunded,
+                    SimpleExpr::from(Expr::col(order_items::Column::ApplicationFeeAmount)),
+                )
+                .filter(order_items::Column::Id.is_in(reversal.item_ids))
+                .exec(&txn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to record transfer reversal: {}", e)))?;
+        }
+
+        txn.commit()
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to commit transfer reversals: {}", e)))
+    }
+}
+
+/// One seller's share of a refunded order that still has to be unwound: the transfer to
+/// reverse, if funds were ever sent, and the items it covered.
+#[derive(Debug, PartialEq)]
+struct PendingReversal {
+    seller_id: Uuid,
+    transfer_id: Option<String>,
+    item_ids: Vec<Uuid>,
+}
+
+fn pending_reversals(items: Vec<order_items::Model>) -> Vec<PendingReversal> {
+    let mut reversals: BTreeMap<(Uuid, Option<String>), Vec<Uuid>> = BTreeMap::new();
+
+    for item in items {
+        let transfer_pending = item.stripe_transfer_id.is_some() && item.stripe_transfer_reversal_id.is_none();
+        let fee_pending = item.application_fee_refunded < item.application_fee_amount;
+
+        if transfer_pending || fee_pending {
+            let transfer_id = item.stripe_transfer_id.filter(|_| transfer_pending);
+            reversals.entry((item.seller_id, transfer_id)).or_default().push(item.id);
+        }
+    }
+
+    reversals.into_iter()
+        .map(|((seller_id, transfer_id), item_ids)| PendingReversal { seller_id, transfer_id, item_ids })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::listings::Condition;
+
+    fn item(seller_id: Uuid, transfer_id: Option<&str>) -> order_items::Model {
+        order_items::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::nil(),
+            listing_id: Uuid::new_v4(),
+            seller_id,
+            product_id: Uuid::new_v4(),
+            product_name: "Test card".to_string(),
+            condition: Condition::NearMint,
+            unit_price: 1000,
+            quantity: 1,
+            stripe_product_id: None,
+            application_fee_amount: 50,
+            stripe_transfer_id: transfer_id.map(str::to_string),
+            shipped_at: None,
+            stripe_transfer_reversal_id: None,
+            application_fee_refunded: 0,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn reverses_one_transfer_per_seller() {
+        let seller_a = Uuid::new_v4();
+        let seller_b = Uuid::new_v4();
+        let a1 = item(seller_a, Some("tr_a"));
+        let a2 = item(seller_a, Some("tr_a"));
+        let b1 = item(seller_b, Some("tr_b"));
+
+        let reversals = pending_reversals(vec![a1.clone(), b1.clone(), a2.clone()]);
+
+        assert_eq!(reversals.len(), 2);
+
+        let for_a = reversals.iter().find(|reversal| reversal.seller_id == seller_a).unwrap();
+        assert_eq!(for_a.transfer_id.as_deref(), Some("tr_a"));
+        assert_eq!(for_a.item_ids, vec![a1.id, a2.id]);
+
+        let for_b = reversals.iter().find(|reversal| reversal.seller_id == seller_b).unwrap();
+        assert_eq!(for_b.transfer_id.as_deref(), Some("tr_b"));
+        assert_eq!(for_b.item_ids, vec![b1.id]);
+    }
+
+    #[test]
+    fn refunds_the_fee_for_items_that_were_never_transferred() {
+        let seller = Uuid::new_v4();
+        let untransferred = item(seller, None);
+
+        let reversals = pending_reversals(vec![untransferred.clone()]);
+
+        assert_eq!(reversals, vec![PendingReversal {
+            seller_id: seller,
+            transfer_id: None,
+            item_ids: vec![untransferred.id],
+        }]);
+    }
+
+    #[test]
+    fn skips_items_that_were_already_reversed() {
+        let seller = Uuid::new_v4();
+        let mut reversed = item(seller, Some("tr_a"));
+        reversed.stripe_transfer_reversal_id = Some("trr_a".to_string());
+        reversed.application_fee_refunded = reversed.application_fee_amount;
+
+        assert!(pending_reversals(vec![reversed]).is_empty());
+    }
+}
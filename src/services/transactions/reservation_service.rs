@@ -0,0 +1,473 @@
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+use sea_orm::sea_query::Expr;
+use uuid::Uuid;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::entities::{listings, stock_holds};
+use crate::entities::listings::ListingStatus;
+use crate::errors::AppError;
+use crate::services::transactions::order_service::OrderService;
+use crate::utils::message_util::MessageUtil;
+
+pub struct ReservationService {
+    pub state: AppState,
+}
+
+impl ReservationService {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    /// Reserves stock with a single conditional UPDATE so concurrent buyers can never
+    /// push `reserved_quantity` past `quantity`, then records an expiring hold for it.
+    pub async fn reserve<C: ConnectionTrait>(
+        conn: &C,
+        order_id: Uuid,
+        user_id: Uuid,
+        listing_id: Uuid,
+        quantity: i64,
+    ) -> Result<(listings::Model, stock_holds::Model), AppError> {
+        if quantity <= 0 {
+            return Err(AppError::field("quantity", "Quantity must be greater than 0"));
+        }
+
+        let result = Self::reserve_statement(listing_id, quantity)
+            .exec(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to reserve listing: {}", e)))?;
+
+        if result.rows_affected == 0 {
+            return Err(AppError::Conflict("Not enough stock available for this listing".to_string()));
+        }
+
+        let listing = listings::Entity::find_by_id(listing_id)
+            .one(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch listing: {}", e)))?
+            .ok_or_else(|| AppError::NotFound("Listing not found".to_string()))?;
+
+        let hold = stock_holds::ActiveModel {
+            listing_id: Set(listing_id),
+            order_id: Set(order_id),
+            user_id: Set(user_id),
+            quantity: Set(quantity),
+            expires_at: Set(Self::hold_expiry()),
+            ..stock_holds::ActiveModel::new()
+        }
+            .insert(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to create stock hold: {}", e)))?;
+
+        Ok((listing, hold))
+    }
+
+    fn reserve_statement(
+        listing_id: Uuid,
+        quantity: i64,
+    ) -> UpdateMany<listings::Entity> {
+        listings::Entity::update_many()
+            .col_expr(
+                listings::Column::ReservedQuantity,
+                Expr::col(listings::Column::ReservedQuantity).add(quantity),
+            )
+            .col_expr(listings::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(listings::Column::Id.eq(listing_id))
+            .filter(listings::Column::Status.eq(ListingStatus::Active))
+            .filter(listings::Column::DeletedAt.is_null())
+            .filter(
+                Expr::expr(Expr::col(listings::Column::Quantity).sub(Expr::col(listings::Column::ReservedQuantity)))
+                    .gte(quantity)
+            )
+    }
+
+    pub async fn release_order_holds<C: ConnectionTrait>(
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<(), AppError> {
+        for hold in Self::get_open_holds(conn, order_id).await? {
+            listings::Entity::update_many()
+                .col_expr(
+                    listings::Column::ReservedQuantity,
+                    Expr::col(listings::Column::ReservedQuantity).sub(hold.quantity),
+                )
+                .col_expr(listings::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+                .filter(listings::Column::Id.eq(hold.listing_id))
+                .filter(listings::Column::ReservedQuantity.gte(hold.quantity))
+                .exec(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to release listing: {}", e)))?;
+
+            let mut hold: stock_holds::ActiveModel = hold.into();
+            hold.released_at = Set(Some(chrono::Utc::now()));
+
+            hold.update(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to release stock hold: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Turns an order's holds into sales: the held units leave both `quantity` and
+    /// `reserved_quantity`, and a listing that runs out moves from Active to Sold.
+    pub async fn commit_order_holds<C: ConnectionTrait>(
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<(), AppError> {
+        for hold in Self::get_open_holds(conn, order_id).await? {
+            let result = listings::Entity::update_many()
+                .col_expr(
+                    listings::Column::Quantity,
+                    Expr::col(listings::Column::Quantity).sub(hold.quantity),
+                )
+                .col_expr(
+                    listings::Column::ReservedQuantity,
+                    Expr::col(listings::Column::ReservedQuantity).sub(hold.quantity),
+                )
+                .col_expr(listings::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+                .filter(listings::Column::Id.eq(hold.listing_id))
+                .filter(listings::Column::Quantity.gte(hold.quantity))
+                .filter(listings::Column::ReservedQuantity.gte(hold.quantity))
+                .exec(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to commit listing sale: {}", e)))?;
+
+            // An open hold is always backed by reserved stock, so a miss means the listing
+            // was changed underneath it and the sale can't be booked.
+            if result.rows_affected == 0 {
+                return Err(AppError::Conflict(format!(
+                    "Listing {} no longer has the {} reserved unit(s) held for order {}",
+                    hold.listing_id, hold.quantity, order_id
+                )));
+            }
+
+            listings::Entity::update_many()
+                .col_expr(listings::Column::Status, Expr::value(ListingStatus::Sold))
+                .filter(listings::Column::Id.eq(hold.listing_id))
+                .filter(listings::Column::Status.eq(ListingStatus::Active))
+                .filter(listings::Column::Quantity.lte(0))
+                .exec(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to mark listing as sold: {}", e)))?;
+
+            let mut hold: stock_holds::ActiveModel = hold.into();
+            hold.committed_at = Set(Some(chrono::Utc::now()));
+
+            hold.update(conn)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to commit stock hold: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Cancels every pending order whose holds have expired, returning their stock.
+    pub async fn release_expired_holds(&self) -> Result<usize, AppError> {
+        let expired_orders: Vec<Uuid> = stock_holds::Entity::find()
+            .select_only()
+            .column(stock_holds::Column::OrderId)
+            .distinct()
+            .filter(stock_holds::Column::ReleasedAt.is_null())
+            .filter(stock_holds::Column::CommittedAt.is_null())
+            .filter(stock_holds::Column::ExpiresAt.lt(chrono::Utc::now()))
+            .into_tuple()
+            .all(&self.state.db)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch expired stock holds: {}", e)))?;
+
+        let order_service = OrderService::new(self.state.clone());
+        let mut expired = 0;
+
+        // One order that can't be expired shouldn't keep every other hold locked up.
+        for order_id in &expired_orders {
+            match order_service.expire_order(*order_id).await {
+                Ok(_) => expired += 1,
+                Err(e) => MessageUtil::error(&format!("Failed to expire order {}: {}", order_id, e)),
+            }
+        }
+
+        Ok(expired)
+    }
+
+    pub fn start_hold_sweeper(state: AppState) {
+        let interval = Duration::from_secs(Config::get().stock_hold_sweep_interval_secs);
+
+        tokio::spawn(async move {
+            let reservation_service = ReservationService::new(state);
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+
+                match reservation_service.release_expired_holds().await {
+                    Ok(0) => {}
+                    Ok(count) => MessageUtil::info(&format!("Released stock holds for {} expired order(s)", count)),
+                    Err(e) => MessageUtil::error(&format!("Failed to release expired stock holds: {}", e)),
+                }
+            }
+        });
+    }
+
+    async fn get_open_holds<C: ConnectionTrait>(
+        conn: &C,
+        order_id: Uuid,
+    ) -> Result<Vec<stock_holds::Model>, AppError> {
+        stock_holds::Entity::find()
+            .filter(stock_holds::Column::OrderId.eq(order_id))
+            .filter(stock_holds::Column::ReleasedAt.is_null())
+            .filter(stock_holds::Column::CommittedAt.is_null())
+            .lock_exclusive()
+            .all(conn)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to fetch stock holds: {}", e)))
+    }
+
+    fn hold_expiry() -> DateTime<Utc> {
+        chrono::Utc::now() + chrono::Duration::minutes(Config::get().stock_hold_minutes)
+    }
+}
+
+/// These run against a real, migrated Postgres because the guarantees live in its row
+/// locks and conditional updates: `cargo test -- --ignored` with the app's environment set.
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::MigratorTrait;
+    use super::*;
+    use crate::database::migrations::Migrator;
+    use crate::entities::{orders, products, users};
+    use crate::entities::listings::Condition;
+    use crate::entities::products::ProductCategory;
+
+    const STOCK: i64 = 5;
+    const BUYERS: usize = 40;
+
+    async fn connect() -> DatabaseConnection {
+        let db = Database::connect(&Config::get().database_url).await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        db
+    }
+
+    async fn seed_user(db: &DatabaseConnection) -> Uuid {
+        let id = Uuid::new_v4();
+
+        users::ActiveModel {
+            id: Set(id),
+            username: Set(Some(format!("reservation-test-{}", id))),
+            password_hash: Set("not-a-real-hash".to_string()),
+            email: Set(format!("reservation-test-{}@example.com", id)),
+            ..users::ActiveModel::new()
+        }
+            .insert(db)
+            .await
+            .unwrap();
+
+        id
+    }
+
+    async fn seed_listing(db: &DatabaseConnection, seller_id: Uuid) -> Uuid {
+        let now = chrono::Utc::now();
+
+        let product = products::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set("Reservation test card".to_string()),
+            description: Set(None),
+            image_url: Set(None),
+            game: Set("Test Game".to_string()),
+            set: Set(None),
+            category: Set(ProductCategory::Card),
+            subcategory: Set(None),
+            metadata: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+            .insert(db)
+            .await
+            .unwrap();
+
+        let listing = listings::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            product_id: Set(product.id),
+            seller_id: Set(seller_id),
+            price: Set(1000),
+            condition: Set(Condition::NearMint),
+            quantity: Set(STOCK),
+            reserved_quantity: Set(0),
+            status: Set(ListingStatus::Active),
+            stripe_product_id: Set(None),
+            previous_stripe_product_id: Set(None),
+            image_url: Set(None),
+            description: Set(None),
+            expires_at: Set(now + chrono::Duration::days(30)),
+            created_at: Set(now),
+            updated_at: Set(now),
+            deleted_at: Set(None),
+        }
+            .insert(db)
+            .await
+            .unwrap();
+
+        listing.id
+    }
+
+    async fn seed_order(db: &DatabaseConnection, buyer_id: Uuid) -> Uuid {
+        orders::ActiveModel {
+            buyer_id: Set(buyer_id),
+            total_amount: Set(1000),
+            currency: Set("eur".to_string()),
+            ..orders::ActiveModel::new()
+        }
+            .insert(db)
+            .await
+            .unwrap()
+            .id
+    }
+
+    /// Runs without a database: the oversell guard is the WHERE clause of one UPDATE.
+    #[test]
+    fn reservation_only_applies_while_unreserved_stock_covers_it() {
+        let sql = ReservationService::reserve_statement(Uuid::nil(), 2)
+            .build(DbBackend::Postgres)
+            .to_string()
+            .replace(['(', ')'], "");
+
+        assert!(sql.contains(r#""reserved_quantity" = "reserved_quantity" + 2"#), "{}", sql);
+        assert!(sql.contains(r#""quantity" - "reserved_quantity" >= 2"#), "{}", sql);
+        assert!(sql.contains(r#""deleted_at" IS NULL"#), "{}", sql);
+        assert!(sql.contains("'active'"), "{}", sql);
+    }
+
+    async fn fetch_listing(db: &DatabaseConnection, listing_id: Uuid) -> listings::Model {
+        listings::Entity::find_by_id(listing_id).one(db).await.unwrap().unwrap()
+    }
+
+    /// Every buyer tries to take one unit at once, each in its own transaction.
+    async fn race_for_stock(db: &DatabaseConnection, buyer_id: Uuid, listing_id: Uuid) -> Vec<Uuid> {
+        let mut tasks = Vec::new();
+
+        for _ in 0..BUYERS {
+            let db = db.clone();
+
+            tasks.push(tokio::spawn(async move {
+                let order_id = seed_order(&db, buyer_id).await;
+                let txn = db.begin().await.unwrap();
+
+                match ReservationService::reserve(&txn, order_id, buyer_id, listing_id, 1).await {
+                    Ok(_) => {
+                        txn.commit().await.unwrap();
+                        Some(order_id)
+                    }
+                    Err(_) => {
+                        txn.rollback().await.unwrap();
+                        None
+                    }
+                }
+            }));
+        }
+
+        let mut winners = Vec::new();
+
+        for task in tasks {
+            if let Some(order_id) = task.await.unwrap() {
+                winners.push(order_id);
+            }
+        }
+
+        winners
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    async fn concurrent_reservations_never_oversell() {
+        let db = connect().await;
+        let seller_id = seed_user(&db).await;
+        let buyer_id = seed_user(&db).await;
+        let listing_id = seed_listing(&db, seller_id).await;
+
+        let winners = race_for_stock(&db, buyer_id, listing_id).await;
+
+        assert_eq!(winners.len() as i64, STOCK);
+
+        let listing = fetch_listing(&db, listing_id).await;
+        assert_eq!(listing.quantity, STOCK);
+        assert_eq!(listing.reserved_quantity, STOCK);
+
+        let holds = stock_holds::Entity::find()
+            .filter(stock_holds::Column::ListingId.eq(listing_id))
+            .count(&db)
+            .await
+            .unwrap();
+        assert_eq!(holds as i64, STOCK);
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    async fn concurrent_commits_and_releases_keep_stock_consistent() {
+        let db = connect().await;
+        let seller_id = seed_user(&db).await;
+        let buyer_id = seed_user(&db).await;
+        let listing_id = seed_listing(&db, seller_id).await;
+
+        let winners = race_for_stock(&db, buyer_id, listing_id).await;
+        let (to_commit, to_release) = winners.split_at(winners.len() / 2 + 1);
+
+        let mut tasks = Vec::new();
+
+        for (order_id, commit) in to_commit.iter().map(|id| (*id, true))
+            .chain(to_release.iter().map(|id| (*id, false)))
+        {
+            let db = db.clone();
+
+            tasks.push(tokio::spawn(async move {
+                let txn = db.begin().await.unwrap();
+
+                if commit {
+                    ReservationService::commit_order_holds(&txn, order_id).await.unwrap();
+                } else {
+                    ReservationService::release_order_holds(&txn, order_id).await.unwrap();
+                }
+
+                txn.commit().await.unwrap();
+            }));
+        }
+
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let listing = fetch_listing(&db, listing_id).await;
+        assert_eq!(listing.quantity, STOCK - to_commit.len() as i64);
+        assert_eq!(listing.reserved_quantity, 0);
+        assert_eq!(listing.status, ListingStatus::Active);
+
+        // The released units are back on sale and can be raced for again.
+        let winners = race_for_stock(&db, buyer_id, listing_id).await;
+        assert_eq!(winners.len(), to_release.len());
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    async fn committing_a_hold_without_reserved_stock_fails() {
+        let db = connect().await;
+        let seller_id = seed_user(&db).await;
+        let buyer_id = seed_user(&db).await;
+        let listing_id = seed_listing(&db, seller_id).await;
+        let order_id = seed_order(&db, buyer_id).await;
+
+        ReservationService::reserve(&db, order_id, buyer_id, listing_id, 2).await.unwrap();
+
+        listings::Entity::update_many()
+            .col_expr(listings::Column::ReservedQuantity, Expr::value(0i64))
+            .filter(listings::Column::Id.eq(listing_id))
+            .exec(&db)
+            .await
+            .unwrap();
+
+        let txn = db.begin().await.unwrap();
+        assert!(ReservationService::commit_order_holds(&txn, order_id).await.is_err());
+        txn.rollback().await.unwrap();
+
+        assert_eq!(fetch_listing(&db, listing_id).await.quantity, STOCK);
+    }
+}
@@ -1,4 +1,18 @@
+use sea_orm::Database;
+use sea_orm_migration::MigratorTrait;
+use crate::app_state::AppState;
+use crate::config::config::Config;
+use crate::database::migrations::Migrator;
+use crate::database::seed::Seeder;
+use crate::services::marketplace::catalogue_import_service::{CatalogueImportService, ImportFormat};
+use crate::utils::message_util::MessageUtil;
+use crate::utils::request_util::ClientInfo;
 
+const USAGE: &str = "Usage:
+  tcgemporium import-catalogue <file> [--format csv|json] [--dry-run] [--report <file>]
+  tcgemporium migrate up [<steps>] [--seed]
+  tcgemporium migrate down [<steps>]
+  tcgemporium migrate status";
 
 pub struct CliUtil;
 
@@ -14,4 +28,130 @@ impl CliUtil {
            ╚═╝    ╚═════╝ ╚═════╝     ╚══════╝╚═╝     ╚═╝╚═╝      ╚═════╝ ╚═╝  ╚═╝╚═╝ ╚═════╝ ╚═╝     ╚═╝
         "#);
     }
-}
\ No newline at end of file
+
+    /// Runs a one-off command given on the command line instead of starting the server.
+    pub async fn run_command(state: AppState, args: &[String]) -> Result<(), String> {
+        match args.first().map(String::as_str) {
+            Some("import-catalogue") => Self::import_catalogue(state, &args[1..]).await,
+            Some(command) => Err(format!("Unknown command '{}'\n{}", command, USAGE)),
+            None => Err(USAGE.to_string()),
+        }
+    }
+
+    /// Only needs the database, so it runs before the rest of the application state is
+    /// built; a fresh environment has no schema for the startup checks to work against.
+    pub async fn migrate(args: &[String]) -> Result<(), String> {
+        let mut steps = None;
+        let mut seed = false;
+
+        for arg in args.iter().skip(1) {
+            match arg.as_str() {
+                "--seed" => seed = true,
+                _ if steps.is_none() => steps = Some(arg.parse::<u32>()
+                    .map_err(|_| format!("Invalid number of steps '{}'", arg))?),
+                _ => return Err(format!("Unexpected argument '{}'\n{}", arg, USAGE)),
+            }
+        }
+
+        let db = Database::connect(&Config::get().database_url)
+            .await
+            .map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+        match args.first().map(String::as_str) {
+            Some("up") => {
+                Migrator::up(&db, steps)
+                    .await
+                    .map_err(|e| format!("Failed to apply migrations: {}", e))?;
+
+                MessageUtil::success("Migrations applied");
+
+                if seed {
+                    Seeder::run(&db).await?;
+                }
+            }
+            Some("down") if !seed => {
+                Migrator::down(&db, Some(steps.unwrap_or(1)))
+                    .await
+                    .map_err(|e| format!("Failed to roll back migrations: {}", e))?;
+
+                MessageUtil::success("Migrations rolled back");
+            }
+            Some("status") if steps.is_none() && !seed => {
+                let migrations = Migrator::get_migration_with_status(&db)
+                    .await
+                    .map_err(|e| format!("Failed to read migration status: {}", e))?;
+
+                for migration in migrations {
+                    MessageUtil::info(&format!("{:<8} {}", migration.status(), migration.name()));
+                }
+            }
+            _ => return Err(USAGE.to_string()),
+        }
+
+        Ok(())
+    }
+
+    async fn import_catalogue(state: AppState, args: &[String]) -> Result<(), String> {
+        let mut path = None;
+        let mut format = None;
+        let mut dry_run = false;
+        let mut report_path = None;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dry-run" => dry_run = true,
+                "--format" => format = args.next().map(|format| ImportFormat::from_name(format)
+                    .ok_or_else(|| format!("Unknown format '{}'", format))).transpose()?,
+                "--report" => report_path = args.next().cloned(),
+                _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.clone()),
+                _ => return Err(format!("Unexpected argument '{}'\n{}", arg, USAGE)),
+            }
+        }
+
+        let path = path.ok_or_else(|| USAGE.to_string())?;
+        let format = format
+            .or_else(|| ImportFormat::from_path(&path))
+            .ok_or_else(|| "Could not tell the file format, pass --format csv or --format json".to_string())?;
+
+        let data = std::fs::read(&path)
+            .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        let rows = CatalogueImportService::parse(&data, format).map_err(|e| e.to_string())?;
+
+        MessageUtil::info(&format!("Importing {} rows from {}{}", rows.len(), path, if dry_run { " (dry run)" } else { "" }));
+
+        let report = CatalogueImportService::new(state)
+            .import(None, &ClientInfo::default(), rows, dry_run)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        for row in report.rows.iter().filter(|row| row.error.is_some()) {
+            MessageUtil::error(&format!(
+                "Row {} ({}): {}",
+                row.row,
+                row.set_number.as_deref().unwrap_or("-"),
+                row.error.as_deref().unwrap_or_default()
+            ));
+        }
+
+        if let Some(report_path) = report_path {
+            let json = serde_json::to_vec_pretty(&report)
+                .map_err(|e| format!("Failed to serialize report: {}", e))?;
+
+            std::fs::write(&report_path, json)
+                .map_err(|e| format!("Failed to write report to '{}': {}", report_path, e))?;
+        }
+
+        let summary = format!(
+            "{} rows processed: {} created, {} updated, {} unchanged, {} failed",
+            report.total_rows, report.created, report.updated, report.unchanged, report.failed
+        );
+
+        if report.failed > 0 {
+            return Err(summary);
+        }
+
+        MessageUtil::success(&summary);
+        Ok(())
+    }
+}
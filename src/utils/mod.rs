@@ -1,3 +1,4 @@
 pub mod message_util;
 pub mod validator_util;
-pub mod cli_util;
\ No newline at end of file
+pub mod cli_util;
+pub mod request_util;
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::net::IpAddr;
+use actix_web::{Error, FromRequest, HttpRequest};
+use actix_web::dev::Payload;
+use actix_web::http::header;
+use actix_web::HttpMessage;
+use futures_util::future::{ready, Ready};
+use uuid::Uuid;
+use crate::config::config::Config;
+
+/// Set by the frontend to a stable per-device identifier.
+const DEVICE_FINGERPRINT_HEADER: &str = "X-Device-Fingerprint";
+
+const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+
+/// The id the logging wrapper in `main.rs` gives every request, kept in the request
+/// extensions so log lines and audit events can be matched up.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestId(pub Uuid);
+
+/// Client details recorded against sessions, logins and audit events.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub device_fingerprint: Option<String>,
+    pub request_id: Option<Uuid>,
+}
+
+impl ClientInfo {
+    pub fn from_http_request(req: &HttpRequest) -> Self {
+        let ip_address = client_ip(req).map(|ip| ip.to_string());
+
+        let user_agent = req.headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let device_fingerprint = req.headers()
+            .get(DEVICE_FINGERPRINT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.to_string());
+
+        let request_id = req.extensions()
+            .get::<RequestId>()
+            .map(|request_id| request_id.0);
+
+        Self {
+            ip_address,
+            user_agent,
+            device_fingerprint,
+            request_id,
+        }
+    }
+}
+
+/// The address a request came from. `X-Forwarded-For` is written by the client as much
+/// as by any proxy, so it's only consulted when the connection itself comes from one of
+/// `TRUSTED_PROXIES`, and then only up to the first hop that isn't a trusted proxy.
+pub fn client_ip(req: &HttpRequest) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+
+    let forwarded_for = req.headers()
+        .get(FORWARDED_FOR_HEADER)
+        .and_then(|value| value.to_str().ok());
+
+    Some(resolve_client_ip(peer, forwarded_for, &Config::get().trusted_proxies))
+}
+
+fn resolve_client_ip(
+    peer: IpAddr,
+    forwarded_for: Option<&str>,
+    trusted_proxies: &[IpAddr],
+) -> IpAddr {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+
+    let mut client = peer;
+
+    // Walk back from the hop nearest to us; everything left of an untrusted hop is hearsay.
+    for hop in forwarded_for.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+
+                if !trusted_proxies.contains(&ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    client
+}
+
+impl FromRequest for ClientInfo {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(ClientInfo::from_http_request(req)))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn ignores_forwarded_headers_from_untrusted_peers() {
+        let trusted = [ip("10.0.0.1")];
+
+        assert_eq!(resolve_client_ip(ip("203.0.113.9"), Some("198.51.100.1"), &trusted), ip("203.0.113.9"));
+        assert_eq!(resolve_client_ip(ip("203.0.113.9"), Some("198.51.100.1"), &[]), ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn takes_the_client_from_a_trusted_proxy() {
+        let trusted = [ip("10.0.0.1")];
+
+        assert_eq!(resolve_client_ip(ip("10.0.0.1"), Some("198.51.100.1"), &trusted), ip("198.51.100.1"));
+        assert_eq!(resolve_client_ip(ip("10.0.0.1"), None, &trusted), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn stops_at_the_first_untrusted_hop() {
+        let trusted = [ip("10.0.0.1"), ip("10.0.0.2")];
+
+        // The client prepended a spoofed address; the edge proxy appended the real one.
+        assert_eq!(
+            resolve_client_ip(ip("10.0.0.1"), Some("1.2.3.4, 198.51.100.1, 10.0.0.2"), &trusted),
+            ip("198.51.100.1"),
+        );
+    }
+
+    #[test]
+    fn stops_at_garbage_in_the_chain() {
+        let trusted = [ip("10.0.0.1")];
+
+        assert_eq!(resolve_client_ip(ip("10.0.0.1"), Some("198.51.100.1, unknown"), &trusted), ip("10.0.0.1"));
+    }
+}
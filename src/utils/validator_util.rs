@@ -26,6 +26,10 @@ pub enum ValidationError {
     UsernameTooShort,
     #[error("Username is too long (max 16 characters)")]
     UsernameTooLong,
+    #[error("Phone number is required")]
+    PhoneNumberRequired,
+    #[error("Phone number must be in international format, e.g. +14155550123")]
+    InvalidPhoneNumber,
 }
 
 pub type ValidationResult<T> = Result<T, ValidationError>;
@@ -47,6 +51,10 @@ static PASSWORD_VALID_CHARS_REGEX: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^[A-Za-z\d@$!%*?&]+$").expect("Invalid password chars regex")
 });
 
+static PHONE_NUMBER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\+[1-9]\d{7,14}$").expect("Invalid phone number regex")
+});
+
 pub struct ValidatorUtil;
 
 impl ValidatorUtil {
@@ -106,4 +114,16 @@ impl ValidatorUtil {
         
         Ok(())
     }
+
+    pub fn validate_phone_number(phone_number: &str) -> ValidationResult<()> {
+        if phone_number.is_empty() {
+            return Err(ValidationError::PhoneNumberRequired);
+        }
+
+        if !PHONE_NUMBER_REGEX.is_match(phone_number) {
+            return Err(ValidationError::InvalidPhoneNumber);
+        }
+
+        Ok(())
+    }
 }
\ No newline at end of file